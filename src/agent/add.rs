@@ -0,0 +1,174 @@
+/*
+ * ##############################################
+ * File responsible for the program which manages
+ * the agent keys, sssh-add:
+ *
+ * sssh-add [options] [file...]
+ *
+ * Adds each private key file to the agent of
+ * SSSH_AUTH_SOCK, or ~/.sssh/id_ed25519 and
+ * ~/.sssh/id_rsa without files.
+ *
+ * -d - Removes the keys of the files instead
+ * -D - Removes every key of the agent
+ * -l - Lists the agent keys, by their fingerprint
+ * -L - Lists the agent public keys PEM, as the
+ *   authorized_keys has them
+ *
+ * A failed file is shown and the next one is
+ * used, the exit code tells if any failed.
+ * ##############################################
+ */
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rsa::pkcs8::der::zeroize::Zeroizing;
+
+use crate::agent::client::AgentClient;
+use crate::cli;
+use crate::crypto;
+use crate::crypto::key_type::KeyType;
+use crate::error::Result;
+use crate::file_sys;
+
+pub const USAGE: &str = "Usage: sssh-add [-dDhlLv] [file...]";
+
+const MAX_VERBOSITY: u8 = 3;
+const UNKNOWN_KEY_TYPE: &str = "unknown";
+
+//The command line of sssh-add
+pub struct AddCli {
+    pub files: Vec<PathBuf>,
+    pub remove: bool,
+    pub remove_all: bool,
+    pub list: bool,
+    pub list_public: bool,
+    pub verbosity: u8,
+    pub help: bool,
+}
+
+impl AddCli {
+
+    //Parses the arguments, without the program name, returning an usage error message
+    pub fn parse(args: &[String]) -> std::result::Result<Self, String> {
+
+        let mut cli = Self { files: Vec::new(), remove: false, remove_all: false, list: false, list_public: false, verbosity: 0, help: false };
+
+        for arg in args {
+
+            if !arg.starts_with('-') || arg.len() == 1 {
+                cli.files.push(PathBuf::from(arg));
+                continue;
+            }
+
+            for flag in arg[1..].chars() {
+                match flag {
+                    'd' => cli.remove = true,
+                    'D' => cli.remove_all = true,
+                    'l' => cli.list = true,
+                    'L' => cli.list_public = true,
+                    'h' => cli.help = true,
+                    'v' => cli.verbosity = (cli.verbosity + 1).min(MAX_VERBOSITY),
+                    _ => return Err(format!("Unknown option -{}", flag)),
+                }
+            }
+        }
+
+        if [cli.remove, cli.remove_all, cli.list, cli.list_public].iter().filter(|option| **option).count() > 1 {
+            return Err("Only one of -d, -D, -l and -L may be used".to_string());
+        }
+
+        Ok(cli)
+    }
+}
+
+/*
+ * Runs the operation of the command line on the agent, showing
+ * each failed file, returning if all of them were used
+ */
+pub fn run(client: &mut AgentClient, cli: &AddCli) -> Result<bool> {
+
+    if cli.list || cli.list_public {
+        list(client, cli.list_public)?;
+        return Ok(true);
+    }
+
+    if cli.remove_all {
+        client.remove_all()?;
+        println!("All identities removed.");
+        return Ok(true);
+    }
+
+    let files = match cli.files.is_empty() {
+        true => file_sys::keys::user_private_key_paths()?,
+        false => cli.files.clone(),
+    };
+
+    let mut used = true;
+
+    for file in &files {
+
+        let result = match cli.remove {
+            true => remove_file_key(client, file),
+            false => add_file_key(client, file),
+        };
+
+        if let Err(e) = result {
+            eprintln!("sssh-add: {}: {}", file.display(), cli::error_message(&e));
+            used = false;
+        }
+    }
+
+    Ok(used)
+}
+
+fn add_file_key(client: &mut AgentClient, file: &Path) -> Result<()> {
+
+    let private_key_pem = Zeroizing::new(fs::read_to_string(file)?);
+
+    client.add(&private_key_pem, &file.display().to_string())?;
+    println!("Identity added: {}", file.display());
+
+    Ok(())
+}
+
+//The key is known by the public key of the private file
+fn remove_file_key(client: &mut AgentClient, file: &Path) -> Result<()> {
+
+    let private_key_pem = Zeroizing::new(fs::read_to_string(file)?);
+    let public_key_pem = crypto::public_key_pem_from_private(&private_key_pem)?;
+
+    client.remove(&public_key_pem)?;
+    println!("Identity removed: {}", file.display());
+
+    Ok(())
+}
+
+fn list(client: &mut AgentClient, public_pem: bool) -> Result<()> {
+
+    let keys = client.list()?;
+
+    if keys.is_empty() {
+        println!("The agent has no identities.");
+        return Ok(());
+    }
+
+    for (index, key) in keys.iter().enumerate() {
+
+        if !public_pem {
+            let key_type = KeyType::of_public_pem(&key.public_key_pem).map(|key_type| key_type.name()).unwrap_or(UNKNOWN_KEY_TYPE);
+            println!("{} {} {}", key_type, crypto::fingerprint(&key.public_key_pem), key.comment);
+            continue;
+        }
+
+        //Separated by a blank line, as on the authorized_keys
+        if index > 0 {
+            println!();
+        }
+
+        println!("{}", key.public_key_pem);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,93 @@
+/*
+ * ##############################################
+ * File responsible for the agent client, which
+ * connects to the agent socket of SSSH_AUTH_SOCK
+ * and sends each request, waiting for his
+ * response, as in protocol.rs.
+ * ##############################################
+ */
+
+use std::env;
+use std::io::{BufReader, BufWriter};
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+
+use crate::agent::protocol::{self, AgentKeyInfo, Request, Response};
+use crate::agent::AGENT_SOCKET_ENV;
+use crate::crypto::algorithms::{Algorithm, HostKeyAlgorithm};
+use crate::error::{Error, Result};
+
+const NO_AGENT_ERROR: &str = "No agent is running, SSSH_AUTH_SOCK is not set";
+const AGENT_CLOSED_ERROR: &str = "The agent closed the connection";
+const UNEXPECTED_RESPONSE_ERROR: &str = "The agent sent an unexpected response";
+
+pub struct AgentClient {
+    reader: BufReader<UnixStream>,
+    writer: BufWriter<UnixStream>,
+}
+
+impl AgentClient {
+
+    //Connects to the agent of SSSH_AUTH_SOCK
+    pub fn connect() -> Result<Self> {
+
+        let path = env::var_os(AGENT_SOCKET_ENV).ok_or(Error::Static(NO_AGENT_ERROR))?;
+
+        Self::connect_to(Path::new(&path))
+    }
+
+    pub fn connect_to(path: &Path) -> Result<Self> {
+
+        let stream = UnixStream::connect(path)?;
+
+        Ok(Self { reader: BufReader::new(stream.try_clone()?), writer: BufWriter::new(stream) })
+    }
+
+    pub fn list(&mut self) -> Result<Vec<AgentKeyInfo>> {
+        match self.request(&Request::List)? {
+            Response::Keys(keys) => Ok(keys),
+            _ => Err(Error::Static(UNEXPECTED_RESPONSE_ERROR)),
+        }
+    }
+
+    pub fn add(&mut self, private_key_pem: &str, comment: &str) -> Result<()> {
+        self.request_ok(&Request::Add { private_key_pem: private_key_pem.to_string(), comment: comment.to_string() })
+    }
+
+    pub fn remove(&mut self, public_key_pem: &str) -> Result<()> {
+        self.request_ok(&Request::Remove { public_key_pem: public_key_pem.to_string() })
+    }
+
+    pub fn remove_all(&mut self) -> Result<()> {
+        self.request_ok(&Request::RemoveAll)
+    }
+
+    //Signs the data with the key of the public key PEM, by the algorithm
+    pub fn sign(&mut self, public_key_pem: &str, algorithm: HostKeyAlgorithm, data: &[u8]) -> Result<Vec<u8>> {
+
+        let request = Request::Sign { public_key_pem: public_key_pem.to_string(), algorithm: algorithm.name().to_string(), data: data.to_vec() };
+
+        match self.request(&request)? {
+            Response::Signature(signature) => Ok(signature),
+            _ => Err(Error::Static(UNEXPECTED_RESPONSE_ERROR)),
+        }
+    }
+
+    fn request(&mut self, request: &Request) -> Result<Response> {
+
+        protocol::write_frame(&mut self.writer, request)?;
+
+        match protocol::read_frame(&mut self.reader)? {
+            Some(Response::Error(reason)) => Err(Error::Agent(reason)),
+            Some(response) => Ok(response),
+            None => Err(Error::Static(AGENT_CLOSED_ERROR)),
+        }
+    }
+
+    fn request_ok(&mut self, request: &Request) -> Result<()> {
+        match self.request(request)? {
+            Response::Ok => Ok(()),
+            _ => Err(Error::Static(UNEXPECTED_RESPONSE_ERROR)),
+        }
+    }
+}
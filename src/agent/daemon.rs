@@ -0,0 +1,219 @@
+/*
+ * ##############################################
+ * File responsible for the agent program,
+ * sssh-agent:
+ *
+ * sssh-agent [options]
+ *
+ * -a socket - Listens at the socket, instead of
+ *   a new private directory of /tmp
+ * -D - Stays on the foreground
+ * -k - Kills the agent of SSSH_AGENT_PID
+ * -v - Shows debug messages, on the foreground
+ *
+ * It prints the shell commands which set the
+ * variables of the agent, so it is started as:
+ *
+ * eval "$(sssh-agent)"
+ *
+ * Then runs on the background, until killed,
+ * where the keys are removed before it ends.
+ * ##############################################
+ */
+
+use std::env;
+use std::ffi::CString;
+use std::io;
+use std::path::PathBuf;
+use std::process;
+use std::sync::Arc;
+use std::thread;
+
+use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM};
+use signal_hook::iterator::Signals;
+
+use crate::agent::protocol::Request;
+use crate::agent::server::{self, Agent, AgentSocket};
+use crate::agent::{AGENT_PID_ENV, AGENT_SOCKET_ENV};
+use crate::error::{Error, Result};
+
+pub const USAGE: &str = "Usage: sssh-agent [-Dhkv] [-a socket]";
+
+const MAX_VERBOSITY: u8 = 3;
+const NULL_DEVICE: &str = "/dev/null";
+
+const NO_AGENT_PID_ERROR: &str = "SSSH_AGENT_PID is not set to an agent process";
+const FORK_ERROR: &str = "Could not start the agent on the background";
+
+//The command line of sssh-agent
+pub struct AgentCli {
+    pub socket: Option<PathBuf>,
+    pub foreground: bool,
+    pub kill: bool,
+    pub verbosity: u8,
+    pub help: bool,
+}
+
+impl AgentCli {
+
+    //Parses the arguments, without the program name, returning an usage error message
+    pub fn parse(args: &[String]) -> std::result::Result<Self, String> {
+
+        let mut cli = Self { socket: None, foreground: false, kill: false, verbosity: 0, help: false };
+        let mut index = 0;
+
+        while index < args.len() {
+
+            let arg = &args[index];
+            index += 1;
+
+            if !arg.starts_with('-') || arg.len() == 1 {
+                return Err(format!("Unexpected argument {}", arg));
+            }
+
+            let mut flags = arg[1..].chars();
+
+            while let Some(flag) = flags.next() {
+                match flag {
+                    'D' => cli.foreground = true,
+                    'k' => cli.kill = true,
+                    'h' => cli.help = true,
+                    'v' => cli.verbosity = (cli.verbosity + 1).min(MAX_VERBOSITY),
+                    'a' => {
+
+                        let attached: String = flags.by_ref().collect();
+
+                        let value = if !attached.is_empty() {
+                            attached
+                        } else if index < args.len() {
+                            index += 1;
+                            args[index - 1].clone()
+                        } else {
+                            return Err("Option -a requires a value".to_string());
+                        };
+
+                        cli.socket = Some(PathBuf::from(value));
+                    },
+                    _ => return Err(format!("Unknown option -{}", flag)),
+                }
+            }
+        }
+
+        Ok(cli)
+    }
+}
+
+//Starts the agent, or kills the running one with -k
+pub fn run(cli: &AgentCli) -> Result<()> {
+
+    if cli.kill {
+        return kill();
+    }
+
+    //Absolute, since the agent leaves the current directory
+    let path = match &cli.socket {
+        Some(path) => Some(env::current_dir()?.join(path)),
+        None => None,
+    };
+
+    let socket = Arc::new(AgentSocket::bind(path.as_deref())?);
+
+    //Keeps the keys out of core dumps and of other processes of the user
+    unsafe { libc::prctl(libc::PR_SET_DUMPABLE, 0) };
+
+    if !cli.foreground {
+
+        //The fork is before any thread, the parent only tells the child variables
+        match unsafe { libc::fork() } {
+            -1 => {
+                socket.remove();
+                return Err(Error::Static(FORK_ERROR));
+            },
+            0 => detach()?,
+            child => {
+                print_variables(&socket, child as u32);
+                process::exit(0);
+            },
+        }
+    } else {
+        print_variables(&socket, process::id());
+    }
+
+    let agent = Agent::new();
+
+    let mut signals = Signals::new([SIGTERM, SIGINT, SIGHUP])?;
+
+    {
+        let agent = agent.clone();
+        let socket = socket.clone();
+
+        thread::spawn(move || {
+            if signals.forever().next().is_some() {
+                agent.handle(Request::RemoveAll);
+                socket.remove();
+                process::exit(0);
+            }
+        });
+    }
+
+    let result = server::serve(agent.clone(), &socket);
+
+    agent.handle(Request::RemoveAll);
+    socket.remove();
+
+    result
+}
+
+//Ends the agent of SSSH_AGENT_PID, and prints the commands which unset the variables
+fn kill() -> Result<()> {
+
+    let pid = env::var(AGENT_PID_ENV).ok()
+        .and_then(|pid| pid.parse::<libc::pid_t>().ok())
+        .filter(|pid| *pid > 0)
+        .ok_or(Error::Static(NO_AGENT_PID_ERROR))?;
+
+    if unsafe { libc::kill(pid, libc::SIGTERM) } < 0 {
+        return Err(Error::Io(io::Error::last_os_error()));
+    }
+
+    println!("unset {};", AGENT_SOCKET_ENV);
+    println!("unset {};", AGENT_PID_ENV);
+    println!("echo Agent pid {} killed;", pid);
+
+    Ok(())
+}
+
+fn print_variables(socket: &AgentSocket, pid: u32) {
+    println!("{}={}; export {};", AGENT_SOCKET_ENV, socket.path.display(), AGENT_SOCKET_ENV);
+    println!("{}={}; export {};", AGENT_PID_ENV, pid, AGENT_PID_ENV);
+    println!("echo Agent pid {};", pid);
+}
+
+//Leaves the terminal of the shell, on a new session, with the standard streams on /dev/null
+fn detach() -> Result<()> {
+
+    let null_device = CString::new(NULL_DEVICE).expect("The path has no null character");
+
+    // SAFETY: only the process own descriptors are changed
+    unsafe {
+        libc::setsid();
+
+        let fd = libc::open(null_device.as_ptr(), libc::O_RDWR);
+
+        if fd < 0 {
+            return Err(Error::Io(io::Error::last_os_error()));
+        }
+
+        for target in [libc::STDIN_FILENO, libc::STDOUT_FILENO, libc::STDERR_FILENO] {
+            libc::dup2(fd, target);
+        }
+
+        if fd > libc::STDERR_FILENO {
+            libc::close(fd);
+        }
+    }
+
+    env::set_current_dir("/")?;
+
+    Ok(())
+}
@@ -0,0 +1,28 @@
+/*
+ *#########################################################
+ * File responsible for the authentication agent, which
+ * keeps the user private keys in memory and signs with
+ * them, so the client does not read the key files:
+ *
+ * protocol - The requests and responses, as frames
+ * server - The keys kept and the agent socket
+ * client - The requests of the client and sssh-add
+ * daemon - The sssh-agent program
+ * add - The sssh-add program, which adds, lists and
+ *   removes the agent keys
+ *
+ * The agent socket is told by SSSH_AUTH_SOCK.
+ *#########################################################
+ */
+
+pub mod protocol;
+pub mod server;
+pub mod client;
+pub mod daemon;
+pub mod add;
+
+//The path of the agent socket
+pub const AGENT_SOCKET_ENV: &str = "SSSH_AUTH_SOCK";
+
+//The process of the agent, so it can be killed with sssh-agent -k
+pub const AGENT_PID_ENV: &str = "SSSH_AGENT_PID";
@@ -0,0 +1,47 @@
+/*
+ * ##############################################
+ * File responsible for the agent protocol, the
+ * requests of a client and the responses of the
+ * agent, framed as the transfer ones:
+ *
+ * length (u32, big endian) || bincode content
+ *
+ * The agent answers each request in order:
+ *
+ * List -> Keys | Error
+ * Add | Remove | RemoveAll -> Ok | Error
+ * Sign -> Signature | Error
+ *
+ * A key is known by his public key PEM, the
+ * private one never leaves the agent.
+ * ##############################################
+ */
+
+use serde::{Deserialize, Serialize};
+
+pub use crate::transfer::protocol::{read_frame, write_frame};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum Request {
+    List,
+    //The comment tells the key on the list, as his file
+    Add { private_key_pem: String, comment: String },
+    Remove { public_key_pem: String },
+    RemoveAll,
+    //Signs the data with the key, by the signature algorithm name
+    Sign { public_key_pem: String, algorithm: String, data: Vec<u8> },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum Response {
+    Ok,
+    Keys(Vec<AgentKeyInfo>),
+    Signature(Vec<u8>),
+    Error(String),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AgentKeyInfo {
+    pub public_key_pem: String,
+    pub comment: String,
+}
@@ -0,0 +1,240 @@
+/*
+ * ##############################################
+ * File responsible for the agent itself, which
+ * keeps the added private keys in memory, each
+ * one zeroized when removed, and answers the
+ * requests of each client on the agent socket.
+ *
+ * The socket is on a directory only the user
+ * may enter, and a client of other user, but
+ * root, is closed without an answer.
+ * ##############################################
+ */
+
+use std::env;
+use std::fs::{self, DirBuilder};
+use std::io::{self, BufReader, BufWriter};
+use std::mem;
+use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+use std::os::unix::io::AsRawFd;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use rsa::pkcs8::der::zeroize::Zeroizing;
+
+use crate::agent::protocol::{self, AgentKeyInfo, Request, Response};
+use crate::cli;
+use crate::crypto;
+use crate::crypto::algorithms::{Algorithm, HostKeyAlgorithm};
+use crate::error::{Error, Result};
+
+const SOCKET_DIRECTORY_PREFIX: &str = "sssh-";
+const SOCKET_DIRECTORY_RANDOM_SIZE: usize = 12;
+const SOCKET_DIRECTORY_MODE: u32 = 0o700;
+const SOCKET_MODE: u32 = 0o600;
+
+const UNKNOWN_KEY_ERROR: &str = "The key is not on the agent";
+const UNKNOWN_ALGORITHM_ERROR: &str = "Unknown signature algorithm";
+const PEER_USER_ERROR: &str = "Could not get the user of the agent client";
+
+//The keys of the agent, shared by every client
+#[derive(Default)]
+pub struct Agent {
+    keys: Mutex<Vec<AgentKey>>,
+}
+
+struct AgentKey {
+    info: AgentKeyInfo,
+    //Zeroized when the key is removed, or the agent ends
+    private_key_pem: Zeroizing<String>,
+}
+
+//The listening socket, removed with his directory at the end
+pub struct AgentSocket {
+    pub listener: UnixListener,
+    pub path: PathBuf,
+    directory: Option<PathBuf>,
+    closed: AtomicBool,
+}
+
+impl Agent {
+
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    pub fn handle(&self, request: Request) -> Response {
+
+        let result = match request {
+            Request::List => Ok(Response::Keys(self.keys.lock().unwrap().iter().map(|key| key.info.clone()).collect())),
+            Request::Add { private_key_pem, comment } => self.add(Zeroizing::new(private_key_pem), comment).map(|_| Response::Ok),
+            Request::Remove { public_key_pem } => self.remove(&public_key_pem).map(|_| Response::Ok),
+            Request::RemoveAll => {
+                self.keys.lock().unwrap().clear();
+                Ok(Response::Ok)
+            },
+            Request::Sign { public_key_pem, algorithm, data } => self.sign(&public_key_pem, &algorithm, &data).map(Response::Signature),
+        };
+
+        result.unwrap_or_else(|e| Response::Error(cli::error_message(&e)))
+    }
+
+    //Adds the key, an added one again only changes his comment
+    fn add(&self, private_key_pem: Zeroizing<String>, comment: String) -> Result<()> {
+
+        let public_key_pem = crypto::public_key_pem_from_private(&private_key_pem)?;
+
+        let mut keys = self.keys.lock().unwrap();
+
+        keys.retain(|key| key.info.public_key_pem != public_key_pem);
+        keys.push(AgentKey { info: AgentKeyInfo { public_key_pem, comment }, private_key_pem });
+
+        Ok(())
+    }
+
+    fn remove(&self, public_key_pem: &str) -> Result<()> {
+
+        let mut keys = self.keys.lock().unwrap();
+
+        let Some(index) = keys.iter().position(|key| key.info.public_key_pem == public_key_pem.trim()) else {
+            return Err(Error::Static(UNKNOWN_KEY_ERROR));
+        };
+
+        keys.remove(index);
+
+        Ok(())
+    }
+
+    fn sign(&self, public_key_pem: &str, algorithm: &str, data: &[u8]) -> Result<Vec<u8>> {
+
+        let algorithm = HostKeyAlgorithm::from_name(algorithm).ok_or(Error::Static(UNKNOWN_ALGORITHM_ERROR))?;
+
+        let keys = self.keys.lock().unwrap();
+
+        let key = keys.iter()
+            .find(|key| key.info.public_key_pem == public_key_pem.trim())
+            .ok_or(Error::Static(UNKNOWN_KEY_ERROR))?;
+
+        crypto::sign(algorithm, &key.private_key_pem, data)
+    }
+}
+
+impl AgentSocket {
+
+    /*
+     * Listens at the path, or at agent.<pid> on a new private directory
+     * of the temporary one, where only the user may connect
+     */
+    pub fn bind(path: Option<&Path>) -> Result<Self> {
+
+        let (path, directory) = match path {
+            Some(path) => (path.to_path_buf(), None),
+            None => {
+                let directory = env::temp_dir().join(format!("{}{}", SOCKET_DIRECTORY_PREFIX, crypto::generate_random_string(SOCKET_DIRECTORY_RANDOM_SIZE)));
+                DirBuilder::new().mode(SOCKET_DIRECTORY_MODE).create(&directory)?;
+                (directory.join(format!("agent.{}", process::id())), Some(directory))
+            },
+        };
+
+        let listener = UnixListener::bind(&path)?;
+        fs::set_permissions(&path, fs::Permissions::from_mode(SOCKET_MODE))?;
+
+        Ok(Self { listener, path, directory, closed: AtomicBool::new(false) })
+    }
+
+    //Stops listening, waking up the thread which accepts, then removes the socket
+    pub fn close(&self) {
+
+        self.closed.store(true, Ordering::Release);
+
+        // SAFETY: the descriptor is of our listener, open while self is borrowed
+        unsafe { libc::shutdown(self.listener.as_raw_fd(), libc::SHUT_RDWR) };
+
+        self.remove();
+    }
+
+    //If it was closed, so a failed accept is the end of the listening
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
+
+    //Removes the socket, and the directory created for it
+    pub fn remove(&self) {
+
+        let _ = fs::remove_file(&self.path);
+
+        if let Some(directory) = &self.directory {
+            let _ = fs::remove_dir(directory);
+        }
+    }
+}
+
+//Answers each client of the socket on his own thread, only of the same user or root
+pub fn serve(agent: Arc<Agent>, socket: &AgentSocket) -> Result<()> {
+
+    let uid = unsafe { libc::getuid() };
+
+    let on_error = |e: &io::Error| crate::utils::debug(1, &format!("Failed to accept an agent client, {}", e));
+
+    //A failed accept only loses that client, not the keys
+    crate::utils::accept_connections(socket.listener.incoming(), || socket.is_closed(), on_error, |stream| {
+
+        match peer_uid(&stream) {
+            Ok(peer) if peer == uid || peer == 0 => {},
+            Ok(peer) => {
+                crate::utils::debug(1, &format!("Refused an agent client of the user {}", peer));
+                return;
+            },
+            Err(e) => {
+                crate::utils::debug(1, &format!("Refused an agent client, {}", e));
+                return;
+            },
+        }
+
+        let agent = agent.clone();
+
+        thread::spawn(move || {
+            if let Err(e) = serve_client(&agent, stream) {
+                crate::utils::debug(1, &format!("Agent client failed, {}", e));
+            }
+        });
+    });
+
+    Ok(())
+}
+
+//Answers the requests of a client until he closes the socket
+fn serve_client(agent: &Agent, stream: UnixStream) -> Result<()> {
+
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = BufWriter::new(stream);
+
+    while let Some(request) = protocol::read_frame(&mut reader)? {
+        protocol::write_frame(&mut writer, &agent.handle(request))?;
+    }
+
+    Ok(())
+}
+
+//The user of the process on the other side of the socket
+pub fn peer_uid(stream: &UnixStream) -> Result<u32> {
+
+    let mut credentials: libc::ucred = unsafe { mem::zeroed() };
+    let mut size = mem::size_of::<libc::ucred>() as libc::socklen_t;
+
+    // SAFETY: the buffer is a ucred, of the size given
+    let result = unsafe {
+        libc::getsockopt(stream.as_raw_fd(), libc::SOL_SOCKET, libc::SO_PEERCRED, &mut credentials as *mut libc::ucred as *mut libc::c_void, &mut size)
+    };
+
+    if result < 0 {
+        crate::utils::debug(2, &format!("SO_PEERCRED failed, {}", io::Error::last_os_error()));
+        return Err(Error::Static(PEER_USER_ERROR));
+    }
+
+    Ok(credentials.uid)
+}
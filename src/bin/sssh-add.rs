@@ -0,0 +1,39 @@
+use std::env;
+use std::process;
+
+use sssh::agent::add::{self, AddCli};
+use sssh::agent::client::AgentClient;
+use sssh::cli;
+use sssh::utils;
+
+fn main(){
+
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let cli = match AddCli::parse(&args) {
+        Ok(cli) => cli,
+        Err(e) => {
+            eprintln!("sssh-add: {}", e);
+            eprintln!("{}", add::USAGE);
+            process::exit(cli::USAGE_EXIT_CODE);
+        }
+    };
+
+    if cli.help {
+        println!("{}", add::USAGE);
+        return;
+    }
+
+    utils::set_verbosity(cli.verbosity);
+
+    let result = AgentClient::connect().and_then(|mut client| add::run(&mut client, &cli));
+
+    match result {
+        Ok(true) => {},
+        Ok(false) => process::exit(cli::ERROR_EXIT_CODE),
+        Err(e) => {
+            eprintln!("sssh-add: {}", cli::error_message(&e));
+            process::exit(cli::error_exit_code(&e));
+        }
+    }
+}
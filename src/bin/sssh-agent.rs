@@ -0,0 +1,32 @@
+use std::env;
+use std::process;
+
+use sssh::agent::daemon::{self, AgentCli};
+use sssh::cli;
+use sssh::utils;
+
+fn main(){
+
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let cli = match AgentCli::parse(&args) {
+        Ok(cli) => cli,
+        Err(e) => {
+            eprintln!("sssh-agent: {}", e);
+            eprintln!("{}", daemon::USAGE);
+            process::exit(cli::USAGE_EXIT_CODE);
+        }
+    };
+
+    if cli.help {
+        println!("{}", daemon::USAGE);
+        return;
+    }
+
+    utils::set_verbosity(cli.verbosity);
+
+    if let Err(e) = daemon::run(&cli) {
+        eprintln!("sssh-agent: {}", cli::error_message(&e));
+        process::exit(cli::error_exit_code(&e));
+    }
+}
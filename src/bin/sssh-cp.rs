@@ -0,0 +1,50 @@
+use std::env;
+use std::process;
+
+use sssh::cli;
+use sssh::session::Session;
+use sssh::transfer::client::TransferClient;
+use sssh::transfer::copy::{self, CopyCli};
+use sssh::utils;
+
+fn main(){
+
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let cli = match CopyCli::parse(&args) {
+        Ok(cli) => cli,
+        Err(e) => {
+            eprintln!("sssh-cp: {}", e);
+            eprintln!("{}", copy::USAGE);
+            process::exit(cli::USAGE_EXIT_CODE);
+        }
+    };
+
+    if cli.help {
+        println!("{}", copy::USAGE);
+        return;
+    }
+
+    utils::set_verbosity(cli.verbosity);
+
+    let result = Session::connect(cli.destination(), &cli.config).and_then(|session| {
+
+        let mut client = TransferClient::start(&session)?;
+
+        let result = copy::run(&mut client, &cli);
+
+        let _ = client.end();
+        let _ = session.close();
+
+        result
+    });
+
+    match result {
+        Ok(true) => {},
+        Ok(false) => process::exit(cli::ERROR_EXIT_CODE),
+        Err(e) => {
+            eprintln!("sssh-cp: {}", cli::error_message(&e));
+            process::exit(cli::error_exit_code(&e));
+        }
+    }
+}
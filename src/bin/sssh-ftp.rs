@@ -0,0 +1,46 @@
+use std::env;
+use std::process;
+
+use sssh::cli;
+use sssh::session::Session;
+use sssh::transfer::client::TransferClient;
+use sssh::transfer::ftp::{self, FtpCli};
+use sssh::utils;
+
+fn main(){
+
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let cli = match FtpCli::parse(&args) {
+        Ok(cli) => cli,
+        Err(e) => {
+            eprintln!("sssh-ftp: {}", e);
+            eprintln!("{}", ftp::USAGE);
+            process::exit(cli::USAGE_EXIT_CODE);
+        }
+    };
+
+    if cli.help {
+        println!("{}", ftp::USAGE);
+        return;
+    }
+
+    utils::set_verbosity(cli.verbosity);
+
+    let result = Session::connect(&cli.destination, &cli.config).and_then(|session| {
+
+        let mut client = TransferClient::start(&session)?;
+
+        let result = ftp::run(&mut client);
+
+        let _ = client.end();
+        let _ = session.close();
+
+        result
+    });
+
+    if let Err(e) = result {
+        eprintln!("sssh-ftp: {}", cli::error_message(&e));
+        process::exit(cli::error_exit_code(&e));
+    }
+}
@@ -0,0 +1,17 @@
+use sssh::server::Server;
+
+fn main(){
+
+    let server = match Server::bind(None) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if let Err(e) = server.run() {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
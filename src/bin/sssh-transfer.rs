@@ -0,0 +1,9 @@
+use sssh::transfer::server;
+
+fn main(){
+
+    if let Err(e) = server::serve() {
+        eprintln!("sssh-transfer: {}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,280 @@
+/*
+ * ##############################################
+ * File responsible for the client command line:
+ *
+ * sssh [options] user#host [command]
+ *
+ * -p port - The server port
+ * -i file - The identity file, the user private key
+ * -v - More debug messages, may be repeated up to -vvv
+ * -o key=value - Overrides an option, as Port=2222,
+ *   the options are Port, IdentityFile, BatchMode, ForwardAgent and the
+ *   algorithms lists KexAlgorithms, HostKeyAlgorithms,
+ *   Ciphers, MACs and Compression, as Ciphers=aes256-ctr,
+ *   and the rekey limits RekeyBytes, RekeyPackets and
+ *   RekeyMinutes, as RekeyBytes=512M
+ * -L [bind:]port:host:hostport - Forwards the local port,
+ *   on localhost if no bind address, to the host port
+ *   as the server reaches it, may be repeated
+ * -R [bind:]port:host:hostport - Forwards the server
+ *   port, on his loopback if no bind address and as his
+ *   GatewayPorts allows, to the host port as the client
+ *   reaches it, may be repeated
+ * -D [bind:]port - A local SOCKS5 and SOCKS4a proxy, on
+ *   localhost if no bind address, where the server connects
+ *   to each asked host, may be repeated
+ * -N - No command nor shell, only forwards the ports
+ * -A - Forwards the agent of SSSH_AUTH_SOCK to the shell
+ *   or command, so it may authenticate onward with our keys
+ * -B - Batch mode, never asks, failing instead
+ * -h - Shows the usage
+ *
+ * Options come before the destination, everything
+ * after it is the command, an optional -- may
+ * separate it, without a command
+ * the user shell is started.
+ * ##############################################
+ */
+
+use std::path::PathBuf;
+
+use crate::error::Error;
+use crate::session::config::SessionConfig;
+use crate::session::forward::Forward;
+use crate::session::socks::DynamicForward;
+
+pub const USAGE: &str = "Usage: sssh [-ABhNv] [-D [bind:]port] [-i identity_file] [-L [bind:]port:host:hostport] [-o option=value] [-p port] [-R [bind:]port:host:hostport] user#host [command]";
+
+//Exit codes, from sysexits.h, the remote command exit code otherwise
+pub const USAGE_EXIT_CODE: i32 = 64;
+const DATA_EXIT_CODE: i32 = 65;
+const IO_EXIT_CODE: i32 = 74;
+const PROTOCOL_EXIT_CODE: i32 = 76;
+pub const ERROR_EXIT_CODE: i32 = 255;
+
+const MAX_VERBOSITY: u8 = 3;
+const COMMAND_SEPARATOR: &str = "--";
+
+pub struct Cli {
+    pub destination: String,
+    pub command: Option<String>,
+    pub verbosity: u8,
+    pub help: bool,
+    pub no_command: bool,
+    pub config: SessionConfig,
+}
+
+impl Cli {
+
+    //Parses the arguments, without the program name, returning an usage error message
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+
+        let mut config = SessionConfig::default();
+        let mut verbosity = 0;
+        let mut help = false;
+        let mut no_command = false;
+        let mut destination = None;
+        let mut index = 0;
+
+        while index < args.len() {
+
+            let arg = &args[index];
+            index += 1;
+
+            //The first argument which is not an option is the destination
+            if !arg.starts_with('-') || arg.len() == 1 {
+                destination = Some(arg.clone());
+                break;
+            }
+
+            //Flags may be grouped, as -vvB, and the option value may be attached, as -p2222
+            let mut flags = arg[1..].chars();
+
+            while let Some(flag) = flags.next() {
+                match flag {
+                    'v' => verbosity = (verbosity + 1).min(MAX_VERBOSITY),
+                    'B' => config.batch_mode = true,
+                    'h' => help = true,
+                    'N' => no_command = true,
+                    'A' => config.forward_agent = true,
+                    'p' | 'i' | 'o' | 'L' | 'R' | 'D' => {
+
+                        let attached: String = flags.by_ref().collect();
+
+                        let value = if !attached.is_empty() {
+                            attached
+                        } else if index < args.len() {
+                            index += 1;
+                            args[index - 1].clone()
+                        } else {
+                            return Err(format!("Option -{} requires a value", flag));
+                        };
+
+                        match flag {
+                            'p' => config.port = Some(parse_port(&value)?),
+                            'i' => config.identity_file = Some(PathBuf::from(value)),
+                            'L' => config.local_forwards.push(parse_forward(&value)?),
+                            'R' => config.remote_forwards.push(parse_forward(&value)?),
+                            'D' => config.dynamic_forwards.push(parse_dynamic_forward(&value)?),
+                            _ => parse_option(&mut config, &value)?,
+                        }
+                    },
+                    _ => return Err(format!("Unknown option -{}", flag)),
+                }
+            }
+        }
+
+        if help {
+            return Ok(Self { destination: String::new(), command: None, verbosity, help, no_command, config });
+        }
+
+        let Some(destination) = destination else {
+            return Err("Missing the destination user#host".to_string());
+        };
+
+        //The command may be separated from the destination with --
+        if args.get(index).is_some_and(|arg| arg == COMMAND_SEPARATOR) {
+            index += 1;
+        }
+
+        let command = match &args[index..] {
+            [] => None,
+            command => Some(command.join(" ")),
+        };
+
+        if no_command && command.is_some() {
+            return Err("Option -N does not take a command".to_string());
+        }
+
+        Ok(Self { destination, command, verbosity, help, no_command, config })
+    }
+}
+
+//Parses an option as key=value, the keys are case insensitive
+pub(crate) fn parse_option(config: &mut SessionConfig, option: &str) -> Result<(), String> {
+
+    let Some((key, value)) = option.split_once('=') else {
+        return Err(format!("Invalid option {}, expected key=value", option));
+    };
+
+    match key.trim().to_lowercase().as_str() {
+        "port" => config.port = Some(parse_port(value.trim())?),
+        "identityfile" => config.identity_file = Some(PathBuf::from(value.trim())),
+        "batchmode" => config.batch_mode = parse_yes_no(key, value.trim())?,
+        "forwardagent" => config.forward_agent = parse_yes_no(key, value.trim())?,
+        _ => {
+            //The algorithms lists, as KexAlgorithms=x25519-sha256, or the rekey limits
+            let is_known = config.algorithms.set_list(key.trim(), value.trim())
+                .and_then(|is_list| Ok(is_list || config.rekey_limits.set_option(key.trim(), value.trim())?))
+                .map_err(|e| format!("Invalid value {} for {}, {}", value.trim(), key, error_message(&e)))?;
+
+            if !is_known {
+                return Err(format!("Unknown option {}", key));
+            }
+        },
+    }
+
+    Ok(())
+}
+
+//Parses a forward as [bind:]port:host:hostport, where an IPv6 address is between brackets
+fn parse_forward(value: &str) -> Result<Forward, String> {
+
+    let fields = split_address_fields(value);
+
+    let (bind, port, host, host_port) = match fields.as_slice() {
+        [port, host, host_port] => (None, port, host, host_port),
+        [bind, port, host, host_port] => (Some(bind.to_string()), port, host, host_port),
+        _ => return Err(format!("Invalid forward {}, expected [bind:]port:host:hostport", value)),
+    };
+
+    if host.is_empty() {
+        return Err(format!("Invalid forward {}, missing the host", value));
+    }
+
+    Ok(Forward { bind: bind.map(wildcard_bind), port: parse_port(port)?, host: host.to_string(), host_port: parse_port(host_port)? })
+}
+
+//Parses a SOCKS proxy as [bind:]port
+fn parse_dynamic_forward(value: &str) -> Result<DynamicForward, String> {
+
+    let (bind, port) = match split_address_fields(value).as_slice() {
+        [port] => (None, port.to_string()),
+        [bind, port] => (Some(bind.to_string()), port.to_string()),
+        _ => return Err(format!("Invalid SOCKS proxy {}, expected [bind:]port", value)),
+    };
+
+    Ok(DynamicForward { bind: bind.map(wildcard_bind), port: parse_port(&port)? })
+}
+
+//An empty bind address is every interface, as *
+fn wildcard_bind(bind: String) -> String {
+    if bind.is_empty() || bind == "*" { "0.0.0.0".to_string() } else { bind }
+}
+
+//Splits on the colons which are not between brackets, removing the brackets
+fn split_address_fields(value: &str) -> Vec<&str> {
+
+    let mut fields = Vec::new();
+    let mut start = 0;
+    let mut in_brackets = false;
+
+    for (index, c) in value.char_indices() {
+        match c {
+            '[' => in_brackets = true,
+            ']' => in_brackets = false,
+            ':' if !in_brackets => {
+                fields.push(&value[start..index]);
+                start = index + 1;
+            },
+            _ => {},
+        }
+    }
+
+    fields.push(&value[start..]);
+
+    fields.into_iter().map(|field| field.trim_start_matches('[').trim_end_matches(']')).collect()
+}
+
+pub(crate) fn parse_port(value: &str) -> Result<u16, String> {
+    match value.parse::<u16>() {
+        Ok(port) if port != 0 => Ok(port),
+        _ => Err(format!("Invalid port {}", value)),
+    }
+}
+
+fn parse_yes_no(key: &str, value: &str) -> Result<bool, String> {
+    match value.to_lowercase().as_str() {
+        "yes" => Ok(true),
+        "no" => Ok(false),
+        _ => Err(format!("Invalid value {} for {}, expected yes or no", value, key)),
+    }
+}
+
+//The exit code of a failed session, by the error type
+pub fn error_exit_code(error: &Error) -> i32 {
+    match error {
+        Error::Io(_) => IO_EXIT_CODE,
+        Error::Str(_) | Error::UnknownMessage(_) | Error::Codec(_) | Error::IncompatibleVersion(..) => PROTOCOL_EXIT_CODE,
+        Error::CryptoRSA(_) | Error::CryptoPkcs1(_) => DATA_EXIT_CODE,
+        Error::Static(_) | Error::ChannelOpenFailure(_) | Error::ForwardFailure(_) | Error::Transfer(_) | Error::Agent(_) => ERROR_EXIT_CODE,
+    }
+}
+
+//A clearer message of a failed session, saying where it failed
+pub fn error_message(error: &Error) -> String {
+    match error {
+        Error::Io(e) => format!("I/O error: {}", e),
+        Error::Str(e) => format!("Protocol error: invalid text, {}", e),
+        Error::UnknownMessage(e) => format!("Protocol error: unknown message type {}", e),
+        Error::Codec(e) => format!("Protocol error: invalid message, {}", e),
+        Error::IncompatibleVersion(ours, theirs) => format!("Protocol error: the server version {} is not compatible with ours {}", theirs, ours),
+        Error::CryptoRSA(e) => format!("Key error: {}", e),
+        Error::CryptoPkcs1(e) => format!("Key error: invalid key file, {}", e),
+        Error::Static(e) => e.to_string(),
+        Error::ChannelOpenFailure(reason) => format!("The server could not open the channel, {}", reason),
+        Error::ForwardFailure(reason) => format!("The server could not forward the port, {}", reason),
+        Error::Transfer(reason) => format!("Remote file error: {}", reason),
+        Error::Agent(reason) => format!("Agent error: {}", reason),
+    }
+}
@@ -0,0 +1,321 @@
+use serde::{Deserialize, Serialize};
+use crate::crypto::key_type::KeyType;
+use crate::crypto::signature::{Ed25519Scheme, RsaPssSha256Scheme, RsaSha256Scheme, RsaSha512Scheme, SignatureScheme};
+use crate::error::{Error, Result};
+/*
+ *######################################################
+ * The algorithms negotiated by both machines, after
+ * the banner, each one sends his ordered lists of:
+ *
+ * kex - The key exchange
+ * host_key - The server key type and signature, also
+ *   the allowed signatures of the user keys
+ * cipher - The packets encryption, an AEAD cipher
+ *   also gives the integrity
+ * mac - The packets integrity, unused with an AEAD cipher
+ * compression - The packets compression
+ *
+ * Of each list, the chosen algorithm is the first one
+ * of the client which the server also supports, so both
+ * machines choose the same without another message.
+ *
+ * A list has the preferred algorithms first, and may
+ * be changed by the user or the server administrator
+ * to disable an algorithm.
+ *######################################################
+ */
+
+const NO_MUTUAL_ALGORITHM_ERROR: &str = "The client and server have no mutual algorithm";
+const UNKNOWN_ALGORITHM_ERROR: &str = "Unknown algorithm on the list";
+const EMPTY_LIST_ERROR: &str = "An algorithms list cannot be empty";
+
+const LIST_SEPARATOR: char = ',';
+
+//An algorithm of a list, known by his name on the wire
+pub trait Algorithm: Copy + 'static {
+
+    //Every supported algorithm, by order of preference
+    fn all() -> &'static [Self];
+
+    fn name(&self) -> &'static str;
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::all().iter().copied().find(|algorithm| algorithm.name() == name)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum KexAlgorithm {
+    X25519,
+    DiffieHellman,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HostKeyAlgorithm {
+    Ed25519,
+    RsaPssSha256,
+    RsaSha512,
+    RsaSha256,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CipherAlgorithm {
+    ChaCha20Poly1305,
+    Aes256Gcm,
+    Aes256Ctr,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MacAlgorithm {
+    HmacSha256,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CompressionAlgorithm {
+    None,
+}
+
+impl Algorithm for KexAlgorithm {
+
+    fn all() -> &'static [Self] {
+        &[KexAlgorithm::X25519, KexAlgorithm::DiffieHellman]
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            KexAlgorithm::X25519 => "x25519-sha256",
+            KexAlgorithm::DiffieHellman => "dh-rfc3526-sha256",
+        }
+    }
+}
+
+impl Algorithm for HostKeyAlgorithm {
+
+    fn all() -> &'static [Self] {
+        &[HostKeyAlgorithm::Ed25519, HostKeyAlgorithm::RsaPssSha256, HostKeyAlgorithm::RsaSha512, HostKeyAlgorithm::RsaSha256]
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            HostKeyAlgorithm::Ed25519 => "ed25519",
+            HostKeyAlgorithm::RsaPssSha256 => "rsa-pss-sha256",
+            HostKeyAlgorithm::RsaSha512 => "rsa-sha512",
+            HostKeyAlgorithm::RsaSha256 => "rsa-sha256",
+        }
+    }
+}
+
+impl HostKeyAlgorithm {
+
+    //The signature scheme, which signs and verifies
+    pub fn scheme(&self) -> &'static dyn SignatureScheme {
+        match self {
+            HostKeyAlgorithm::Ed25519 => &Ed25519Scheme,
+            HostKeyAlgorithm::RsaPssSha256 => &RsaPssSha256Scheme,
+            HostKeyAlgorithm::RsaSha512 => &RsaSha512Scheme,
+            HostKeyAlgorithm::RsaSha256 => &RsaSha256Scheme,
+        }
+    }
+
+    //The type of the key, which signs with this algorithm
+    pub fn key_type(&self) -> KeyType {
+        self.scheme().key_type()
+    }
+}
+
+impl Algorithm for CipherAlgorithm {
+
+    fn all() -> &'static [Self] {
+        &[CipherAlgorithm::ChaCha20Poly1305, CipherAlgorithm::Aes256Gcm, CipherAlgorithm::Aes256Ctr]
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            CipherAlgorithm::ChaCha20Poly1305 => "chacha20-poly1305",
+            CipherAlgorithm::Aes256Gcm => "aes256-gcm",
+            CipherAlgorithm::Aes256Ctr => "aes256-ctr",
+        }
+    }
+}
+
+impl CipherAlgorithm {
+
+    //An AEAD cipher authenticates the packets by itself, without the negotiated MAC
+    pub fn is_aead(&self) -> bool {
+        matches!(self, CipherAlgorithm::ChaCha20Poly1305 | CipherAlgorithm::Aes256Gcm)
+    }
+
+    pub fn key_size(&self) -> usize {
+        match self {
+            CipherAlgorithm::ChaCha20Poly1305 | CipherAlgorithm::Aes256Gcm | CipherAlgorithm::Aes256Ctr => 32,
+        }
+    }
+
+    //The initial counter of CTR, or the nonce of an AEAD cipher
+    pub fn iv_size(&self) -> usize {
+        match self {
+            CipherAlgorithm::ChaCha20Poly1305 | CipherAlgorithm::Aes256Gcm => 12,
+            CipherAlgorithm::Aes256Ctr => 16,
+        }
+    }
+}
+
+impl Algorithm for MacAlgorithm {
+
+    fn all() -> &'static [Self] {
+        &[MacAlgorithm::HmacSha256]
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            MacAlgorithm::HmacSha256 => "hmac-sha256",
+        }
+    }
+}
+
+impl MacAlgorithm {
+
+    pub fn key_size(&self) -> usize {
+        match self {
+            MacAlgorithm::HmacSha256 => 32,
+        }
+    }
+}
+
+impl Algorithm for CompressionAlgorithm {
+
+    fn all() -> &'static [Self] {
+        &[CompressionAlgorithm::None]
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            CompressionAlgorithm::None => "none",
+        }
+    }
+}
+
+//The lists sent by each machine, with the algorithms names
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AlgorithmLists {
+    pub kex: Vec<String>,
+    pub host_key: Vec<String>,
+    pub cipher: Vec<String>,
+    pub mac: Vec<String>,
+    pub compression: Vec<String>,
+}
+
+//The chosen algorithms, the same on both machines
+#[derive(Clone, Copy, Debug)]
+pub struct Algorithms {
+    pub kex: KexAlgorithm,
+    pub host_key: HostKeyAlgorithm,
+    pub cipher: CipherAlgorithm,
+    pub mac: MacAlgorithm,
+    pub compression: CompressionAlgorithm,
+}
+
+impl Default for AlgorithmLists {
+    fn default() -> Self {
+        Self {
+            kex: names::<KexAlgorithm>(),
+            host_key: names::<HostKeyAlgorithm>(),
+            cipher: names::<CipherAlgorithm>(),
+            mac: names::<MacAlgorithm>(),
+            compression: names::<CompressionAlgorithm>(),
+        }
+    }
+}
+
+impl AlgorithmLists {
+
+    /*
+     * Changes a list by his option name, with the algorithms separated by ',' as:
+     *
+     * KexAlgorithms, HostKeyAlgorithms, Ciphers, MACs or Compression
+     *
+     * Returns false if the option is not a list, an unknown algorithm is an error
+     */
+    pub fn set_list(&mut self, option: &str, value: &str) -> Result<bool> {
+
+        match option.to_lowercase().as_str() {
+            "kexalgorithms" => self.kex = parse_list::<KexAlgorithm>(value)?,
+            "hostkeyalgorithms" => self.host_key = parse_list::<HostKeyAlgorithm>(value)?,
+            "ciphers" => self.cipher = parse_list::<CipherAlgorithm>(value)?,
+            "macs" => self.mac = parse_list::<MacAlgorithm>(value)?,
+            "compression" => self.compression = parse_list::<CompressionAlgorithm>(value)?,
+            _ => return Ok(false),
+        }
+
+        Ok(true)
+    }
+
+    //Chooses the algorithms, where self are the client lists
+    pub fn negotiate(&self, server: &AlgorithmLists) -> Result<Algorithms> {
+        Ok(Algorithms {
+            kex: choose(&self.kex, &server.kex)?,
+            host_key: choose(&self.host_key, &server.host_key)?,
+            cipher: choose(&self.cipher, &server.cipher)?,
+            mac: choose(&self.mac, &server.mac)?,
+            compression: choose(&self.compression, &server.compression)?,
+        })
+    }
+
+    /*
+     * The algorithms, by the order of self, to sign the user keys,
+     * the server only lists the ones of its host key types, so
+     * for other key types all of self are kept
+     */
+    pub fn signature_algorithms(&self, server: &AlgorithmLists) -> Vec<HostKeyAlgorithm> {
+
+        let server_algorithms: Vec<HostKeyAlgorithm> = server.host_key.iter().filter_map(|name| HostKeyAlgorithm::from_name(name)).collect();
+
+        self.host_key.iter()
+            .filter_map(|name| HostKeyAlgorithm::from_name(name))
+            .filter(|algorithm| {
+                server_algorithms.contains(algorithm) || !server_algorithms.iter().any(|other| other.key_type() == algorithm.key_type())
+            })
+            .collect()
+    }
+}
+
+impl std::fmt::Display for Algorithms {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mac = if self.cipher.is_aead() { "implicit" } else { self.mac.name() };
+
+        write!(f, "kex {}, host key {}, cipher {}, mac {}, compression {}",
+            self.kex.name(), self.host_key.name(), self.cipher.name(), mac, self.compression.name())
+    }
+}
+
+fn names<T: Algorithm>() -> Vec<String> {
+    T::all().iter().map(|algorithm| algorithm.name().to_string()).collect()
+}
+
+fn parse_list<T: Algorithm>(value: &str) -> Result<Vec<String>> {
+
+    let mut list = Vec::new();
+
+    for name in value.split(LIST_SEPARATOR).map(str::trim).filter(|name| !name.is_empty()) {
+
+        if T::from_name(name).is_none() {
+            return Err(Error::Static(UNKNOWN_ALGORITHM_ERROR));
+        }
+
+        list.push(name.to_string());
+    }
+
+    if list.is_empty() {
+        return Err(Error::Static(EMPTY_LIST_ERROR));
+    }
+
+    Ok(list)
+}
+
+//The first algorithm of the client, also on the server, which we support
+fn choose<T: Algorithm>(client: &[String], server: &[String]) -> Result<T> {
+    client.iter()
+        .filter(|name| server.contains(name))
+        .find_map(|name| T::from_name(name))
+        .ok_or(Error::Static(NO_MUTUAL_ALGORITHM_ERROR))
+}
@@ -1,6 +1,6 @@
 use num_bigint::BigUint;
 use num_traits::{Zero};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use crate::crypto;
 use crate::error::{Error, Result};
 /*
@@ -32,10 +32,11 @@ use crate::error::{Error, Result};
  */
 
 const PRIVATE_KEY_SIZE: u64 = 512;
-const INVALID_PUBLIC_KEY_ERROR: &'static str = "Received Invalid Public Key";
+const INVALID_PUBLIC_KEY_ERROR: &str = "Received Invalid Public Key";
+const UNKNOWN_GROUP_ERROR: &str = "Received a prime and generator which are not supported";
 
 //Keys and values that are exchanged
-#[derive(Serialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct ExchangedKeys {
     public_key : BigUint,
     prime : BigUint,
@@ -55,10 +56,31 @@ impl DHKeys {
 
         let prime_and_generator = crypto::choose_random_prime();
         
+        Self::from_group(prime_and_generator.0, prime_and_generator.1)
+    }
+
+    //Creates a new set of keys for DH, on the fixed group
+    pub fn from_fixed_group() -> Self{
+
+        let prime_and_generator = crypto::fixed_prime();
+
+        Self::from_group(prime_and_generator.0, prime_and_generator.1)
+    }
+
+    //Creates a new set of keys for DH, with the prime and generator chosen by the other machine
+    pub fn from_exchanged_keys(other_keys: &ExchangedKeys) -> Result<Self>{
+
+        if !crypto::is_known_group(&other_keys.prime, &other_keys.generator){
+            return Err(Error::Static(UNKNOWN_GROUP_ERROR));
+        }
+
+        Ok(Self::from_group(other_keys.prime.clone(), other_keys.generator.clone()))
+    }
+
+    fn from_group(prime: BigUint, generator: BigUint) -> Self{
+
         //Creates all keys attributes
         let mut private_key : BigUint = BigUint::zero();
-        let prime: BigUint = prime_and_generator.0;
-        let generator: BigUint = prime_and_generator.1;
         let mut public_key: BigUint = BigUint::zero();
 
         Self::generate_keys(&mut private_key, &mut public_key, &prime, &generator);
@@ -98,10 +120,20 @@ impl DHKeys {
     }
 }
 
+impl Default for DHKeys {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ExchangedKeys{
 
    pub fn new(public_key: BigUint, prime: BigUint, generator: BigUint) -> Self{
 
        Self {public_key,prime,generator}
     } 
+
+    pub fn get_public_key(&self) -> &BigUint{
+        &self.public_key
+    }
 }
@@ -0,0 +1,90 @@
+use ed25519_dalek::pkcs8::spki::der::pem::LineEnding;
+use ed25519_dalek::pkcs8::{DecodePrivateKey, DecodePublicKey, EncodePrivateKey, EncodePublicKey};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use rand::rngs::OsRng;
+use rsa::pkcs8::der::zeroize::Zeroizing;
+
+use crate::error::{Error, Result};
+/*
+ *#########################################
+ * File responsible for containing the key
+ * generation for Ed25519 signatures, used on
+ * the host and user authentication.
+ *
+ * The keys are much smaller and faster to
+ * generate than the RSA ones, where the
+ * private key is a PKCS#8 PEM and the public
+ * key is a SubjectPublicKeyInfo PEM.
+ * ########################################
+ */
+
+const ERROR_PRIVATE_KEY_CONVERSION :&str = "Error converting the Ed25519 private key";
+const ERROR_PUBLIC_KEY_CONVERSION : &str = "Error converting the Ed25519 public key";
+const INVALID_SIGNATURE_ERROR : &str = "Invalid Ed25519 signature";
+
+pub struct Ed25519Keys{
+    pub private_key : SigningKey,
+    pub public_key : VerifyingKey,
+}
+
+impl Ed25519Keys {
+
+    pub fn new() -> Self{
+
+        let private_key = SigningKey::generate(&mut OsRng);
+        let public_key = private_key.verifying_key();
+
+        Self { private_key, public_key }
+    }
+
+    //Converts the keys to a pem format
+    pub fn to_pem(&self) -> (Zeroizing<String>,String){
+
+        let private_pem = self.private_key.to_pkcs8_pem(LineEnding::LF).expect(ERROR_PRIVATE_KEY_CONVERSION);
+        let public_pem = self.public_key.to_public_key_pem(LineEnding::LF).expect(ERROR_PUBLIC_KEY_CONVERSION);
+
+        (private_pem,public_pem)
+    }
+
+    //Validates a public key PEM, returns true or false if is valid
+    pub fn is_valid_pem(pem : &str) -> bool{
+        VerifyingKey::from_public_key_pem(pem).is_ok()
+    }
+
+    //Validates a private key PEM
+    pub fn is_valid_private_pem(pem : &str) -> bool{
+        SigningKey::from_pkcs8_pem(pem).is_ok()
+    }
+
+    //The strict verification also rejects the weak public keys and malleable signatures
+    pub fn is_valid_signature(public_pem: &str, bytes: &[u8], signature: &[u8]) -> Result<()>{
+
+        let public_key = VerifyingKey::from_public_key_pem(public_pem).map_err(|_| Error::Static(ERROR_PUBLIC_KEY_CONVERSION))?;
+        let signature = Signature::from_slice(signature).map_err(|_| Error::Static(INVALID_SIGNATURE_ERROR))?;
+
+        public_key.verify_strict(bytes, &signature).map_err(|_| Error::Static(INVALID_SIGNATURE_ERROR))
+    }
+
+    //Gets the public key PEM of a private key PEM
+    pub fn public_pem_from_private(private_pem: &str) -> Result<String>{
+
+        let private_key = SigningKey::from_pkcs8_pem(private_pem).map_err(|_| Error::Static(ERROR_PRIVATE_KEY_CONVERSION))?;
+        let public_pem = private_key.verifying_key().to_public_key_pem(LineEnding::LF).map_err(|_| Error::Static(ERROR_PUBLIC_KEY_CONVERSION))?;
+
+        Ok(public_pem.trim().to_string())
+    }
+
+    //Signs the bytes with the private key
+    pub fn sign(private_pem: &str, bytes: &[u8]) -> Result<Vec<u8>>{
+
+        let private_key = SigningKey::from_pkcs8_pem(private_pem).map_err(|_| Error::Static(ERROR_PRIVATE_KEY_CONVERSION))?;
+
+        Ok(private_key.sign(bytes).to_bytes().to_vec())
+    }
+}
+
+impl Default for Ed25519Keys {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,103 @@
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use crate::crypto::algorithms::KexAlgorithm;
+use crate::crypto::dhkeys::{DHKeys, ExchangedKeys};
+use crate::crypto::x25519::X25519Keys;
+use crate::error::Result;
+/*
+ *######################################################
+ * The key exchange, negotiated on the handshake,
+ * which may be:
+ *
+ * x25519-sha256 - Diffie-Hellman on the Curve25519, x25519.rs
+ * dh-rfc3526-sha256 - Finite field Diffie-Hellman, dhkeys.rs
+ *
+ * The client sends his public values, of the negotiated
+ * algorithm, and the server answers with his public key,
+ * both give the shared key K, for the SessionKeys.
+ *
+ * A rekey sends the same public values, by both machines,
+ * where the DH group is fixed, so the keys match even if
+ * both machines start the rekey at the same time.
+ *######################################################
+ */
+
+//The public values of the client, which say the algorithm
+#[derive(Clone, Serialize, Deserialize)]
+pub enum ClientKexKeys {
+    X25519(Vec<u8>),
+    DiffieHellman(ExchangedKeys),
+}
+
+pub enum KexKeys {
+    X25519(X25519Keys),
+    DiffieHellman(DHKeys),
+}
+
+impl ClientKexKeys {
+
+    pub fn get_algorithm(&self) -> KexAlgorithm {
+        match self {
+            ClientKexKeys::X25519(_) => KexAlgorithm::X25519,
+            ClientKexKeys::DiffieHellman(_) => KexAlgorithm::DiffieHellman,
+        }
+    }
+
+    pub fn get_public_key(&self) -> Vec<u8> {
+        match self {
+            ClientKexKeys::X25519(public_key) => public_key.clone(),
+            ClientKexKeys::DiffieHellman(keys) => keys.get_public_key().to_bytes_be(),
+        }
+    }
+}
+
+impl KexKeys {
+
+    //Creates the client keys, with the chosen algorithm
+    pub fn new(algorithm: KexAlgorithm) -> Self {
+        match algorithm {
+            KexAlgorithm::X25519 => KexKeys::X25519(X25519Keys::new()),
+            KexAlgorithm::DiffieHellman => KexKeys::DiffieHellman(DHKeys::new()),
+        }
+    }
+
+    //Creates the keys which start a rekey
+    pub fn for_rekey(algorithm: KexAlgorithm) -> Self {
+        match algorithm {
+            KexAlgorithm::X25519 => KexKeys::X25519(X25519Keys::new()),
+            KexAlgorithm::DiffieHellman => KexKeys::DiffieHellman(DHKeys::from_fixed_group()),
+        }
+    }
+
+    //Creates the server keys, with the algorithm and the group chosen by the client
+    pub fn from_client_keys(client_keys: &ClientKexKeys) -> Result<Self> {
+        match client_keys {
+            ClientKexKeys::X25519(_) => Ok(KexKeys::X25519(X25519Keys::new())),
+            ClientKexKeys::DiffieHellman(keys) => Ok(KexKeys::DiffieHellman(DHKeys::from_exchanged_keys(keys)?)),
+        }
+    }
+
+    //The values sent by the client
+    pub fn get_client_keys(&self) -> ClientKexKeys {
+        match self {
+            KexKeys::X25519(keys) => ClientKexKeys::X25519(keys.get_public_key().to_vec()),
+            KexKeys::DiffieHellman(keys) => ClientKexKeys::DiffieHellman(keys.get_exchanged_keys().clone()),
+        }
+    }
+
+    //The public key sent by the server, the group is already known
+    pub fn get_public_key(&self) -> Vec<u8> {
+        match self {
+            KexKeys::X25519(keys) => keys.get_public_key().to_vec(),
+            KexKeys::DiffieHellman(keys) => keys.get_exchanged_keys().get_public_key().to_bytes_be(),
+        }
+    }
+
+    //Computes the shared key with the other machine public key
+    pub fn compute_shared_key(self, public_key: &[u8]) -> Result<BigUint> {
+        match self {
+            KexKeys::X25519(keys) => keys.compute_shared_key(public_key),
+            KexKeys::DiffieHellman(keys) => keys.compute_shared_key(&BigUint::from_bytes_be(public_key)),
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use rsa::pkcs8::der::zeroize::Zeroizing;
+
+use crate::crypto::ed25519::Ed25519Keys;
+use crate::crypto::rsa::RSAKeys;
+/*
+ *#########################################
+ * The types of the host and user keys:
+ *
+ * rsa - RSA of 4096 bits, PKCS#1 PEM
+ * ed25519 - Ed25519, PKCS#8 and SPKI PEM
+ *
+ * The type of a key is known by his PEM,
+ * and written as a tag at the known hosts.
+ * ########################################
+ */
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum KeyType {
+    Ed25519,
+    Rsa,
+}
+
+impl KeyType {
+
+    pub fn all() -> &'static [KeyType] {
+        &[KeyType::Ed25519, KeyType::Rsa]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            KeyType::Ed25519 => "ed25519",
+            KeyType::Rsa => "rsa",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::all().iter().copied().find(|key_type| key_type.name() == name)
+    }
+
+    //The type of a public key PEM, None if it is not a valid key
+    pub fn of_public_pem(pem: &str) -> Option<Self> {
+        if RSAKeys::is_valid_pem(pem) {
+            Some(KeyType::Rsa)
+        } else if Ed25519Keys::is_valid_pem(pem) {
+            Some(KeyType::Ed25519)
+        } else {
+            None
+        }
+    }
+
+    //The type of a private key PEM, None if it is not a valid key
+    pub fn of_private_pem(pem: &str) -> Option<Self> {
+        if RSAKeys::is_valid_private_pem(pem) {
+            Some(KeyType::Rsa)
+        } else if Ed25519Keys::is_valid_private_pem(pem) {
+            Some(KeyType::Ed25519)
+        } else {
+            None
+        }
+    }
+
+    //Generates a new pair of keys, returning the private and public PEM
+    pub fn generate(&self) -> (Zeroizing<String>, String) {
+        match self {
+            KeyType::Ed25519 => Ed25519Keys::new().to_pem(),
+            KeyType::Rsa => RSAKeys::new().to_pem(),
+        }
+    }
+}
@@ -3,16 +3,27 @@ use num_bigint::RandBigInt;
 use num_traits::{Num, One};
 use rand::seq::SliceRandom;
 use rand::distributions::{Alphanumeric,DistString};
+use ::rsa::sha2::{Digest, Sha256};
 use crate::crypto::dhkeys::DHKeys;
 use crate::crypto::session_keys::SessionKeys;
-use crate::error::Result;
+use crate::error::{Error, Result};
+use ::rsa::pkcs8::der::zeroize::Zeroizing;
 
 use crate::crypto::rsa::RSAKeys;
+use crate::crypto::ed25519::Ed25519Keys;
+use crate::crypto::key_type::KeyType;
+use crate::crypto::algorithms::{Algorithms, HostKeyAlgorithm};
 use crate::crypto::dhprimes::GROUPS;
 
 mod dhprimes;
 mod rsa;
+mod ed25519;
+pub mod key_type;
+pub mod signature;
 pub mod dhkeys;
+pub mod x25519;
+pub mod kex;
+pub mod algorithms;
 pub mod session_keys;
 /*
  *###############################################
@@ -39,8 +50,26 @@ pub fn choose_random_prime() -> (BigUint,BigUint){
     let mut rng = rand::thread_rng();
     let values = GROUPS.choose(&mut rng).unwrap();
     
+    group_to_biguint(values)
+}
+
+//The group used when both machines must agree without choosing it, as on a rekey
+pub fn fixed_prime() -> (BigUint,BigUint){
+    group_to_biguint(&GROUPS[0])
+}
+
+//Checks if a prime and generator are one of the pre-computed groups,
+//so the other machine cannot force a weak group
+pub fn is_known_group(prime: &BigUint, generator: &BigUint) -> bool{
+    GROUPS.iter()
+        .map(group_to_biguint)
+        .any(|(p, g)| p == *prime && g == *generator)
+}
+
+fn group_to_biguint(values: &(&str, u32)) -> (BigUint,BigUint){
+
     let hex = values.0.replace([' ', '\n'], ""); //Clears the prime string
-    let prime = BigUint::from_str_radix(&hex, 16).expect("Invalid Hex Prime");
+    let prime = BigUint::from_str_radix(&hex, HEX_RADIX).expect("Invalid Hex Prime");
 
     (prime, BigUint::from(values.1))
 }
@@ -49,27 +78,83 @@ pub fn choose_random_prime() -> (BigUint,BigUint){
  *  A subgroup of a number N, is the interval of [1,N-1]
  */
 pub fn is_subgroup_of(group_max : &BigUint, value : &BigUint ) -> bool{
-    *value < BigUint::one() || *value >= *group_max
+    *value >= BigUint::one() && *value < *group_max
 }
 
-//Generetes RSA Keys a public key and a private
-pub fn generate_rsa_keys()-> RSAKeys{
-    RSAKeys::new()
+const INVALID_KEY_ERROR: &str = "The key is not a valid key of the signature algorithm";
+
+//Generates a public key and a private, of the type, returning both PEM
+pub fn generate_keys(key_type: KeyType) -> (Zeroizing<String>, String){
+    key_type.generate()
 }
 
-//Validates a PEM to check if is a valid public key 
+//Validates a PEM to check if is a valid public key, of any type
 pub fn is_valid_public_key_pem(pem: &str) -> bool{
-    RSAKeys::is_valid_pem(pem)
+    KeyType::of_public_pem(pem).is_some()
 }
 
 pub fn generate_random_string(size: usize) -> String{
     Alphanumeric.sample_string(&mut rand::thread_rng(), size)
 }
 
-pub fn is_valid_signature_sha256(public_pem: &str, bytes: &[u8], signature: &[u8]) -> Result<()>{
-    RSAKeys::is_valid_signature_sha256(public_pem, bytes, signature)
+//Verifies a signature with a public key in PEM format, which must be of the algorithm key type
+pub fn is_valid_signature(algorithm: HostKeyAlgorithm, public_pem: &str, bytes: &[u8], signature: &[u8]) -> Result<()>{
+
+    if KeyType::of_public_pem(public_pem) != Some(algorithm.key_type()) {
+        return Err(Error::Static(INVALID_KEY_ERROR));
+    }
+
+    algorithm.scheme().verify(public_pem, bytes, signature)
+}
+
+//Gets the public key PEM of a private key PEM
+pub fn public_key_pem_from_private(private_pem: &str) -> Result<String>{
+    match KeyType::of_private_pem(private_pem) {
+        Some(KeyType::Rsa) => RSAKeys::public_pem_from_private(private_pem),
+        Some(KeyType::Ed25519) => Ed25519Keys::public_pem_from_private(private_pem),
+        None => Err(Error::Static(INVALID_KEY_ERROR)),
+    }
+}
+
+//A short name of a public key PEM, as SHA256:hex of the PEM, to tell keys apart
+pub fn fingerprint(public_pem: &str) -> String{
+
+    let digest = Sha256::digest(public_pem.trim().as_bytes());
+
+    format!("SHA256:{}", digest.iter().map(|byte| format!("{:02x}", byte)).collect::<String>())
+}
+
+//Signs the bytes with a private key in PEM format, which must be of the algorithm key type
+pub fn sign(algorithm: HostKeyAlgorithm, private_pem: &str, bytes: &[u8]) -> Result<Vec<u8>>{
+
+    if KeyType::of_private_pem(private_pem) != Some(algorithm.key_type()) {
+        return Err(Error::Static(INVALID_KEY_ERROR));
+    }
+
+    algorithm.scheme().sign(private_pem, bytes)
 }
 
-pub fn generate_session_keys(session_hash: &Vec<u8>, shared_key : BigUint) -> SessionKeys{
-    SessionKeys::new(session_hash, shared_key)
+pub fn generate_session_keys(session_hash: &[u8], shared_key : BigUint, algorithms: &Algorithms) -> SessionKeys{
+    SessionKeys::new(session_hash, shared_key, algorithms)
+}
+
+/*
+ * The session hash is unique for each session, computed by both machines
+ *
+ * H = HASH(shared key || Server public key || user || negotiation || client kex public || server kex public)
+ *
+ * The negotiation are the client and server Negotiation messages, as sent,
+ * and the server signs H with his host key, so the key exchange is his
+ */
+pub fn compute_session_hash(shared_key : &BigUint, public_key_pem : &str, user : &str, negotiation : &[u8], client_kex_public : &[u8], server_kex_public : &[u8]) -> Vec<u8>{
+
+    let mut hasher = Sha256::new();
+    hasher.update(shared_key.to_bytes_be());
+    hasher.update(public_key_pem.as_bytes());
+    hasher.update(user.as_bytes());
+    hasher.update(negotiation);
+    hasher.update(client_kex_public);
+    hasher.update(server_kex_public);
+
+    hasher.finalize().to_vec()
 }
@@ -1,10 +1,9 @@
 use rsa::pkcs8::der::zeroize::Zeroizing;
-use rsa::sha2::{Digest, Sha256};
+use rsa::sha2::Digest;
+use rsa::traits::SignatureScheme;
 use rsa::{ RsaPrivateKey, RsaPublicKey};
-use rsa::pkcs1::{DecodeRsaPublicKey, EncodeRsaPrivateKey, EncodeRsaPublicKey};
-use rsa::Pkcs1v15Sign;
+use rsa::pkcs1::{DecodeRsaPrivateKey, DecodeRsaPublicKey, EncodeRsaPrivateKey, EncodeRsaPublicKey};
 
-use crate::crypto::is_valid_public_key_pem;
 use crate::error::{Result,Error};
 /*
  *#########################################
@@ -12,7 +11,9 @@ use crate::error::{Result,Error};
  * generation for RSA Encryption used on
  * authentication.
  *
- * Will be generated a public key and a private key
+ * Will be generated a public key and a private key,
+ * the signatures may use the PKCS#1 v1.5 or the
+ * PSS padding, as in signature.rs
  * ########################################
  */
 
@@ -29,6 +30,7 @@ pub struct RSAKeys{
 
 impl RSAKeys {
     
+    #[allow(clippy::new_without_default)]
     pub fn new() -> Self{
         
         let mut rng = rand::thread_rng();
@@ -56,19 +58,52 @@ impl RSAKeys {
 
     }
 
-    pub fn is_valid_signature_sha256(public_pem: &str, bytes: &[u8], signature: &[u8]) -> Result<()>{
+    //Validates a private key PEM
+    pub fn is_valid_private_pem(pem : &str) -> bool{
 
+        RsaPrivateKey::from_pkcs1_pem(pem).is_ok()
 
-        if !is_valid_public_key_pem(public_pem){
+    }
+
+    /*
+     * Verifies a signature of the hashed bytes, with the padding scheme,
+     * as Pkcs1v15Sign or Pss, of the same digest D
+     */
+    pub fn is_valid_signature<D: Digest, S: SignatureScheme>(public_pem: &str, bytes: &[u8], signature: &[u8], scheme: S) -> Result<()>{
+
+
+        if !Self::is_valid_pem(public_pem){
             return Err(Error::Static(ERROR_PUBLIC_KEY_CONVERSION))
         }
 
         let public_key = RsaPublicKey::from_pkcs1_pem(public_pem)?;
 
-        let hashed = Sha256::digest(bytes);
-        public_key.verify(Pkcs1v15Sign::new::<Sha256>(), &hashed, signature)?;
+        let hashed = D::digest(bytes);
+        public_key.verify(scheme, &hashed, signature)?;
     
         Ok(())
     }
 
+    //Gets the public key PEM of a private key PEM
+    pub fn public_pem_from_private(private_pem: &str) -> Result<String>{
+
+        let private_key = RsaPrivateKey::from_pkcs1_pem(private_pem)?;
+        let public_pem = RsaPublicKey::from(&private_key).to_pkcs1_pem(rsa::pkcs1::LineEnding::LF)?;
+
+        Ok(public_pem.trim().to_string())
+    }
+
+    //Signs the hashed bytes with the private key, with the padding scheme of the same digest D
+    pub fn sign<D: Digest, S: SignatureScheme>(private_pem: &str, bytes: &[u8], scheme: S) -> Result<Vec<u8>>{
+
+        let private_key = RsaPrivateKey::from_pkcs1_pem(private_pem)?;
+
+        let hashed = D::digest(bytes);
+
+        //The PSS salt is random, PKCS#1 v1.5 does not use the generator
+        let signature = private_key.sign_with_rng(&mut rand::thread_rng(), scheme, &hashed)?;
+
+        Ok(signature)
+    }
+
 }
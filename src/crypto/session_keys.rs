@@ -2,26 +2,45 @@
  * ###################################################
  * File responsible for containing the keys struct,
  * which will be used for the communication encryption,
- * this keys will be:
+ * this keys are derived with HKDF-SHA256 (RFC 5869),
+ * where the shared key K is the input key material,
+ * the session hash H is the salt and a letter is the
+ * info of each key:
  *
- * Client -> Server Encryption = Hash(H || K || "A")
- * Server -> Client Encryption = Hash(H || K || "B")
+ * Client -> Server Encryption = HKDF(H, K, "A")
+ * Server -> Client Encryption = HKDF(H, K, "B")
  *
  * Used for encryption e decryption.
  *
- * Client -> Server Integrity = Hash(H || K || "C")
- * Server -> Client Integrity = Hash(H || K || "D")
+ * Client -> Server Integrity = HKDF(H, K, "C")
+ * Server -> Client Integrity = HKDF(H, K, "D")
  *
- * This keys are the MAC, which verifies the Integrity
- * of the packet.
+ * This keys are the MAC keys, which verify the Integrity
+ * of the packet, encrypted then MACed, as in transport.rs:
  *
- * MAC = Hash(sequence number | Decripted packet)
+ * Encrypt(length || payload) || HMAC(mac key, sequence number || length || payload)
+ *
+ * Client -> Server IV = HKDF(H, K, "E")
+ * Server -> Client IV = HKDF(H, K, "F")
+ *
+ * The initial counter of CTR, or the nonce of an AEAD
+ * cipher, as ChaCha20-Poly1305, which authenticates by
+ * itself and does not use the integrity keys:
+ *
+ * length || AEAD(payload, associated data = length) || tag
+ *
+ * nonce = IV XOR sequence number
+ *
+ * Each key has the size required by the negotiated
+ * algorithms, as the HKDF output may have any size.
  * ###################################################
  */
 
+use hkdf::Hkdf;
 use num_bigint::BigUint;
-use rsa::sha2::{Sha256, Digest};
-use crate::session::protocol::{CLIENT_SERVER_ENCRYPTION_BYTE,SERVER_CLIENT_ENCRYPTION_BYTE,CLIENT_SERVER_INTEGRITY_BYTE,SERVER_CLIENT_INTEGIRTY_BYTE};
+use rsa::sha2::Sha256;
+use crate::crypto::algorithms::Algorithms;
+use crate::session::protocol::{CLIENT_SERVER_ENCRYPTION_BYTE,SERVER_CLIENT_ENCRYPTION_BYTE,CLIENT_SERVER_INTEGRITY_BYTE,SERVER_CLIENT_INTEGIRTY_BYTE,CLIENT_SERVER_IV_BYTE,SERVER_CLIENT_IV_BYTE};
 
 
 pub struct SessionKeys{
@@ -29,36 +48,124 @@ pub struct SessionKeys{
     pub server_client_enc_key : Vec<u8>,
     pub client_server_mac_key : Vec<u8>,
     pub server_client_mac_key : Vec<u8>,
+    pub client_server_iv : Vec<u8>,
+    pub server_client_iv : Vec<u8>,
 }
 
 impl SessionKeys {
-    
-    pub fn new(session_hash: &Vec<u8>, shared_key : BigUint) -> Self{
+
+    pub fn new(session_hash: &[u8], shared_key : BigUint, algorithms: &Algorithms) -> Self{
 
 
         let shared_key_bytes = shared_key.to_bytes_be();
-        
-        let client_server_enc_key = Self::derive_key(session_hash,&shared_key_bytes,CLIENT_SERVER_ENCRYPTION_BYTE);
-        let server_client_enc_key = Self::derive_key(session_hash,&shared_key_bytes,SERVER_CLIENT_ENCRYPTION_BYTE);
-        let client_server_mac_key = Self::derive_key(session_hash,&shared_key_bytes,CLIENT_SERVER_INTEGRITY_BYTE);
-        let server_client_mac_key = Self::derive_key(session_hash,&shared_key_bytes,SERVER_CLIENT_INTEGIRTY_BYTE);
 
-        Self {client_server_enc_key, server_client_enc_key, client_server_mac_key, server_client_mac_key}
+        let hkdf = Hkdf::<Sha256>::new(Some(session_hash), &shared_key_bytes);
+
+        let enc_size = algorithms.cipher.key_size();
+        let mac_size = algorithms.mac.key_size();
+        let iv_size = algorithms.cipher.iv_size();
+
+        let client_server_enc_key = Self::derive_key(&hkdf,CLIENT_SERVER_ENCRYPTION_BYTE,enc_size);
+        let server_client_enc_key = Self::derive_key(&hkdf,SERVER_CLIENT_ENCRYPTION_BYTE,enc_size);
+        let client_server_mac_key = Self::derive_key(&hkdf,CLIENT_SERVER_INTEGRITY_BYTE,mac_size);
+        let server_client_mac_key = Self::derive_key(&hkdf,SERVER_CLIENT_INTEGIRTY_BYTE,mac_size);
+        let client_server_iv = Self::derive_key(&hkdf,CLIENT_SERVER_IV_BYTE,iv_size);
+        let server_client_iv = Self::derive_key(&hkdf,SERVER_CLIENT_IV_BYTE,iv_size);
+
+        Self {client_server_enc_key, server_client_enc_key, client_server_mac_key, server_client_mac_key, client_server_iv, server_client_iv}
 
 
     }
 
-    fn derive_key(h : &Vec<u8>, k: &Vec<u8>, constant: u8) -> Vec<u8>{
+    fn derive_key(hkdf: &Hkdf<Sha256>, constant: u8, size: usize) -> Vec<u8>{
+        expand(hkdf, &[constant], size)
+    }
+}
 
-        let mut hasher = Sha256::new();
+//The HKDF output is limited to 255 hashes, far above any key size
+fn expand(hkdf: &Hkdf<Sha256>, info: &[u8], size: usize) -> Vec<u8>{
 
-        hasher.update(h);
-        hasher.update(k);
-        hasher.update([constant]);
+    let mut key = vec![0u8; size];
+    hkdf.expand(info, &mut key).expect("The key size is within the HKDF limit");
 
+    key
+}
 
-        hasher.finalize().to_vec()
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::algorithms::{Algorithm, AlgorithmLists};
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
     }
-}
 
+    fn algorithms_with_cipher(cipher: &str) -> Algorithms {
+        let lists = AlgorithmLists { cipher: vec![cipher.to_string()], ..Default::default() };
+        lists.negotiate(&AlgorithmLists::default()).unwrap()
+    }
 
+    //RFC 5869, test case 1
+    #[test]
+    fn hkdf_basic_vector() {
+        let hkdf = Hkdf::<Sha256>::new(Some(&hex("000102030405060708090a0b0c")), &[0x0b; 22]);
+
+        assert_eq!(expand(&hkdf, &hex("f0f1f2f3f4f5f6f7f8f9"), 42),
+            hex("3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865"));
+    }
+
+    //RFC 5869, test case 2, longer inputs and output
+    #[test]
+    fn hkdf_long_vector() {
+        let ikm: Vec<u8> = (0x00..=0x4f).collect();
+        let salt: Vec<u8> = (0x60..=0xaf).collect();
+        let info: Vec<u8> = (0xb0..=0xff).collect();
+
+        let hkdf = Hkdf::<Sha256>::new(Some(&salt), &ikm);
+
+        assert_eq!(expand(&hkdf, &info, 82), hex(concat!(
+            "b11e398dc80327a1c8e7f78c596a49344f012eda2d4efad8a050cc4c19afa97c",
+            "59045a99cac7827271cb41c65e590e09da3275600c2f09b8367793a9aca3db71",
+            "cc30c58179ec3e87c14c01d5c1f3434f1d87")));
+    }
+
+    //RFC 5869, test case 3, without salt and info
+    #[test]
+    fn hkdf_empty_salt_vector() {
+        let hkdf = Hkdf::<Sha256>::new(None, &[0x0b; 22]);
+
+        assert_eq!(expand(&hkdf, &[], 42),
+            hex("8da4e775a563c18f715f802a063c5a31b8a11f5c5ee1879ec3454e5f3c738d2d9d201395faa4b61a96c8"));
+    }
+
+    //The session keys are the HKDF output of each letter
+    #[test]
+    fn session_keys_use_letters_as_info() {
+        let session_hash = [0x42u8; 32];
+        let shared_key = BigUint::from(0x0102030405u64);
+        let keys = SessionKeys::new(&session_hash, shared_key.clone(), &algorithms_with_cipher("aes256-ctr"));
+
+        let hkdf = Hkdf::<Sha256>::new(Some(&session_hash), &shared_key.to_bytes_be());
+
+        assert_eq!(keys.client_server_enc_key, expand(&hkdf, b"A", 32));
+        assert_eq!(keys.server_client_enc_key, expand(&hkdf, b"B", 32));
+        assert_eq!(keys.client_server_mac_key, expand(&hkdf, b"C", 32));
+        assert_eq!(keys.server_client_mac_key, expand(&hkdf, b"D", 32));
+        assert_eq!(keys.client_server_iv, expand(&hkdf, b"E", 16));
+        assert_eq!(keys.server_client_iv, expand(&hkdf, b"F", 16));
+    }
+
+    #[test]
+    fn session_keys_have_the_cipher_sizes() {
+        for cipher in crate::crypto::algorithms::CipherAlgorithm::all() {
+            let algorithms = algorithms_with_cipher(cipher.name());
+            let keys = SessionKeys::new(&[1u8; 32], BigUint::from(7u8), &algorithms);
+
+            assert_eq!(keys.client_server_enc_key.len(), cipher.key_size());
+            assert_eq!(keys.server_client_enc_key.len(), cipher.key_size());
+            assert_eq!(keys.client_server_iv.len(), cipher.iv_size());
+            assert_eq!(keys.server_client_iv.len(), cipher.iv_size());
+            assert_ne!(keys.client_server_iv, keys.server_client_iv);
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use ::rsa::sha2::{Sha256, Sha512};
+use ::rsa::{Pkcs1v15Sign, Pss};
+
+use crate::crypto::ed25519::Ed25519Keys;
+use crate::crypto::key_type::KeyType;
+use crate::crypto::rsa::RSAKeys;
+use crate::error::Result;
+/*
+ *#########################################
+ * The signature schemes, used by the server
+ * and by the user to sign the session hash:
+ *
+ * ed25519 - Ed25519
+ * rsa-pss-sha256 - RSA with PSS padding, SHA-256
+ * rsa-sha512 - RSA with PKCS#1 v1.5 padding, SHA-512
+ * rsa-sha256 - RSA with PKCS#1 v1.5 padding, SHA-256
+ *
+ * Each one signs and verifies with the keys
+ * in PEM format, of his key type.
+ * ########################################
+ */
+
+pub trait SignatureScheme: Sync {
+
+    //The type of the keys which sign with this scheme
+    fn key_type(&self) -> KeyType;
+
+    fn sign(&self, private_pem: &str, bytes: &[u8]) -> Result<Vec<u8>>;
+
+    fn verify(&self, public_pem: &str, bytes: &[u8], signature: &[u8]) -> Result<()>;
+}
+
+pub struct Ed25519Scheme;
+pub struct RsaPssSha256Scheme;
+pub struct RsaSha512Scheme;
+pub struct RsaSha256Scheme;
+
+impl SignatureScheme for Ed25519Scheme {
+
+    fn key_type(&self) -> KeyType {
+        KeyType::Ed25519
+    }
+
+    fn sign(&self, private_pem: &str, bytes: &[u8]) -> Result<Vec<u8>> {
+        Ed25519Keys::sign(private_pem, bytes)
+    }
+
+    fn verify(&self, public_pem: &str, bytes: &[u8], signature: &[u8]) -> Result<()> {
+        Ed25519Keys::is_valid_signature(public_pem, bytes, signature)
+    }
+}
+
+impl SignatureScheme for RsaPssSha256Scheme {
+
+    fn key_type(&self) -> KeyType {
+        KeyType::Rsa
+    }
+
+    fn sign(&self, private_pem: &str, bytes: &[u8]) -> Result<Vec<u8>> {
+        RSAKeys::sign::<Sha256, _>(private_pem, bytes, Pss::new::<Sha256>())
+    }
+
+    fn verify(&self, public_pem: &str, bytes: &[u8], signature: &[u8]) -> Result<()> {
+        RSAKeys::is_valid_signature::<Sha256, _>(public_pem, bytes, signature, Pss::new::<Sha256>())
+    }
+}
+
+impl SignatureScheme for RsaSha512Scheme {
+
+    fn key_type(&self) -> KeyType {
+        KeyType::Rsa
+    }
+
+    fn sign(&self, private_pem: &str, bytes: &[u8]) -> Result<Vec<u8>> {
+        RSAKeys::sign::<Sha512, _>(private_pem, bytes, Pkcs1v15Sign::new::<Sha512>())
+    }
+
+    fn verify(&self, public_pem: &str, bytes: &[u8], signature: &[u8]) -> Result<()> {
+        RSAKeys::is_valid_signature::<Sha512, _>(public_pem, bytes, signature, Pkcs1v15Sign::new::<Sha512>())
+    }
+}
+
+impl SignatureScheme for RsaSha256Scheme {
+
+    fn key_type(&self) -> KeyType {
+        KeyType::Rsa
+    }
+
+    fn sign(&self, private_pem: &str, bytes: &[u8]) -> Result<Vec<u8>> {
+        RSAKeys::sign::<Sha256, _>(private_pem, bytes, Pkcs1v15Sign::new::<Sha256>())
+    }
+
+    fn verify(&self, public_pem: &str, bytes: &[u8], signature: &[u8]) -> Result<()> {
+        RSAKeys::is_valid_signature::<Sha256, _>(public_pem, bytes, signature, Pkcs1v15Sign::new::<Sha256>())
+    }
+}
@@ -0,0 +1,73 @@
+use num_bigint::BigUint;
+use rand::rngs::OsRng;
+use x25519_dalek::{EphemeralSecret, PublicKey};
+use crate::error::{Error, Result};
+/*
+ *######################################################
+ * X25519 Key Exchange, the Diffie-Hellman on the
+ * Curve25519, much faster than the finite field one,
+ * with 32 bytes keys.
+ *
+ * The keys are an:
+ * a - Private Key, a random scalar of 32 bytes
+ * A - Public Key, a point of 32 bytes
+ * K - Exchanged Key
+ *
+ * A = a * G, where G is the curve base point
+ *
+ * K = a * B
+ *
+ * As the private key is ephemeral, it is used only
+ * once, to compute K.
+ *######################################################
+ */
+
+pub const PUBLIC_KEY_SIZE: usize = 32;
+
+const INVALID_PUBLIC_KEY_ERROR: &str = "Received Invalid X25519 Public Key";
+
+pub struct X25519Keys {
+    private_key: EphemeralSecret,
+    public_key: PublicKey,
+}
+
+impl X25519Keys {
+
+    //Creates a new pair of keys, with the OS random generator
+    pub fn new() -> Self {
+
+        let private_key = EphemeralSecret::random_from_rng(OsRng);
+        let public_key = PublicKey::from(&private_key);
+
+        Self { private_key, public_key }
+    }
+
+    pub fn get_public_key(&self) -> [u8; PUBLIC_KEY_SIZE] {
+        self.public_key.to_bytes()
+    }
+
+    /*
+     * Computes the shared key, consuming the private key,
+     * a public key of small order gives a zero shared key
+     * which is not contributory, so it is an error
+     */
+    pub fn compute_shared_key(self, public_key: &[u8]) -> Result<BigUint> {
+
+        let public_key: [u8; PUBLIC_KEY_SIZE] = public_key.try_into()
+            .map_err(|_| Error::Static(INVALID_PUBLIC_KEY_ERROR))?;
+
+        let shared_key = self.private_key.diffie_hellman(&PublicKey::from(public_key));
+
+        if !shared_key.was_contributory() {
+            return Err(Error::Static(INVALID_PUBLIC_KEY_ERROR));
+        }
+
+        Ok(BigUint::from_bytes_be(shared_key.as_bytes()))
+    }
+}
+
+impl Default for X25519Keys {
+    fn default() -> Self {
+        Self::new()
+    }
+}
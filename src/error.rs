@@ -1,5 +1,3 @@
-use std::fmt::write;
-
 /*
  * ########################################################
  * Errors enum for a better code base organization,
@@ -8,6 +6,13 @@ use std::fmt::write;
  * Io - IO errors
  * Str - Error for parsing UTF8
  * Static - For defined errors
+ * UnknownMessage - For a message type byte not defined
+ * Codec - For messages which cannot be encoded or decoded
+ * IncompatibleVersion - For a peer protocol version, with ours and his
+ * ChannelOpenFailure - For a channel the other machine did not open, with his reason
+ * ForwardFailure - For a port the server did not forward, with his reason
+ * Transfer - For a file request the transfer server failed, with his reason
+ * Agent - For a request the authentication agent failed, with his reason
  * 
  * Also has Result<T> which is the same as Result<T,Error>
  * ########################################################
@@ -19,6 +24,19 @@ pub enum Error {
     Static(&'static str),
     CryptoRSA(rsa::Error),
     CryptoPkcs1(rsa::pkcs1::Error),
+    UnknownMessage(u8),
+    Codec(bincode::Error),
+    IncompatibleVersion(String, String),
+    ChannelOpenFailure(String),
+    ForwardFailure(String),
+    Transfer(String),
+    Agent(String),
+}
+
+impl From<bincode::Error> for Error {
+    fn from(e: bincode::Error) -> Self {
+        Error::Codec(e)
+    }
 }
 
 impl From<rsa::pkcs1::Error> for Error {
@@ -60,6 +78,13 @@ impl std::fmt::Display for Error {
             Error::Str(e) => write!(f, "Error: {}",e),
             Error::CryptoRSA(e) => write!(f,"Error: {}", e),
             Error::CryptoPkcs1(e) => write!(f,"Error: {}",e),
+            Error::UnknownMessage(e) => write!(f,"Error: Unknown message type {}",e),
+            Error::Codec(e) => write!(f,"Error: Invalid message {}",e),
+            Error::IncompatibleVersion(ours, theirs) => write!(f,"Error: Incompatible protocol version {}, ours is {}",theirs,ours),
+            Error::ChannelOpenFailure(reason) => write!(f,"Error: The channel could not be opened, {}",reason),
+            Error::ForwardFailure(reason) => write!(f,"Error: The port could not be forwarded, {}",reason),
+            Error::Transfer(reason) => write!(f,"Error: {}",reason),
+            Error::Agent(reason) => write!(f,"Error: The agent failed, {}",reason),
         }
     }
 }
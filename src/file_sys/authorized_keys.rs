@@ -0,0 +1,78 @@
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::file_sys::path::authorized_keys_path;
+/*
+ * #########################################################
+ * Contains the logic behind verifying if a user key
+ * is allowed to login, by searching it on the user
+ * ~/.sssh/authorized_keys.
+ *
+ * The file will store the keys by the following rules:
+ *
+ * PUBLIC_KEY_A\n\nPUBLIC_KEY_B\n\n...
+ *
+ * The file, ~/.sssh and the home must be of the user, or
+ * root, and not writable by the group or others, otherwise
+ * another user could have added his key, and it is refused.
+ * #########################################################
+ */
+
+//The group and others write permissions
+const SHARED_WRITE_MODE: u32 = 0o022;
+
+const UNSAFE_AUTHORIZED_KEYS_ERROR: &str = "The authorized_keys file, ~/.sssh or the home is not of the user, or is writable by others";
+
+//Checks if the public key is at the authorized_keys of the home, of the user with the uid
+pub fn is_authorized_key(home: &Path, uid: u32, public_key_pem: &str) -> Result<bool> {
+
+    let path = authorized_keys_path(home);
+
+    //Without the file no key is allowed
+    if !path.exists() {
+        return Ok(false);
+    }
+
+    //The file, then each directory up to the home
+    let paths = path.ancestors().take_while(|ancestor| ancestor.starts_with(home));
+
+    for checked in paths {
+        if !is_safe(checked, uid)? {
+            return Err(Error::Static(UNSAFE_AUTHORIZED_KEYS_ERROR));
+        }
+    }
+
+    let reader = BufReader::new(File::open(path)?);
+
+    let mut pem_lines: Vec<String> = Vec::new();
+
+    for line in reader.lines() {
+
+        let line = line?;
+
+        // A blank line is the end of a key
+        if line.trim().is_empty() {
+            if pem_lines.join("\n") == public_key_pem.trim() {
+                return Ok(true);
+            }
+            pem_lines.clear();
+            continue;
+        }
+
+        pem_lines.push(line.trim().to_string());
+    }
+
+    //The last key may not have a blank line
+    Ok(!pem_lines.is_empty() && pem_lines.join("\n") == public_key_pem.trim())
+}
+
+//Of the user or root, and only they may write it
+fn is_safe(path: &Path, uid: u32) -> Result<bool> {
+
+    let metadata = fs::metadata(path)?;
+
+    Ok((metadata.uid() == uid || metadata.uid() == 0) && metadata.mode() & SHARED_WRITE_MODE == 0)
+}
@@ -0,0 +1,21 @@
+use std::fs;
+use std::io::ErrorKind;
+use crate::error::Result;
+use crate::file_sys::path::SSSH_SERVER_CONFIG;
+
+/*
+ * ################################################
+ * File responsible for reading the configurations,
+ * which are only read, written by the administrator
+ * ################################################
+ */
+
+//Reads the server config, which is optional
+pub fn read_server_config() -> Result<Option<String>>{
+
+    match fs::read_to_string(SSSH_SERVER_CONFIG) {
+        Ok(config) => Ok(Some(config)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -1,7 +1,7 @@
 use std::fs::{File, OpenOptions};
-use std::net::Ipv4Addr;
 use std::io::{BufReader, Write, BufRead};
 use crate::file_sys::path::known_hosts_path;
+use crate::crypto::key_type::KeyType;
 use crate::error::{Result,Error};
 use crate::file_sys::update_host_key;
 /*
@@ -14,15 +14,25 @@ use crate::file_sys::update_host_key;
  *
  * The file will store by the following rules: 
  *
- * SERVER_A_IP#\nPUBLIC_KEY_A\n\nSERVER_B_IP#\nPUBLIC_KEY_B\n\n...
+ * SERVER_A_NAMES#KEY_TYPE\nPUBLIC_KEY_A\n\nSERVER_B_NAMES#KEY_TYPE\nPUBLIC_KEY_B\n\n...
+ *
+ * Where the names are separated by ',' as the host name
+ * and his address, so a host is found by any of them.
+ *
+ * The key type is rsa or ed25519, so a host may have one
+ * key of each type, an entry without it is a rsa key.
  * #########################################################
  */
 
 const INVALID_PUBLIC_KEY_PEM_ERROR : &str = "The received public key is invalid";
+const HOST_KEY_VERIFICATION_ERROR : &str = "Host key verification failed, the host key changed";
+const HOST_KEY_TYPE_VERIFICATION_ERROR : &str = "Host key verification failed, the host has a known key of another type";
 
  const WARNING_PUBLIC_KEY_CHANGED : &str = "The following address, has a different public key from the stored one at 
 ~/.ssh/known_hosts, this could be an ATTACK, known as MITM (Man In The Middle).\n If you are sure the connection is safe you may continue at your own risk. This will overwrite the stored key by the new one if you proceed."; 
 
+ const WARNING_HOST_KEY_TYPE : &str = "The following address, has a stored public key at ~/.sssh/known_hosts of another type from the received one,
+and the server did not offer it, this could be an ATTACK, known as MITM (Man In The Middle).\n If you are sure the connection is safe you may continue at your own risk. This will store the new key if you proceed.";
 
 //Verifies if the file exists and creates it, if not exists
 fn ensure_known_hosts_file() -> Result<()> {
@@ -35,20 +45,69 @@ fn ensure_known_hosts_file() -> Result<()> {
     Ok(())
 }
 
-//Writes a new host on the file
-pub fn write_new_host(address : &Ipv4Addr, public_key_pem : &str) -> Result<()>{
+const NAMES_SEPARATOR : char = ',';
+const NAMES_END : char = '#';
+//The key type of the entries written before the key types
+const UNTAGGED_KEY_TYPE : KeyType = KeyType::Rsa;
+
+//Gets the names and key type of an entry line, None if it is not an entry line
+fn parse_entry(line : &str) -> Option<(Vec<&str>, Option<KeyType>)> {
+
+    let (names, tag) = line.trim().split_once(NAMES_END)?;
+
+    let key_type = match tag.trim() {
+        "" => Some(UNTAGGED_KEY_TYPE),
+        tag => KeyType::from_name(tag), //An unknown type is kept, but never matches
+    };
+
+    Some((names.split(NAMES_SEPARATOR).map(str::trim).collect(), key_type))
+}
+
+//Checks if the line is the names of an entry, which includes the name, of the key type
+fn is_entry_of(line : &str, name : &str, key_type : KeyType) -> bool {
+    match parse_entry(line) {
+        Some((names, entry_key_type)) => entry_key_type == Some(key_type) && names.contains(&name),
+        None => false,
+    }
+}
+
+//Writes a new host on the file, under all his names, with the key type
+pub fn write_new_host(names : &[String], key_type : KeyType, public_key_pem : &str) -> Result<()>{
 
     ensure_known_hosts_file()?;
 
     let path = known_hosts_path()?;
    let mut file = OpenOptions::new().append(true).open(path)?;
 
-   write!(file,"{}#\n{}\n\n",address,public_key_pem)?;
+   write!(file,"{}{}{}\n{}\n\n",names.join(&NAMES_SEPARATOR.to_string()),NAMES_END,key_type.name(),public_key_pem)?;
 
     Ok(())
 }
 
-pub fn get_host_public_key_by_ip(address: &Ipv4Addr) -> Result<Option<String>>{
+//Gets the key types stored for any of the names
+pub fn get_host_key_types(names: &[String]) -> Result<Vec<KeyType>>{
+
+    ensure_known_hosts_file()?;
+
+    let reader = BufReader::new(File::open(known_hosts_path()?)?);
+
+    let mut key_types = Vec::new();
+
+    for line in reader.lines() {
+
+        let line = line?;
+
+        if let Some((entry_names, Some(key_type))) = parse_entry(&line) {
+            if names.iter().any(|name| entry_names.contains(&name.as_str())) && !key_types.contains(&key_type) {
+                key_types.push(key_type);
+            }
+        }
+    }
+
+    Ok(key_types)
+}
+
+pub fn get_host_public_key_by_name(name: &str, key_type: KeyType) -> Result<Option<String>>{
 
     ensure_known_hosts_file()?;
 
@@ -57,15 +116,13 @@ pub fn get_host_public_key_by_ip(address: &Ipv4Addr) -> Result<Option<String>>{
 
     let reader = BufReader::new(file);
 
-    let target = format!("{}#",address);
-
     let mut lines = reader.lines();
 
     while let Some(line) = lines.next(){
 
         let line = line?;
 
-        if line.trim() == target {
+        if is_entry_of(&line, name, key_type) {
             //Reads the PEM
             let mut pem_lines = Vec::new();
 
@@ -85,21 +142,19 @@ pub fn get_host_public_key_by_ip(address: &Ipv4Addr) -> Result<Option<String>>{
 
     }
 
-    Ok(None) //No IP was found stored
+    Ok(None) //No name was found stored
 
 }
 
 
-//Updates a host key
-pub fn replace_host_key(address: &Ipv4Addr, new_public_key_pem: &str) -> Result<()> {
+//Updates a host key, on the entry with the name and key type
+pub fn replace_host_key(name: &str, key_type: KeyType, new_public_key_pem: &str) -> Result<()> {
     ensure_known_hosts_file()?;
 
     let path = known_hosts_path()?;
     let file = File::open(&path)?;
     let reader = BufReader::new(file);
 
-    let target = format!("{}#", address);
-
     let mut lines: Vec<String> = Vec::new();
     let mut lines_iter = reader.lines().peekable();
 
@@ -107,7 +162,7 @@ pub fn replace_host_key(address: &Ipv4Addr, new_public_key_pem: &str) -> Result<
 
     while let Some(line) = lines_iter.next() {
         let line = line?;
-        if line.trim() == target && !replaced {
+        if is_entry_of(&line, name, key_type) && !replaced {
             lines.push(line);
 
             while let Some(Ok(pem_line)) = lines_iter.peek() {
@@ -141,37 +196,62 @@ pub fn replace_host_key(address: &Ipv4Addr, new_public_key_pem: &str) -> Result<
 }
 
 /*
- * Handles the known host verification of an address 
+ * Handles the known host verification of a host key, by all his names and the key type,
+ * on batch mode the user is never asked, so a changed key fails.
+ *
+ * The names without an entry are added, as a known host
+ * reached by a new address, but if the host has a known key
+ * of another type, the new type is asked as a changed key,
+ * since the negotiation prefers the known types.
  */
-pub fn public_key_file_verification(stored_public_key_pem: &Option<String>, public_key_pem : &str, address : &Ipv4Addr) -> Result<()>{
+pub fn public_key_file_verification(names : &[String], public_key_pem : &str, batch_mode : bool) -> Result<()>{
 
     //Validates public key received in pem format 
-    if !crate::crypto::is_valid_public_key_pem(&public_key_pem){
+    let Some(key_type) = KeyType::of_public_pem(public_key_pem) else {
         return Err(Error::Static(INVALID_PUBLIC_KEY_PEM_ERROR)); //Error because is not valid
-    }   
+    };
 
-    if let Some(stored_key) = stored_public_key_pem {
+    let mut changed : Vec<&String> = Vec::new();
+    let mut missing : Vec<String> = Vec::new();
 
-        //if the key changed could mean a man in the middle
-        if stored_key != public_key_pem {
-        
-            //Asks confirmation if wants to save and progress on the connection
-            let flag : bool = crate::utils::ask_confirmation(WARNING_PUBLIC_KEY_CHANGED);
+    for name in names {
+        match get_host_public_key_by_name(name, key_type)? {
+            //if the key changed could mean a man in the middle
+            Some(stored_key) if stored_key != public_key_pem => changed.push(name),
+            Some(_) => {},
+            None => missing.push(name.clone()),
+        }
+    }
 
-            //Updates the key
-            if flag {
-                update_host_key(address, public_key_pem)?;
-            }else{
-                std::process::exit(0); //Closes the program, if the user does not want to continue
-            }
+    if !changed.is_empty() {
+
+        //Asks confirmation if wants to save and progress on the connection
+        let flag : bool = !batch_mode && crate::utils::ask_confirmation(WARNING_PUBLIC_KEY_CHANGED);
+
+        if !flag {
+            return Err(Error::Static(HOST_KEY_VERIFICATION_ERROR)); //Ends the connection, if the user does not want to continue
         }
 
-    } else {
-        // if there is no key, we save this one
-        write_new_host(address, public_key_pem)?;
+        //Updates the key
+        for name in changed {
+            update_host_key(name, key_type, public_key_pem)?;
+        }
     }
-    Ok(())
 
-}
+    //A server which stopped offering the known key type is verified by none of them
+    if !missing.is_empty() && get_host_key_types(names)?.iter().any(|known| *known != key_type) {
+
+        let flag : bool = !batch_mode && crate::utils::ask_confirmation(WARNING_HOST_KEY_TYPE);
+
+        if !flag {
+            return Err(Error::Static(HOST_KEY_TYPE_VERIFICATION_ERROR));
+        }
+    }
 
+    // if there is no key, we save this one
+    if !missing.is_empty() {
+        write_new_host(&missing, key_type, public_key_pem)?;
+    }
 
+    Ok(())
+}
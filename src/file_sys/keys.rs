@@ -0,0 +1,183 @@
+use std::io::Write;
+use std::{fs::File, path::{Path, PathBuf}};
+use std::fs;
+
+use crate::crypto::key_type::KeyType;
+use crate::error::Result;
+use crate::file_sys::path::{identity_paths, server_key_paths};
+use crate::file_sys::utils::{create_private_file, ensure_base_path};
+
+
+/*
+ *##################################################
+ * File responsible for SSSH Keys writing, of each
+ * key type, RSA or Ed25519
+ *
+ * ensure_server_keys() -> Checks if the server keys
+ * of every type exist, else creates them
+ *
+ * generate_server_key() -> Creates the server keys
+ *
+ * read_server_keys() -> Reads the server keys PEM
+ *
+ * ensure_user_keys() -> Checks if the user has a key,
+ * at ~/.sssh/id_ed25519 or ~/.sssh/id_rsa, else
+ * creates an Ed25519 one
+ *
+ * read_user_private_keys() -> Reads the user private keys PEM
+ *
+ * user_private_key_paths() -> The user default private keys, which exist
+ * ##################################################
+ */
+
+//The user key created, when he has none
+const DEFAULT_USER_KEY_TYPE : KeyType = KeyType::Ed25519;
+
+
+ //Ensures that the server keys exist, else we create it
+ pub fn ensure_server_keys() -> Result<()> {
+    
+    ensure_base_path()?;
+
+    for key_type in KeyType::all() {
+
+        let (private_path, public_path) = server_key_paths(*key_type);
+
+        // If at at least one of the keys does not exist we create both 
+        if !keys_exist(private_path, public_path) {
+            generate_server_key_files_and_store(*key_type)?
+        }
+    }
+
+    Ok(())
+}
+
+
+// Checks if both keys exist
+fn keys_exist(private_path: &Path, public_path: &Path) -> bool {
+    private_path.exists() &&
+    public_path.exists()
+}
+
+// Deletes the existing key
+fn remove_keys_if_exist(private_path: &Path, public_path: &Path) -> Result<()> {
+    if private_path.exists() {
+        fs::remove_file(private_path)?;
+    }
+    if public_path.exists() {
+        fs::remove_file(public_path)?;
+    }
+    Ok(())
+}
+
+//Creates new keys, after deleting old ones
+fn generate_server_key_files_and_store(key_type: KeyType) -> Result<()>{
+
+    let (private_path, public_path) = server_key_paths(key_type);
+
+    remove_keys_if_exist(private_path, public_path)?; //Removes if one of the keys is there
+
+    //Writes both keys
+    write_keys_in_files(key_type, private_path, public_path)?;
+
+    Ok(())
+}
+
+//For public use, to update the server keys of a type
+pub fn generate_server_key(key_type: KeyType) -> Result<()>{
+
+    ensure_base_path()?;
+    generate_server_key_files_and_store(key_type)?;
+
+    Ok(())
+}
+
+//Generates the keys, and writes them
+fn write_keys_in_files(key_type: KeyType, private_path: &Path, public_path: &Path) -> Result<()>{
+
+    //Creates the keys in PEM format
+    let (private_pem,public_pem) = crate::crypto::generate_keys(key_type);
+
+    //Creates the key files, the private one only readable by the owner
+    let mut priv_file = create_private_file(private_path)?;
+    let mut pub_file = File::create(public_path)?;
+
+    //Writes on the files the pem
+    priv_file.write_all(private_pem.as_bytes())?;
+    pub_file.write_all(public_pem.as_bytes())?;
+
+    Ok(())
+}
+
+//Reads the server keys of each type, returning the private and public PEM
+pub fn read_server_keys() -> Result<Vec<(KeyType,String,String)>>{
+
+    let mut keys = Vec::new();
+
+    for key_type in KeyType::all() {
+
+        let (private_path, public_path) = server_key_paths(*key_type);
+        let (private_pem, public_pem) = read_keys(private_path, public_path)?;
+
+        keys.push((*key_type, private_pem, public_pem));
+    }
+
+    Ok(keys)
+}
+
+//The user private keys paths, which exist, by order of preference
+pub fn user_private_key_paths() -> Result<Vec<PathBuf>> {
+
+    let mut paths = Vec::new();
+
+    for key_type in KeyType::all() {
+
+        let (private_path, _) = identity_paths(*key_type)?;
+
+        if private_path.exists() {
+            paths.push(private_path);
+        }
+    }
+
+    Ok(paths)
+}
+
+//Ensures that the user has keys, else we create them, returning if they were created
+pub fn ensure_user_keys() -> Result<bool> {
+
+    if !user_private_key_paths()?.is_empty() {
+        return Ok(false);
+    }
+
+    let (private_path, public_path) = identity_paths(DEFAULT_USER_KEY_TYPE)?;
+
+    remove_keys_if_exist(&private_path, &public_path)?;
+    write_keys_in_files(DEFAULT_USER_KEY_TYPE, &private_path, &public_path)?;
+
+    Ok(true)
+}
+
+//Reads the user private keys PEM, from the identity file or the default ones
+pub fn read_user_private_keys(identity_file: Option<&Path>) -> Result<Vec<String>>{
+
+    let paths = match identity_file {
+        Some(path) => vec![path.to_path_buf()],
+        None => user_private_key_paths()?,
+    };
+
+    let mut private_keys_pem = Vec::new();
+
+    for path in paths {
+        private_keys_pem.push(fs::read_to_string(path)?);
+    }
+
+    Ok(private_keys_pem)
+}
+
+fn read_keys(private_path: &Path, public_path: &Path) -> Result<(String,String)>{
+
+    let private_pem = fs::read_to_string(private_path)?;
+    let public_pem = fs::read_to_string(public_path)?;
+
+    Ok((private_pem, public_pem.trim().to_string()))
+}
@@ -1,9 +1,13 @@
-use std::net::Ipv4Addr;
+use std::path::Path;
+use crate::crypto::key_type::KeyType;
 use crate::error::Result;
 mod path;
 mod utils;
 mod hosts;
-pub mod rsa;
+mod authorized_keys;
+mod config;
+pub mod keys;
+pub mod users;
 
 /*
  *#########################################
@@ -12,18 +16,34 @@ pub mod rsa;
  *#########################################
  */
 
-pub fn add_new_known_host(address : &Ipv4Addr, public_key_pem : &str) -> Result<()>{
-    hosts::write_new_host(address, public_key_pem)
+pub fn add_new_known_host(names : &[String], key_type : KeyType, public_key_pem : &str) -> Result<()>{
+    hosts::write_new_host(names, key_type, public_key_pem)
 }
 
-pub fn get_known_host_key(address : &Ipv4Addr) -> Result<Option<String>>{
-    hosts::get_host_public_key_by_ip(address)
+pub fn get_known_host_key(name : &str, key_type : KeyType) -> Result<Option<String>>{
+    hosts::get_host_public_key_by_name(name, key_type)
 }
 
-pub fn update_host_key(address : &Ipv4Addr, new_public_key_pem : &str) -> Result<()>{
-    hosts::replace_host_key(address, new_public_key_pem)
+pub fn get_known_host_key_types(names : &[String]) -> Result<Vec<KeyType>>{
+    hosts::get_host_key_types(names)
 }
 
-pub fn handle_key_verification_on_known_hosts(stored_public_key_pem: &Option<String>, public_key_pem : &str, address : &Ipv4Addr) -> Result<()>{
-    hosts::public_key_file_verification(stored_public_key_pem, public_key_pem, address)
+pub fn update_host_key(name : &str, key_type : KeyType, new_public_key_pem : &str) -> Result<()>{
+    hosts::replace_host_key(name, key_type, new_public_key_pem)
+}
+
+pub fn handle_key_verification_on_known_hosts(names : &[String], public_key_pem : &str, batch_mode : bool) -> Result<()>{
+    hosts::public_key_file_verification(names, public_key_pem, batch_mode)
+}
+
+pub fn read_server_config() -> Result<Option<String>>{
+    config::read_server_config()
+}
+
+pub fn is_authorized_key(home : &Path, uid : u32, public_key_pem : &str) -> Result<bool>{
+    authorized_keys::is_authorized_key(home, uid, public_key_pem)
+}
+
+pub fn set_file_times(path : &Path, accessed : i64, modified : i64) -> Result<()>{
+    utils::set_file_times(path, accessed, modified)
 }
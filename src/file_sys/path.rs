@@ -1,5 +1,6 @@
 use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use crate::crypto::key_type::KeyType;
 use crate::file_sys::utils::ensure_relative_path;
 use crate::error::{Error,Result};
 /*
@@ -7,8 +8,15 @@ use crate::error::{Error,Result};
  * All the used file paths from the system
  *
  * SSSH_SERVER_KEYS_PATH - The path to the server keys 
- * SSSH_SERVER_PRIVATE_KEY - The private key from the server
- * SSSH_SERVER_PUBLIC_KEY - The public key from the server
+ * SSSH_SERVER_PRIVATE_KEY - The RSA private key from the server
+ * SSSH_SERVER_PUBLIC_KEY - The RSA public key from the server
+ * SSSH_SERVER_ED25519_PRIVATE_KEY - The Ed25519 private key from the server
+ * SSSH_SERVER_ED25519_PUBLIC_KEY - The Ed25519 public key from the server
+ * SSSH_SERVER_CONFIG - The server options, set by the administrator
+ * SSSH_RELATIVE_ID_RSA - The user RSA private key, at his home
+ * SSSH_RELATIVE_ID_ED25519 - The user Ed25519 private key, at his home
+ * SSSH_RELATIVE_AUTHORIZED_KEYS - The user keys allowed to login
+ * SYSTEM_USERS_FILE - The system users, with their home and shell
  *
  *##############################################################
  */
@@ -17,7 +25,16 @@ pub const SSSH_SERVER_KEYS_PATH : &str = "/etc/sssh/";
 pub const SSSH_RELATIVE_PATH : &str = ".sssh/";
 pub const SSSH_SERVER_PRIVATE_KEY: &str = "/etc/sssh/priv";
 pub const SSSH_SERVER_PUBLIC_KEY : &str = "/etc/sssh/public.pub";
+pub const SSSH_SERVER_ED25519_PRIVATE_KEY : &str = "/etc/sssh/ed25519";
+pub const SSSH_SERVER_ED25519_PUBLIC_KEY : &str = "/etc/sssh/ed25519.pub";
+pub const SSSH_SERVER_CONFIG : &str = "/etc/sssh/sssh_server_config";
 pub const SSSH_RELATIVE_KNOWN_HOSTS : &str = ".sssh/known_hosts";
+pub const SSSH_RELATIVE_ID_RSA : &str = ".sssh/id_rsa";
+pub const SSSH_RELATIVE_ID_RSA_PUB : &str = ".sssh/id_rsa.pub";
+pub const SSSH_RELATIVE_ID_ED25519 : &str = ".sssh/id_ed25519";
+pub const SSSH_RELATIVE_ID_ED25519_PUB : &str = ".sssh/id_ed25519.pub";
+pub const SSSH_RELATIVE_AUTHORIZED_KEYS : &str = ".sssh/authorized_keys";
+pub const SYSTEM_USERS_FILE : &str = "/etc/passwd";
 
 const HOME_NOT_SET : &str = "HOME variable not set";
 
@@ -30,9 +47,35 @@ pub fn known_hosts_path() -> Result<PathBuf> {
 
     let path = get_home_path()?;
 
-    return Ok(path.join(SSSH_RELATIVE_KNOWN_HOSTS));
+    Ok(path.join(SSSH_RELATIVE_KNOWN_HOSTS))
+}
+
+//Gets the user private and public keys paths, of the key type
+pub fn identity_paths(key_type: KeyType) -> Result<(PathBuf, PathBuf)> {
+
+    ensure_relative_path()?;
+
+    let path = get_home_path()?;
+
+    let (private_key, public_key) = match key_type {
+        KeyType::Ed25519 => (SSSH_RELATIVE_ID_ED25519, SSSH_RELATIVE_ID_ED25519_PUB),
+        KeyType::Rsa => (SSSH_RELATIVE_ID_RSA, SSSH_RELATIVE_ID_RSA_PUB),
+    };
+
+    Ok((path.join(private_key), path.join(public_key)))
+}
+
+//Gets the server private and public keys paths, of the key type
+pub fn server_key_paths(key_type: KeyType) -> (&'static Path, &'static Path) {
+    match key_type {
+        KeyType::Ed25519 => (Path::new(SSSH_SERVER_ED25519_PRIVATE_KEY), Path::new(SSSH_SERVER_ED25519_PUBLIC_KEY)),
+        KeyType::Rsa => (Path::new(SSSH_SERVER_PRIVATE_KEY), Path::new(SSSH_SERVER_PUBLIC_KEY)),
+    }
+}
 
-    
+//Gets the authorized_keys of a user, which is not the one running on the server
+pub fn authorized_keys_path(home: &Path) -> PathBuf {
+    home.join(SSSH_RELATIVE_AUTHORIZED_KEYS)
 }
 
 pub fn get_home_path() -> Result<PathBuf>{
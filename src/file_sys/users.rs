@@ -0,0 +1,65 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+
+use crate::error::Result;
+use crate::file_sys::path::SYSTEM_USERS_FILE;
+/*
+ * #########################################################
+ * Contains the logic to find a system user, which is
+ * needed by the server to know where the user home is,
+ * and which shell to start.
+ *
+ * The users are read from /etc/passwd, where each line is:
+ *
+ * name:password:uid:gid:comment:home:shell
+ * #########################################################
+ */
+
+const PASSWD_FIELDS : usize = 7;
+
+pub struct SystemUser {
+    pub name : String,
+    pub uid : u32,
+    pub gid : u32,
+    pub home : PathBuf,
+    pub shell : String,
+}
+
+//Finds the user by his name, returning None if there is not such user
+pub fn get_system_user(name: &str) -> Result<Option<SystemUser>> {
+
+    let file = File::open(SYSTEM_USERS_FILE)?;
+    let reader = BufReader::new(file);
+
+    for line in reader.lines() {
+
+        let line = line?;
+
+        if let Some(user) = parse_passwd_line(&line) {
+            if user.name == name {
+                return Ok(Some(user));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+//Converts a line to a user, ignoring invalid lines
+fn parse_passwd_line(line: &str) -> Option<SystemUser> {
+
+    let fields: Vec<&str> = line.trim().split(':').collect();
+
+    if fields.len() != PASSWD_FIELDS {
+        return None;
+    }
+
+    Some(SystemUser {
+        name: fields[0].to_string(),
+        uid: fields[2].parse().ok()?,
+        gid: fields[3].parse().ok()?,
+        home: PathBuf::from(fields[5]),
+        shell: fields[6].to_string(),
+    })
+}
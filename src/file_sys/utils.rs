@@ -1,7 +1,15 @@
+use std::ffi::CString;
+use std::io;
 use std::path::{Path, PathBuf};
-use std::fs;
+use std::fs::{self, File, OpenOptions};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::OpenOptionsExt;
 use crate::file_sys::path::{SSSH_SERVER_KEYS_PATH,SSSH_RELATIVE_PATH};
-use crate::error::Result;
+use crate::error::{Error, Result};
+
+const PRIVATE_FILE_MODE : u32 = 0o600;
+
+const INVALID_PATH_ERROR : &str = "The path has a null character";
 /*
  * ################################################
  * File responsible for holding auxiliary functions
@@ -37,3 +45,28 @@ pub fn ensure_relative_path() -> Result<()>{
     }
     Ok(())
 }
+
+//Creates a file only readable by the owner, used for private keys
+pub fn create_private_file(path: &Path) -> Result<File>{
+
+    let file = OpenOptions::new().create(true).write(true).truncate(true).mode(PRIVATE_FILE_MODE).open(path)?;
+
+    Ok(file)
+}
+
+//Sets the access and modification times, in seconds since the epoch, following links as stat does
+pub fn set_file_times(path: &Path, accessed: i64, modified: i64) -> Result<()>{
+
+    let path = CString::new(path.as_os_str().as_bytes()).map_err(|_| Error::Static(INVALID_PATH_ERROR))?;
+
+    let times = [
+        libc::timespec { tv_sec: accessed as libc::time_t, tv_nsec: 0 },
+        libc::timespec { tv_sec: modified as libc::time_t, tv_nsec: 0 },
+    ];
+
+    if unsafe { libc::utimensat(libc::AT_FDCWD, path.as_ptr(), times.as_ptr(), 0) } < 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+
+    Ok(())
+}
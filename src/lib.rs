@@ -0,0 +1,10 @@
+pub mod error;
+pub mod utils;
+pub mod session;
+pub mod crypto;
+pub mod file_sys;
+pub mod server;
+pub mod terminal;
+pub mod cli;
+pub mod transfer;
+pub mod agent;
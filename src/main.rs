@@ -1,44 +1,65 @@
-use std::net::Ipv4Addr;
-pub mod error;
-mod utils;
-mod session;
-mod crypto;
-mod file_sys;
+use std::env;
+use std::process;
+
+use sssh::cli::{self, Cli};
+use sssh::session::Session;
+use sssh::utils;
 
 fn main(){
 
-    let address = Ipv4Addr::new(124, 34, 1, 1);
-    /*let pem = String::from("-----BEGIN RSA PUBLIC KEY-----
-MIIBigKCAYEAq3DnhgYgLVJknvDA3clATozPtjI7yauqD4/ZuqgZn4KzzzkQ4BzJ
-ar4jRygpzbghlFn0Luk1mdVKzPUgYj0VkbRlHyYfcahbgOHixOOnXkKXrtZW7yWG
-jXPqy/ZJ/+...
------END RSA PUBLIC KEY-----");
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let cli = match Cli::parse(&args) {
+        Ok(cli) => cli,
+        Err(e) => {
+            eprintln!("sssh: {}", e);
+            eprintln!("{}", cli::USAGE);
+            process::exit(cli::USAGE_EXIT_CODE);
+        }
+    };
 
-    match file_sys::add_new_known_host(&address, &pem){
-        Ok(_) => println!("Sucesso"),
-        Err(e) => panic!("Error: {}",e),
+    if cli.help {
+        println!("{}", cli::USAGE);
+        return;
     }
 
-    let adress2 = Ipv4Addr::new(124,34,1,2);
+    utils::set_verbosity(cli.verbosity);
+
+    //With a command it is executed, without it a shell is started, and the ports are forwarded meanwhile
+    let result = Session::connect(&cli.destination, &cli.config).and_then(|session| {
+
+        for forward in &cli.config.local_forwards {
+            session.forward_local(forward)?;
+        }
 
-   match file_sys::get_known_host_key(&adress2) {
+        for proxy in &cli.config.dynamic_forwards {
+            session.forward_dynamic(proxy)?;
+        }
 
-       Ok(Some(key)) => println!("Chave: {}", key),
-       Ok(None) => println!("Endereço não encontrado"),
-       Err(e) => panic!("Error: {}",e),
-       
-   } 
+        for forward in &cli.config.remote_forwards {
+            session.forward_remote(forward)?;
+        }
 
-   let pem2 = String::from("-----BEGIN RSA PUBLIC KEY-----
-MIIBigKCAYEAq3DnhgYgLVJknvDA3clATozPtjI7yauqD4/ZuqgZn4KzzzkQ4BzJ
-ar4jRygpzbghlFn0Luk1mdVKzPUgYj0VkbRlHyYfcahbgOHixOOnXkKXrtZW7yWG
-jXPqy/ZJ/+...ABC
------END RSA PUBLIC KEY-----");
+        if cli.no_command {
+            return session.wait().map(|_| 0);
+        }
 
-    match file_sys::update_host_key(&address, &pem2) {
-        Ok(_) => {},
-        Err(e) => panic!("Error: {}",e),
+        let code = match cli.command {
+            Some(command) => session.exec(&command)?,
+            None => session.shell()?,
+        };
+
+        //The server may have already left, the command still ended
+        let _ = session.close();
+
+        Ok(code)
+    });
+
+    match result {
+        Ok(code) => process::exit(code),
+        Err(e) => {
+            eprintln!("sssh: {}", cli::error_message(&e));
+            process::exit(cli::error_exit_code(&e));
+        }
     }
-    */
 }
-
@@ -0,0 +1,121 @@
+/*
+ * ##############################################
+ * File responsible for the agent forwarding, on
+ * the server side, as sssh -A asks before the
+ * shell or command of a session channel.
+ *
+ * The server listens on an agent socket of his
+ * own, on a new directory only the user may
+ * enter, both created and removed as the user,
+ * which the shell or command is told by
+ * SSSH_AUTH_SOCK, and each connection of the
+ * user to it opens an AuthAgent channel, relayed
+ * to the client agent.
+ *
+ * The socket is removed once the shell or
+ * command ends, with AllowAgentForwarding no
+ * at the server config every request is refused.
+ * ##############################################
+ */
+
+use std::path::Path;
+use std::process::Command;
+use std::sync::Arc;
+use std::thread;
+
+use crate::agent::server::{self, AgentSocket};
+use crate::agent::AGENT_SOCKET_ENV;
+use crate::error::Result;
+use crate::file_sys::users::SystemUser;
+use crate::server::process::UserCredentials;
+use crate::session::channel::Channel;
+use crate::session::forward;
+use crate::session::message::{AgentForwardFailure, AgentForwardSuccess, ChannelKind, Message};
+
+//The agent socket of a channel, closed when dropped
+pub struct ForwardedAgent {
+    socket: Arc<AgentSocket>,
+    credentials: UserCredentials,
+}
+
+impl ForwardedAgent {
+
+    pub fn path(&self) -> &Path {
+        &self.socket.path
+    }
+}
+
+impl Drop for ForwardedAgent {
+    fn drop(&mut self) {
+        //The directory is of the user, so it is never removed as root
+        if let Err(e) = self.credentials.as_user_files(|| self.socket.close()) {
+            crate::utils::debug(1, &format!("Failed to remove the forwarded agent socket, {}", e));
+        }
+    }
+}
+
+/*
+ * Answers the agent forwarding request of the channel, returning the
+ * forwarded agent, or None if it was refused, where a failed socket
+ * only refuses it, the shell or command still runs
+ */
+pub fn handle_agent_forward(channel: &Channel, system_user: &SystemUser, allowed: bool) -> Result<Option<ForwardedAgent>> {
+
+    let agent = match allowed {
+        true => listen_agent(channel, system_user).inspect_err(|e| eprintln!("{}: forwarding the agent, {}", system_user.name, e)).ok(),
+        false => None,
+    };
+
+    let reply = match agent {
+        Some(_) => Message::AgentForwardSuccess(AgentForwardSuccess { channel: channel.remote_id() }),
+        None => Message::AgentForwardFailure(AgentForwardFailure { channel: channel.remote_id() }),
+    };
+
+    channel.send_message(&reply)?;
+
+    Ok(agent)
+}
+
+//Tells the agent socket to the shell or command, if the agent is forwarded
+pub fn set_agent_env(command: &mut Command, agent_socket: Option<&Path>) {
+    if let Some(path) = agent_socket {
+        command.env(AGENT_SOCKET_ENV, path);
+    }
+}
+
+//Listens on a new agent socket of the user, opening a channel for each of his connections
+fn listen_agent(channel: &Channel, system_user: &SystemUser) -> Result<ForwardedAgent> {
+
+    let credentials = UserCredentials::new(system_user)?;
+
+    //Created as the user, so root never follows a path he controls
+    let socket = Arc::new(credentials.as_user_files(|| AgentSocket::bind(None))??);
+
+    let listener_socket = Arc::clone(&socket);
+    let opener = channel.opener();
+    let uid = system_user.uid;
+
+    thread::spawn(move || {
+
+        let on_error = |e: &std::io::Error| crate::utils::debug(1, &format!("Failed to accept on the forwarded agent, {}", e));
+
+        crate::utils::accept_connections(listener_socket.listener.incoming(), || listener_socket.is_closed(), on_error, |stream| {
+
+            //Only the user, or root, may use his agent
+            if !server::peer_uid(&stream).is_ok_and(|peer| peer == uid || peer == 0) {
+                return;
+            }
+
+            let opener = opener.clone();
+
+            //Opening waits for the client, so each connection has his own thread
+            thread::spawn(move || {
+                if let Ok(channel) = opener.open(ChannelKind::AuthAgent) {
+                    forward::relay(channel, stream);
+                }
+            });
+        });
+    });
+
+    Ok(ForwardedAgent { socket, credentials })
+}
@@ -0,0 +1,93 @@
+/*
+ * ##############################################
+ * File responsible for the user authentication,
+ * on the server side, where the user public key
+ * must be at his ~/.sssh/authorized_keys and the
+ * session hash must be signed by his private key,
+ * with one of the allowed HostKeyAlgorithms
+ * ##############################################
+ */
+
+use crate::crypto;
+use crate::crypto::algorithms::{Algorithm, HostKeyAlgorithm};
+use crate::error::{Error, Result};
+use crate::file_sys;
+use crate::file_sys::users::SystemUser;
+use crate::session::message::{Auth, AuthFailure, AuthSuccess, Message};
+use crate::session::transport::SecureChannel;
+use crate::session::utils;
+
+const MAX_AUTH_ATTEMPTS: usize = 3;
+
+const UNEXPECTED_MESSAGE_ERROR: &str = "The client sent an unexpected message before the authentication";
+const TOO_MANY_ATTEMPTS_ERROR: &str = "The client failed the authentication too many times";
+
+/*
+ * Reads the Auth messages until one is valid, answering
+ * each one, returns the system user authenticated or None
+ * if the client ended the session.
+ */
+pub fn handle_authentication(channel: &mut SecureChannel, user: &str, session_hash: &[u8], allowed_algorithms: &[String]) -> Result<Option<SystemUser>> {
+
+    for _ in 0..MAX_AUTH_ATTEMPTS {
+
+        //The client closing the socket is the same as End
+        let message = match channel.receive_message() {
+            Ok(m) => m,
+            Err(e) if utils::is_connection_closed(&e) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        let auth = match message {
+            Message::Auth(auth) => auth,
+            Message::End(_) => return Ok(None),
+            _ => return Err(Error::Static(UNEXPECTED_MESSAGE_ERROR)),
+        };
+
+        if let Some(system_user) = verify_auth(&auth, user, session_hash, allowed_algorithms)? {
+            channel.send_message(&Message::AuthSuccess(AuthSuccess))?;
+            return Ok(Some(system_user));
+        }
+
+        channel.send_message(&Message::AuthFailure(AuthFailure))?;
+    }
+
+    Err(Error::Static(TOO_MANY_ATTEMPTS_ERROR))
+}
+
+//Verifies the user exists, the key is authorized and the signature is valid, by an allowed algorithm
+fn verify_auth(auth: &Auth, user: &str, session_hash: &[u8], allowed_algorithms: &[String]) -> Result<Option<SystemUser>> {
+
+    //The user must be the same one used on the session hash
+    if auth.user != user {
+        return Ok(None);
+    }
+
+    if !allowed_algorithms.contains(&auth.algorithm) {
+        return Ok(None);
+    }
+
+    let Some(algorithm) = HostKeyAlgorithm::from_name(&auth.algorithm) else {
+        return Ok(None);
+    };
+
+    let Some(system_user) = file_sys::users::get_system_user(user)? else {
+        return Ok(None);
+    };
+
+    //Unsafe permissions only fail this authentication, as any other unauthorized key
+    match file_sys::is_authorized_key(&system_user.home, system_user.uid, &auth.public_key_pem) {
+        Ok(true) => {},
+        Ok(false) => return Ok(None),
+        Err(e) => {
+            eprintln!("{}: refused the authorized keys, {}", user, e);
+            return Ok(None);
+        },
+    }
+
+    if crypto::is_valid_signature(algorithm, &auth.public_key_pem, session_hash, &auth.signature).is_err() {
+        return Ok(None);
+    }
+
+    Ok(Some(system_user))
+}
@@ -0,0 +1,29 @@
+/*
+ * ##########################################
+ * File responsible for proving the server
+ * identity, sending the public key and
+ * signing the session hash with the private
+ * key, on the key exchange reply, so the key
+ * exchange the client sees is the server one
+ * ##########################################
+ */
+
+use std::net::TcpStream;
+
+use crate::crypto;
+use crate::crypto::algorithms::HostKeyAlgorithm;
+use crate::error::Result;
+use crate::server::HostKey;
+use crate::session::message::{Message, PublicKeyReply};
+use crate::session::utils;
+
+//Sends the server public key PEM, of the negotiated type, so the client can compare with the known hosts
+pub fn send_public_key(stream: &mut TcpStream, host_key: &HostKey) -> Result<()> {
+    utils::write_message(stream, &Message::PublicKeyReply(PublicKeyReply { public_key_pem: host_key.public_key_pem.clone() }))
+}
+
+// signs the session hash with the private key, by the negotiated algorithm, where the hash
+// covers the shared key and both key exchange public values, so only the server could sign it
+pub fn sign_session_hash(host_key: &HostKey, algorithm: HostKeyAlgorithm, session_hash: &[u8]) -> Result<Vec<u8>> {
+    crypto::sign(algorithm, &host_key.private_key_pem, session_hash)
+}
@@ -0,0 +1,147 @@
+/*
+ * ##############################################
+ * File responsible for the server configuration,
+ * set by the administrator at the config file,
+ * with an option by line:
+ *
+ * Option value
+ *
+ * Where a line starting with '#' is a comment,
+ * the options are the algorithms lists, as:
+ *
+ * KexAlgorithms x25519-sha256
+ * Ciphers aes256-ctr
+ *
+ * And the rekey limits, as in rekey.rs:
+ *
+ * RekeyBytes 512M
+ *
+ * And where the ports forwarded by the clients,
+ * as -R, may listen:
+ *
+ * GatewayPorts no - Only on the loopback, the default
+ * GatewayPorts yes - On every interface
+ * GatewayPorts clientspecified - On the client bind address
+ *
+ * And if the clients may forward their agent, as -A:
+ *
+ * AllowAgentForwarding yes - The default
+ * AllowAgentForwarding no
+ *
+ * So a weak algorithm may be disabled, without
+ * the file every supported algorithm is allowed.
+ * ##############################################
+ */
+
+use crate::crypto::algorithms::AlgorithmLists;
+use crate::error::{Error, Result};
+use crate::file_sys;
+use crate::session::rekey::RekeyLimits;
+
+const COMMENT_CHAR: char = '#';
+
+const LOOPBACK_ADDRESS: &str = "localhost";
+const WILDCARD_ADDRESS: &str = "0.0.0.0";
+
+const UNKNOWN_OPTION_ERROR: &str = "Unknown option at the server config";
+const MISSING_VALUE_ERROR: &str = "Missing an option value at the server config";
+const GATEWAY_PORTS_ERROR: &str = "GatewayPorts must be no, yes or clientspecified";
+const ALLOW_AGENT_FORWARDING_ERROR: &str = "AllowAgentForwarding must be yes or no";
+
+pub struct ServerConfig {
+    pub algorithms: AlgorithmLists,
+    pub rekey_limits: RekeyLimits,
+    pub gateway_ports: GatewayPorts,
+    pub allow_agent_forwarding: bool,
+}
+
+//Where the forwarded ports of the clients may listen
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum GatewayPorts {
+    #[default]
+    No,
+    Yes,
+    ClientSpecified,
+}
+
+impl GatewayPorts {
+
+    //The address a forwarded port listens on, for the bind address the client asked
+    pub fn bind_address<'a>(&self, requested: &'a str) -> &'a str {
+        match self {
+            GatewayPorts::No => LOOPBACK_ADDRESS,
+            GatewayPorts::Yes => WILDCARD_ADDRESS,
+            GatewayPorts::ClientSpecified if requested.is_empty() || requested == "*" => WILDCARD_ADDRESS,
+            GatewayPorts::ClientSpecified => requested,
+        }
+    }
+
+    fn parse(value: &str) -> Result<Self> {
+        match value.to_lowercase().as_str() {
+            "no" => Ok(GatewayPorts::No),
+            "yes" => Ok(GatewayPorts::Yes),
+            "clientspecified" => Ok(GatewayPorts::ClientSpecified),
+            _ => Err(Error::Static(GATEWAY_PORTS_ERROR)),
+        }
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            algorithms: AlgorithmLists::default(),
+            rekey_limits: RekeyLimits::default(),
+            gateway_ports: GatewayPorts::default(),
+            allow_agent_forwarding: true,
+        }
+    }
+}
+
+impl ServerConfig {
+
+    //Reads the config file, the default config if it does not exist
+    pub fn load() -> Result<Self> {
+        match file_sys::read_server_config()? {
+            Some(config) => Self::parse(&config),
+            None => Ok(Self::default()),
+        }
+    }
+
+    fn parse(config: &str) -> Result<Self> {
+
+        let mut server_config = Self::default();
+
+        for line in config.lines().map(str::trim) {
+
+            if line.is_empty() || line.starts_with(COMMENT_CHAR) {
+                continue;
+            }
+
+            let Some((option, value)) = line.split_once(char::is_whitespace) else {
+                return Err(Error::Static(MISSING_VALUE_ERROR));
+            };
+
+            let value = value.trim();
+
+            if option.eq_ignore_ascii_case("gatewayports") {
+                server_config.gateway_ports = GatewayPorts::parse(value)?;
+                continue;
+            }
+
+            if option.eq_ignore_ascii_case("allowagentforwarding") {
+                server_config.allow_agent_forwarding = match value.to_lowercase().as_str() {
+                    "yes" => true,
+                    "no" => false,
+                    _ => return Err(Error::Static(ALLOW_AGENT_FORWARDING_ERROR)),
+                };
+                continue;
+            }
+
+            if !server_config.algorithms.set_list(option, value)? && !server_config.rekey_limits.set_option(option, value)? {
+                return Err(Error::Static(UNKNOWN_OPTION_ERROR));
+            }
+        }
+
+        Ok(server_config)
+    }
+}
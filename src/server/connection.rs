@@ -0,0 +1,240 @@
+/*
+ * #####################################
+ *  File responsible for the connection
+ * of a client on the server side,
+ * answering every message it sends
+ * #####################################
+ *
+ */
+
+use std::net::TcpStream;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use num_bigint::BigUint;
+
+use crate::crypto;
+use crate::crypto::algorithms::{Algorithm, AlgorithmLists, Algorithms, HostKeyAlgorithm};
+use crate::error::{Error, Result};
+use crate::file_sys::users::SystemUser;
+use crate::server::config::{GatewayPorts, ServerConfig};
+use crate::server::agent::{self, ForwardedAgent};
+use crate::server::{auth, challenge, dhkeys, exec, forward, shell, subsystem, HostKey, ServerKeys};
+use crate::session::channel::{Channel, Incoming, IncomingChannel, Mux};
+use crate::session::message::{ChannelKind, Message, Negotiation};
+use crate::session::transport::SecureChannel;
+use crate::session::banner::Banner;
+use crate::session::utils;
+
+const UNEXPECTED_MESSAGE_ERROR: &str = "The client sent an unexpected message";
+const HOST_KEY_ERROR: &str = "The server has no key of the negotiated host key algorithm";
+const KEX_ALGORITHM_ERROR: &str = "The client used a key exchange different from the negotiated one";
+const FORWARDED_CHANNEL_ERROR: &str = "The server only opens forwarded connections";
+const AGENT_CHANNEL_ERROR: &str = "The server only opens the agent connections of a forwarded agent";
+
+/*
+ * Verifies the banner sent by the client and answers
+ * with the same one, then negotiates the algorithms and
+ * answers the handshake messages until the key exchange,
+ * where the connection becomes encrypted, and the user
+ * must authenticate.
+ */
+pub fn handle_client(mut stream: TcpStream, keys: &ServerKeys, config: &ServerConfig) -> Result<()> {
+
+    verify_banner(&mut stream)?;
+
+    let (algorithms, negotiation) = negotiate_algorithms(&mut stream, &config.algorithms, keys)?;
+
+    //The negotiated host key is always one the server has
+    let host_key = keys.get(algorithms.host_key.key_type()).ok_or(Error::Static(HOST_KEY_ERROR))?;
+
+    //The client may leave before the key exchange
+    let Some((shared_key, session_hash, user)) = handle_handshake(&mut stream, host_key, &algorithms, &negotiation)? else {
+        return Ok(());
+    };
+
+    let session_keys = crypto::generate_session_keys(&session_hash, shared_key, &algorithms);
+
+    let mut channel = SecureChannel::server(stream, &session_keys, &algorithms, &session_hash, config.rekey_limits)?;
+
+    //The client may leave before the authentication
+    let Some(system_user) = auth::handle_authentication(&mut channel, &user, &session_hash, &config.algorithms.host_key)? else {
+        return Ok(());
+    };
+
+    handle_session(channel, system_user, config.gateway_ports, config.allow_agent_forwarding)
+}
+
+/*
+ * Reads the client banner, and if it is the same protocol sends ours back,
+ * even if the version is not compatible, so the client knows ours
+ */
+fn verify_banner(stream: &mut TcpStream) -> Result<()>{
+
+    let theirs = Banner::parse(&utils::read_from_tcp(stream)?)?;
+
+    let ours = Banner::ours();
+    utils::write_to_tcp(stream, ours.to_string().as_bytes())?;
+
+    ours.check_compatible(&theirs)
+}
+
+/*
+ * Reads the client algorithms lists and sends ours, even if
+ * there is no mutual algorithm, so the client also knows it,
+ * returning also both messages as sent.
+ *
+ * Only the host keys algorithms of the server keys are sent.
+ */
+fn negotiate_algorithms(stream: &mut TcpStream, config_lists: &AlgorithmLists, keys: &ServerKeys) -> Result<(Algorithms, Vec<u8>)>{
+
+    let mut lists = config_lists.clone();
+    lists.host_key.retain(|name| {
+        HostKeyAlgorithm::from_name(name).is_some_and(|algorithm| keys.get(algorithm.key_type()).is_some())
+    });
+
+    let mut negotiation = utils::read_from_tcp(stream)?;
+
+    let client_lists = match Message::decode(&negotiation)? {
+        Message::Negotiation(client) => client.lists,
+        _ => return Err(Error::Static(UNEXPECTED_MESSAGE_ERROR)),
+    };
+
+    let reply = Message::Negotiation(Negotiation { lists: lists.clone() }).encode()?;
+    utils::write_to_tcp(stream, &reply)?;
+
+    negotiation.extend_from_slice(&reply);
+
+    Ok((client_lists.negotiate(&lists)?, negotiation))
+}
+
+//Reads each message and answers it, returning the shared key, session hash and user after the key exchange
+fn handle_handshake(stream: &mut TcpStream, host_key: &HostKey, algorithms: &Algorithms, negotiation: &[u8]) -> Result<Option<(BigUint, Vec<u8>, String)>> {
+
+    loop {
+
+        //The client closing the socket is the same as End
+        let message = match utils::read_message(stream) {
+            Ok(m) => m,
+            Err(e) if utils::is_connection_closed(&e) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        match message {
+            Message::PublicKey(_) => challenge::send_public_key(stream, host_key)?,
+            Message::KeyExchange(key_exchange) => {
+
+                if key_exchange.keys.get_algorithm() != algorithms.kex {
+                    return Err(Error::Static(KEX_ALGORITHM_ERROR));
+                }
+
+                let (shared_key, session_hash) = dhkeys::answer_dh_keys_exchange(stream, &key_exchange, host_key, algorithms.host_key, negotiation)?;
+
+                return Ok(Some((shared_key, session_hash, key_exchange.user)));
+            },
+            Message::End(_) => return Ok(None),
+            _ => return Err(Error::Static(UNEXPECTED_MESSAGE_ERROR)),
+        }
+    }
+}
+
+/*
+ * Accepts the channels the authenticated user opens, shells, commands
+ * and forwarded connections, each on his own thread, and the ports he
+ * asks to forward, until the client ends the session or leaves
+ */
+fn handle_session(channel: SecureChannel, system_user: SystemUser, gateway_ports: GatewayPorts, allow_agent_forwarding: bool) -> Result<()> {
+
+    let system_user = Arc::new(system_user);
+    let listeners = Arc::new(Mutex::new(Vec::new()));
+    let session_listeners = Arc::clone(&listeners);
+
+    let mux = Mux::start(channel, move |incoming| match incoming {
+        Incoming::Channel(incoming) => open_channel(incoming, &system_user, allow_agent_forwarding),
+        Incoming::Forward(request) => {
+
+            let (bind, port) = (request.bind.clone(), request.port);
+
+            match forward::listen_remote(request, &system_user, gateway_ports) {
+                Ok(Some(listener)) => session_listeners.lock().unwrap().push(listener),
+                Ok(None) => {},
+                Err(e) => eprintln!("{}: forwarding {}:{}, {}", system_user.name, bind, port, e),
+            }
+        },
+    });
+
+    let result = mux.wait();
+
+    //The forwarded ports only listen while the session lasts
+    forward::stop_listening(&listeners.lock().unwrap());
+
+    result
+}
+
+//Answers a channel opened by the client on his own thread
+fn open_channel(incoming: IncomingChannel, system_user: &Arc<SystemUser>, allow_agent_forwarding: bool) {
+
+    match incoming.kind.clone() {
+        ChannelKind::Session => {
+            let system_user = Arc::clone(system_user);
+
+            thread::spawn(move || {
+                if let Err(e) = incoming.accept().and_then(|channel| handle_channel(channel, &system_user, allow_agent_forwarding)) {
+                    eprintln!("{}: {}", system_user.name, e);
+                }
+            });
+        },
+        ChannelKind::DirectTcpip { host, port, originator } => {
+            let user = system_user.name.clone();
+
+            thread::spawn(move || {
+                if let Err(e) = forward::handle_direct_tcpip(incoming, &host, port) {
+                    eprintln!("{}: forwarding {} to {}:{}, {}", user, originator, host, port, e);
+                }
+            });
+        },
+        //Only the server forwards the connections of a listened port
+        ChannelKind::ForwardedTcpip { .. } => {
+            let _ = incoming.reject(FORWARDED_CHANNEL_ERROR);
+        },
+        //Only the client has the agent
+        ChannelKind::AuthAgent => {
+            let _ = incoming.reject(AGENT_CHANNEL_ERROR);
+        },
+    }
+}
+
+/*
+ * Reads the first message of a session channel, which asks for a shell, a command
+ * or a subsystem, where the agent forwarding may be asked before the shell or command
+ */
+fn handle_channel(mut channel: Channel, system_user: &SystemUser, allow_agent_forwarding: bool) -> Result<()> {
+
+    //The client may close the channel without a request
+    let Some(mut message) = channel.receive() else {
+        return Ok(());
+    };
+
+    //Forwarded until the shell or command ends, as it is dropped
+    let mut forwarded_agent = None;
+
+    if let Message::AgentForward(_) = message {
+
+        forwarded_agent = agent::handle_agent_forward(&channel, system_user, allow_agent_forwarding)?;
+
+        let Some(next) = channel.receive() else {
+            return Ok(());
+        };
+
+        message = next;
+    }
+
+    let agent_socket = forwarded_agent.as_ref().map(ForwardedAgent::path);
+
+    match message {
+        Message::Shell(request) => shell::handle_shell(channel, system_user, &request, agent_socket),
+        Message::Exec(request) => exec::handle_exec(channel, system_user, &request, agent_socket),
+        Message::Subsystem(request) => subsystem::handle_subsystem(channel, system_user, &request),
+        _ => Err(Error::Static(UNEXPECTED_MESSAGE_ERROR)),
+    }
+}
@@ -0,0 +1,42 @@
+/*
+ * ##############################################
+ * File responsible for the key exchange, on the
+ * server side, using the algorithm and the group
+ * sent by the client
+ * ##############################################
+ *
+ */
+
+use std::net::TcpStream;
+
+use num_bigint::BigUint;
+
+use crate::crypto;
+use crate::crypto::algorithms::HostKeyAlgorithm;
+use crate::crypto::kex::KexKeys;
+use crate::error::Result;
+use crate::server::{challenge, HostKey};
+use crate::session::message::{KeyExchange, KeyExchangeReply, Message};
+use crate::session::utils;
+
+/*
+ * Generates our keys with the same algorithm of the client, computes
+ * the shared key and the session hash, then sends our public key with
+ * the host key signature of the session hash, returning both.
+ */
+pub fn answer_dh_keys_exchange(stream: &mut TcpStream, key_exchange: &KeyExchange, host_key: &HostKey, algorithm: HostKeyAlgorithm, negotiation: &[u8]) -> Result<(BigUint, Vec<u8>)> {
+
+    let keys = KexKeys::from_client_keys(&key_exchange.keys)?; //Generates our keys on the same algorithm
+
+    let public_key = keys.get_public_key();
+    let client_public_key = key_exchange.keys.get_public_key();
+    let shared_key = keys.compute_shared_key(&client_public_key)?;
+
+    let session_hash = crypto::compute_session_hash(&shared_key, &host_key.public_key_pem, &key_exchange.user, negotiation, &client_public_key, &public_key);
+    let signature = challenge::sign_session_hash(host_key, algorithm, &session_hash)?;
+
+    //Only the public key, the client already knows the algorithm
+    utils::write_message(stream, &Message::KeyExchangeReply(KeyExchangeReply { public_key, signature }))?;
+
+    Ok((shared_key, session_hash))
+}
@@ -0,0 +1,148 @@
+/*
+ * ##############################################
+ * File responsible for the command execution,
+ * on the server side.
+ *
+ * The command runs with the user shell, as
+ * shell -c command, without a terminal, where:
+ *
+ * Data -> stdin, until Eof
+ * stdout -> Data, stderr -> ErrorData
+ *
+ * Once both outputs close and the command ends,
+ * his exit status is sent, and the channel closed.
+ *
+ * The command leads his own process group, so
+ * closing the channel terminates every process
+ * it started, which could hold the outputs open.
+ * ##############################################
+ */
+
+use std::io::{Read, Write};
+use std::os::unix::process::CommandExt;
+use std::path::Path;
+use std::process::{Child, ChildStdin, Stdio};
+use std::thread;
+
+use crate::error::{Error, Result};
+use crate::file_sys::users::SystemUser;
+use crate::server::agent;
+use crate::server::process::{self, ProcessSignaler};
+use crate::session::channel::{Channel, ChannelWriter};
+use crate::session::message::{Exec, ExecFailure, ExecSuccess, Message};
+use crate::session::protocol;
+
+const UNEXPECTED_MESSAGE_ERROR: &str = "The client sent an unexpected message during the command";
+
+pub fn handle_exec(channel: Channel, system_user: &SystemUser, exec: &Exec, agent_socket: Option<&Path>) -> Result<()> {
+
+    let spawned = process::user_command(system_user, &system_user.shell).and_then(|mut command| {
+        agent::set_agent_env(&mut command, agent_socket);
+        command.arg("-c").arg(&exec.command)
+            .process_group(0)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(Error::from)
+    });
+
+    let child = match spawned {
+        Ok(c) => c,
+        Err(e) => {
+            channel.send_message(&Message::ExecFailure(ExecFailure { channel: channel.remote_id() }))?;
+            return Err(e);
+        }
+    };
+
+    channel.send_message(&Message::ExecSuccess(ExecSuccess { channel: channel.remote_id() }))?;
+
+    relay_process(channel, child)
+}
+
+//Relays the stdio of a process started leading his group on his channel, until it ends or the client closes the channel
+pub fn relay_process(mut channel: Channel, mut child: Child) -> Result<()> {
+
+    let signaler = ProcessSignaler::for_group(&child);
+    let stdin = child.stdin.take();
+
+    let output = relay_output(child, signaler.clone(), channel.writer());
+
+    let result = relay_input(&mut channel, stdin);
+
+    //If the client closed the channel before the command ended, it is terminated, with every process it started
+    signaler.signal(libc::SIGTERM);
+
+    let _ = output.join();
+
+    result
+}
+
+//Writes the client data on the command stdin, until the channel closes
+fn relay_input(channel: &mut Channel, mut stdin: Option<ChildStdin>) -> Result<()> {
+
+    while let Some(message) = channel.receive() {
+        match message {
+            Message::Data(data) => {
+                //The command may not read his stdin, so the data is dropped
+                if let Some(input) = stdin.as_mut() {
+                    if input.write_all(&data.data).is_err() {
+                        stdin = None;
+                    }
+                }
+            },
+            Message::WindowChange(_) => {},
+            Message::Eof(_) => stdin = None, //Closing the stdin is the end of the input
+            _ => return Err(Error::Static(UNEXPECTED_MESSAGE_ERROR)),
+        }
+    }
+
+    Ok(())
+}
+
+//Sends stdout and stderr, then the exit status once the command ends, closing the channel
+fn relay_output(mut child: Child, signaler: ProcessSignaler, writer: ChannelWriter) -> thread::JoinHandle<()> {
+
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+
+    let stdout_relay = stdout.map(|out| relay_stream(out, writer.clone(), ChannelWriter::send_data));
+    let stderr_relay = stderr.map(|err| relay_stream(err, writer.clone(), ChannelWriter::send_error_data));
+
+    thread::spawn(move || {
+
+        for relay in [stdout_relay, stderr_relay].into_iter().flatten() {
+            let _ = relay.join();
+        }
+
+        let Ok(status) = signaler.wait(&mut child) else {
+            return;
+        };
+
+        let _ = writer.send_message(&process::exit_message(writer.remote_id(), status));
+        let _ = writer.close();
+    })
+}
+
+//Reads a stream until it closes, sending each read with the send function
+fn relay_stream<R, F>(mut stream: R, writer: ChannelWriter, send: F) -> thread::JoinHandle<()>
+where
+    R: Read + Send + 'static,
+    F: Fn(&ChannelWriter, &[u8]) -> Result<()> + Send + 'static,
+{
+    thread::spawn(move || {
+
+        let mut buffer = [0u8; protocol::BUFFER_MAX_SIZE];
+
+        loop {
+            match stream.read(&mut buffer) {
+                Ok(0) | Err(_) => return,
+                Ok(size) => {
+                    if send(&writer, &buffer[..size]).is_err() {
+                        return;
+                    }
+                },
+            }
+        }
+    })
+}
@@ -0,0 +1,118 @@
+/*
+ * ##############################################
+ * File responsible for the forwarding on the
+ * server side:
+ *
+ * DirectTcpip - The server connects to the host
+ *   the client asked for, as -L
+ * ForwardRequest - The server listens on the port
+ *   the client asked for, as -R, where each accepted
+ *   connection opens a ForwardedTcpip channel
+ *
+ * The bind address of a forwarded port follows
+ * GatewayPorts at the server config, and only root
+ * may forward a privileged port.
+ *
+ * A failed connection or listen rejects the request
+ * with the reason, and the connections are relayed
+ * as the client does.
+ * ##############################################
+ */
+
+use std::net::{TcpListener, TcpStream};
+use std::os::fd::AsRawFd;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use crate::error::Result;
+use crate::file_sys::users::SystemUser;
+use crate::server::config::GatewayPorts;
+use crate::session::channel::{IncomingChannel, IncomingForward};
+use crate::session::forward;
+use crate::session::message::ChannelKind;
+
+//The ports below it are only for root
+const PRIVILEGED_PORTS: u16 = 1024;
+
+const PRIVILEGED_PORT_ERROR: &str = "Only root may forward a privileged port";
+
+//A forwarded port, which only stops accepting once stopped, not on a failed accept
+pub struct RemoteListener {
+    listener: TcpListener,
+    stopped: Arc<AtomicBool>,
+}
+
+//Connects to the host and relays it, until the connection or the channel closes
+pub fn handle_direct_tcpip(incoming: IncomingChannel, host: &str, port: u16) -> Result<()> {
+
+    let stream = match TcpStream::connect((host, port)) {
+        Ok(stream) => stream,
+        Err(e) => return incoming.reject(&e.to_string()),
+    };
+
+    forward::relay(incoming.accept()?, stream);
+
+    Ok(())
+}
+
+/*
+ * Listens on the port the client asked for, forwarding each connection
+ * back to the client, returning the listener to stop it once the session
+ * ends, or None if the request was rejected
+ */
+pub fn listen_remote(request: IncomingForward, system_user: &SystemUser, gateway_ports: GatewayPorts) -> Result<Option<RemoteListener>> {
+
+    if request.port < PRIVILEGED_PORTS && system_user.uid != 0 {
+        request.reject(PRIVILEGED_PORT_ERROR)?;
+        return Ok(None);
+    }
+
+    let address = gateway_ports.bind_address(&request.bind);
+
+    let listener = match TcpListener::bind((address, request.port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            request.reject(&e.to_string())?;
+            return Ok(None);
+        },
+    };
+
+    let stopper = RemoteListener { listener: listener.try_clone()?, stopped: Arc::new(AtomicBool::new(false)) };
+    let stopped = Arc::clone(&stopper.stopped);
+    let (bind, port) = (request.bind.clone(), request.port);
+    let opener = request.accept()?;
+
+    thread::spawn(move || {
+
+        let is_stopped = || stopped.load(Ordering::Acquire);
+        let on_error = |e: &std::io::Error| crate::utils::debug(1, &format!("Failed to accept on the forwarded port {}, {}", port, e));
+
+        crate::utils::accept_connections(listener.incoming(), is_stopped, on_error, |stream| {
+
+            let opener = opener.clone();
+            let bind = bind.clone();
+
+            //Opening waits for the client, so each connection has his own thread
+            thread::spawn(move || {
+
+                let originator = stream.peer_addr().map(|address| address.to_string()).unwrap_or_default();
+
+                if let Ok(channel) = opener.open(ChannelKind::ForwardedTcpip { bind, port, originator }) {
+                    forward::relay(channel, stream);
+                }
+            });
+        });
+    });
+
+    Ok(Some(stopper))
+}
+
+//Stops the forwarded ports, waking up the threads which accept on them
+pub fn stop_listening(listeners: &[RemoteListener]) {
+    for remote in listeners {
+        remote.stopped.store(true, Ordering::Release);
+        // SAFETY: the descriptor is of the listener clone, open while it is borrowed
+        unsafe { libc::shutdown(remote.listener.as_raw_fd(), libc::SHUT_RDWR) };
+    }
+}
@@ -0,0 +1,123 @@
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddrV4, SocketAddrV6, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+
+mod connection;
+mod config;
+mod auth;
+mod challenge;
+mod dhkeys;
+mod process;
+mod pty;
+mod shell;
+mod exec;
+mod forward;
+mod subsystem;
+mod agent;
+
+use crate::crypto::key_type::KeyType;
+use crate::error::Result;
+use crate::file_sys;
+use crate::server::config::ServerConfig;
+use crate::session::protocol;
+/*
+ *#########################################################
+ * File responsible for the SSSH Server, where
+ * it contains the struct Server, which has the
+ * listening socket and the server keys.
+ *
+ * Each accepted client is answered on his own thread,
+ * following the same order the client asks:
+ *
+ * Banner -> Negotiation -> PublicKey -> KeyExchange -> Auth
+ *
+ * After the authentication, the user opens channels, each
+ * one a shell, a command, a subsystem, as the file transfer,
+ * or a forwarded connection answered on his own thread.
+ *
+ * The server keys, RSA and Ed25519, are created at the
+ * first start, at /etc/sssh/, and the negotiated one
+ * signs the session hash, proving the server identity,
+ * the allowed algorithms are at the server config.
+ *#########################################################
+ */
+
+pub struct Server{
+    listener : TcpListener,
+    keys : Arc<ServerKeys>,
+    config : Arc<ServerConfig>,
+}
+
+//A server key in PEM format
+pub struct HostKey{
+    pub key_type : KeyType,
+    pub private_key_pem : String,
+    pub public_key_pem : String,
+}
+
+//The server keys, one of each type, shared between the clients threads
+pub struct ServerKeys{
+    pub host_keys : Vec<HostKey>,
+}
+
+impl ServerKeys {
+
+    pub fn get(&self, key_type: KeyType) -> Option<&HostKey>{
+        self.host_keys.iter().find(|key| key.key_type == key_type)
+    }
+}
+
+impl Server {
+
+    //Binds the server on the port, the default one if not selected
+    pub fn bind(_port: Option<u16>) -> Result<Self>{
+
+        let port = _port.unwrap_or(protocol::DEFAULT_PORT);
+
+        //Creates the server keys on the first start
+        file_sys::keys::ensure_server_keys()?;
+
+        let host_keys = file_sys::keys::read_server_keys()?.into_iter()
+            .map(|(key_type, private_key_pem, public_key_pem)| HostKey { key_type, private_key_pem, public_key_pem })
+            .collect();
+
+        let keys = Arc::new(ServerKeys { host_keys });
+
+        let config = Arc::new(ServerConfig::load()?);
+
+        //The IPv6 socket also accepts IPv4 clients, without IPv6 only IPv4 is used
+        let listener = match TcpListener::bind(SocketAddrV6::new(Ipv6Addr::UNSPECIFIED, port, 0, 0)) {
+            Ok(l) => l,
+            Err(_) => TcpListener::bind(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, port))?,
+        };
+
+        Ok(Self { listener, keys, config })
+    }
+
+    //Accepts clients forever, each one on a new thread
+    pub fn run(&self) -> Result<()>{
+
+        let on_error = |e: &std::io::Error| eprintln!("Failed to accept a client: {}", e);
+
+        crate::utils::accept_connections(self.listener.incoming(), || false, on_error, |stream: TcpStream| {
+
+            let keys = Arc::clone(&self.keys);
+            let config = Arc::clone(&self.config);
+
+            thread::spawn(move || {
+
+                let peer = stream.peer_addr();
+
+                //A failed client must never stop the server
+                if let Err(e) = connection::handle_client(stream, &keys, &config) {
+                    match peer {
+                        Ok(address) => eprintln!("{}: {}", address, e),
+                        Err(_) => eprintln!("{}", e),
+                    }
+                }
+            });
+        });
+
+        Ok(())
+    }
+}
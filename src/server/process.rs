@@ -0,0 +1,193 @@
+/*
+ * ##############################################
+ * File responsible for the processes started
+ * for a user, as his shell or a command, which
+ * must run as the user, on his home, with a
+ * clean environment.
+ *
+ * Also converts how the process ended to the
+ * message sent to the client, and runs the file
+ * operations on places of the user as him.
+ * ##############################################
+ */
+
+use std::ffi::CString;
+use std::io;
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::process::{Child, Command, ExitStatus};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::error::{Error, Result};
+use crate::file_sys::users::SystemUser;
+use crate::session::message::{self, ExitSignal, Message};
+
+const DEFAULT_PATH: &str = "/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin";
+const MAX_GROUPS: usize = 256;
+
+const INVALID_USER_ERROR: &str = "The user name has an invalid character";
+const GROUPS_ERROR: &str = "Could not get the user groups";
+
+//Creates a command which will run as the user
+pub fn user_command(user: &SystemUser, program: &str) -> Result<Command> {
+
+    let groups = get_groups(user)?;
+    let (uid, gid) = (user.uid, user.gid);
+
+    let mut command = Command::new(program);
+
+    command.current_dir(&user.home)
+        .env_clear()
+        .env("HOME", &user.home)
+        .env("USER", &user.name)
+        .env("LOGNAME", &user.name)
+        .env("SHELL", &user.shell)
+        .env("PATH", DEFAULT_PATH);
+
+    // SAFETY: only async-signal-safe calls, without allocations, run after the fork
+    unsafe {
+        command.pre_exec(move || {
+
+            //Only root may change the groups, otherwise the user is the server one
+            if libc::getuid() == 0 && libc::setgroups(groups.len(), groups.as_ptr()) < 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            if libc::setgid(gid) < 0 || libc::setuid(uid) < 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            Ok(())
+        });
+    }
+
+    Ok(command)
+}
+
+/*
+ * Signals a started process only until it is reaped, since then his pid
+ * may already be of another process, shared by the thread which waits it
+ */
+#[derive(Clone)]
+pub struct ProcessSignaler {
+    pid: libc::pid_t,
+    group: bool,
+    reaped: Arc<Mutex<bool>>,
+}
+
+impl ProcessSignaler {
+
+    pub fn new(child: &Child) -> Self {
+        Self { pid: child.id() as libc::pid_t, group: false, reaped: Arc::new(Mutex::new(false)) }
+    }
+
+    /*
+     * Signals every process of the group the child leads, as started with
+     * process_group(0), so the processes it started, holding his pipes, end
+     * with it, while the unreaped child keeps the group id from being reused
+     */
+    pub fn for_group(child: &Child) -> Self {
+        Self { group: true, ..Self::new(child) }
+    }
+
+    //Sends the signal, if the process was not reaped yet
+    pub fn signal(&self, signal: libc::c_int) {
+
+        let reaped = self.reaped.lock().unwrap();
+        let target = if self.group { -self.pid } else { self.pid };
+
+        if !*reaped {
+            // SAFETY: the process, or the group it leads, is still ours, as it was not reaped
+            unsafe { libc::kill(target, signal) };
+        }
+    }
+
+    //Waits for the process to end, then reaps it, while no signal is being sent
+    pub fn wait(&self, child: &mut Child) -> Result<ExitStatus> {
+
+        let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+
+        //Only waits, the ended process keeps his pid until it is reaped below
+        while unsafe { libc::waitid(libc::P_PID, self.pid as libc::id_t, &mut info, libc::WEXITED | libc::WNOWAIT) } < 0 {
+
+            let error = io::Error::last_os_error();
+
+            if error.kind() != io::ErrorKind::Interrupted {
+                return Err(Error::Io(error));
+            }
+        }
+
+        let mut reaped = self.reaped.lock().unwrap();
+        let status = child.wait()?;
+        *reaped = true;
+
+        Ok(status)
+    }
+}
+
+//The message telling how the process ended, a signal if killed by one, for the client channel
+pub fn exit_message(channel: u32, status: ExitStatus) -> Message {
+    match status.signal() {
+        Some(signal) => Message::ExitSignal(ExitSignal { channel, signal }),
+        None => Message::ExitStatus(message::ExitStatus { channel, code: status.code().unwrap_or_default() }),
+    }
+}
+
+//The user and every group of him, to run file operations as him
+pub struct UserCredentials {
+    uid: libc::uid_t,
+    gid: libc::gid_t,
+    groups: Vec<libc::gid_t>,
+}
+
+impl UserCredentials {
+
+    pub fn new(user: &SystemUser) -> Result<Self> {
+        Ok(Self { uid: user.uid, gid: user.gid, groups: get_groups(user)? })
+    }
+
+    /*
+     * Runs the action with the filesystem credentials and the groups of the user,
+     * so the files it creates are his, and a path he controls, as a symbolic link,
+     * gives him nothing more than he already has. They are set on a new thread,
+     * where the raw setgroups changes only it, as the libc one changes them all
+     */
+    pub fn as_user_files<T: Send>(&self, action: impl FnOnce() -> T + Send) -> Result<T> {
+
+        thread::scope(|scope| {
+            scope.spawn(|| {
+
+                //Only root may change the groups, otherwise the user is the server one
+                // SAFETY: the groups pointer and length are of a live vector, and only this thread is changed
+                if unsafe { libc::getuid() == 0 && libc::syscall(libc::SYS_setgroups, self.groups.len(), self.groups.as_ptr()) < 0 } {
+                    return Err(Error::Io(io::Error::last_os_error()));
+                }
+
+                // SAFETY: only the filesystem credentials of this thread are changed, which ends after it
+                unsafe {
+                    libc::setfsgid(self.gid);
+                    libc::setfsuid(self.uid);
+                }
+
+                Ok(action())
+            }).join().unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+        })
+    }
+}
+
+//Gets every group of the user, before the fork, since it reads /etc/group
+fn get_groups(user: &SystemUser) -> Result<Vec<libc::gid_t>> {
+
+    let name = CString::new(user.name.as_str()).map_err(|_| Error::Static(INVALID_USER_ERROR))?;
+
+    let mut groups: Vec<libc::gid_t> = vec![0; MAX_GROUPS];
+    let mut size = MAX_GROUPS as libc::c_int;
+
+    if unsafe { libc::getgrouplist(name.as_ptr(), user.gid, groups.as_mut_ptr(), &mut size) } < 0 {
+        return Err(Error::Static(GROUPS_ERROR));
+    }
+
+    groups.truncate(size as usize);
+
+    Ok(groups)
+}
@@ -0,0 +1,108 @@
+/*
+ * ##############################################
+ * File responsible for the pseudo-terminal,
+ * where the user login shell runs.
+ *
+ * The shell gets the PTY slave as his terminal,
+ * on a new session, and runs as the user, while
+ * the server reads and writes the PTY master.
+ * ##############################################
+ */
+
+use std::fs::File;
+use std::io;
+use std::mem;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::os::unix::process::CommandExt;
+use std::path::Path;
+use std::process::{Child, Stdio};
+use std::ptr;
+
+use crate::error::Result;
+use crate::file_sys::users::SystemUser;
+use crate::server::{agent, process};
+
+pub struct Pty {
+    pub master: File,
+    slave: OwnedFd,
+}
+
+impl Pty {
+
+    //Opens a new PTY, with the client terminal size
+    pub fn open(columns: u16, rows: u16) -> Result<Self> {
+
+        let mut master: libc::c_int = -1;
+        let mut slave: libc::c_int = -1;
+        let size = window_size(columns, rows);
+
+        if unsafe { libc::openpty(&mut master, &mut slave, ptr::null_mut(), ptr::null(), &size) } < 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+
+        // SAFETY: openpty returned both file descriptors, which are only owned here
+        let (master, slave) = unsafe { (File::from_raw_fd(master), OwnedFd::from_raw_fd(slave)) };
+
+        Ok(Self { master, slave })
+    }
+
+    /*
+     * Starts the user login shell on the slave, the shell
+     * is named with a '-' first, as login shells are, and
+     * only the slave is closed here, so the master reads
+     * fail once the shell ends.
+     */
+    pub fn spawn_login_shell(self, user: &SystemUser, terminal: &str, agent_socket: Option<&Path>) -> Result<(File, Child)> {
+
+        let shell_name = Path::new(&user.shell).file_name().unwrap_or_default().to_string_lossy();
+
+        let mut command = process::user_command(user, &user.shell)?;
+
+        agent::set_agent_env(&mut command, agent_socket);
+
+        command.arg0(format!("-{}", shell_name))
+            .env("TERM", terminal)
+            .stdin(Stdio::from(self.slave.try_clone()?))
+            .stdout(Stdio::from(self.slave.try_clone()?))
+            .stderr(Stdio::from(self.slave));
+
+        // SAFETY: only async-signal-safe calls run after the fork
+        unsafe {
+            command.pre_exec(|| {
+
+                //A new session, where the slave is the controlling terminal
+                if libc::setsid() < 0 || libc::ioctl(libc::STDIN_FILENO, libc::TIOCSCTTY, 0) < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+
+                Ok(())
+            });
+        }
+
+        let child = command.spawn()?;
+
+        Ok((self.master, child))
+    }
+}
+
+//Changes the terminal size, when the client terminal is resized
+pub fn set_window_size(master: &File, columns: u16, rows: u16) -> Result<()> {
+
+    let size = window_size(columns, rows);
+
+    if unsafe { libc::ioctl(master.as_raw_fd(), libc::TIOCSWINSZ, &size) } < 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+
+    Ok(())
+}
+
+fn window_size(columns: u16, rows: u16) -> libc::winsize {
+
+    // SAFETY: winsize is a plain struct
+    let mut size: libc::winsize = unsafe { mem::zeroed() };
+    size.ws_col = columns;
+    size.ws_row = rows;
+
+    size
+}
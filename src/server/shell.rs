@@ -0,0 +1,106 @@
+/*
+ * ##############################################
+ * File responsible for the interactive shell,
+ * on the server side.
+ *
+ * The user login shell runs on a PTY, where:
+ *
+ * Data -> PTY master, WindowChange -> PTY size
+ * PTY master -> Data, on another thread
+ *
+ * When the shell ends, his exit status is sent and
+ * the channel closed, and when the client closes it,
+ * or leaves, the shell is hung up.
+ * ##############################################
+ */
+
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::Child;
+use std::thread;
+
+use crate::error::{Error, Result};
+use crate::file_sys::users::SystemUser;
+use crate::server::process::{self, ProcessSignaler};
+use crate::server::pty::{self, Pty};
+use crate::session::channel::{Channel, ChannelWriter};
+use crate::session::message::{Message, Shell, ShellFailure, ShellSuccess};
+use crate::session::protocol;
+
+//The character which the terminal reads as the end of the input
+const END_OF_TRANSMISSION: u8 = 0x04;
+
+const UNEXPECTED_MESSAGE_ERROR: &str = "The client sent an unexpected message during the shell";
+
+pub fn handle_shell(mut channel: Channel, system_user: &SystemUser, shell: &Shell, agent_socket: Option<&Path>) -> Result<()> {
+
+    let spawned = Pty::open(shell.columns, shell.rows)
+        .and_then(|pty| pty.spawn_login_shell(system_user, &shell.terminal, agent_socket));
+
+    let (master, child) = match spawned {
+        Ok(s) => s,
+        Err(e) => {
+            channel.send_message(&Message::ShellFailure(ShellFailure { channel: channel.remote_id() }))?;
+            return Err(e);
+        }
+    };
+
+    channel.send_message(&Message::ShellSuccess(ShellSuccess { channel: channel.remote_id() }))?;
+
+    let signaler = ProcessSignaler::new(&child);
+
+    let output = relay_output(master.try_clone()?, child, signaler.clone(), channel.writer());
+
+    let result = relay_input(&mut channel, master);
+
+    //If the client left, the shell is hung up, so the output relay ends
+    signaler.signal(libc::SIGHUP);
+
+    let _ = output.join();
+
+    result
+}
+
+//Writes the client messages on the PTY, until the channel closes
+fn relay_input(channel: &mut Channel, mut master: File) -> Result<()> {
+
+    while let Some(message) = channel.receive() {
+        match message {
+            Message::Data(data) => master.write_all(&data.data)?,
+            Message::WindowChange(size) => pty::set_window_size(&master, size.columns, size.rows)?,
+            Message::Eof(_) => master.write_all(&[END_OF_TRANSMISSION])?,
+            _ => return Err(Error::Static(UNEXPECTED_MESSAGE_ERROR)),
+        }
+    }
+
+    Ok(())
+}
+
+//Sends the shell output, and the exit status once the shell ends, closing the channel
+fn relay_output(mut master: File, mut child: Child, signaler: ProcessSignaler, writer: ChannelWriter) -> thread::JoinHandle<()> {
+
+    thread::spawn(move || {
+
+        let mut buffer = [0u8; protocol::BUFFER_MAX_SIZE];
+
+        //The read fails once the shell, and every process on the terminal, closes it
+        while let Ok(size) = master.read(&mut buffer) {
+
+            if size == 0 {
+                break;
+            }
+
+            if writer.send_data(&buffer[..size]).is_err() {
+                break;
+            }
+        }
+
+        let Ok(status) = signaler.wait(&mut child) else {
+            return;
+        };
+
+        let _ = writer.send_message(&process::exit_message(writer.remote_id(), status));
+        let _ = writer.close();
+    })
+}
@@ -0,0 +1,70 @@
+/*
+ * ##############################################
+ * File responsible for the subsystems, on the
+ * server side, where each one is a program which
+ * runs as the user, as a command, and talks on
+ * the channel with his stdin and stdout:
+ *
+ * transfer - The file transfer server, sssh-transfer
+ *
+ * The programs are next to the server one.
+ * ##############################################
+ */
+
+use std::env;
+use std::os::unix::process::CommandExt;
+use std::path::PathBuf;
+use std::process::Stdio;
+
+use crate::error::{Error, Result};
+use crate::file_sys::users::SystemUser;
+use crate::server::{exec, process};
+use crate::session::channel::Channel;
+use crate::session::message::{Message, Subsystem, SubsystemFailure, SubsystemSuccess};
+use crate::transfer::protocol::TRANSFER_SUBSYSTEM;
+
+const TRANSFER_PROGRAM: &str = "sssh-transfer";
+
+const UNKNOWN_SUBSYSTEM_ERROR: &str = "The client asked for an unknown subsystem";
+const PROGRAM_PATH_ERROR: &str = "The subsystem program path is not valid";
+
+pub fn handle_subsystem(channel: Channel, system_user: &SystemUser, subsystem: &Subsystem) -> Result<()> {
+
+    let spawned = program_path(&subsystem.name)
+        .and_then(|program| program.to_str().map(str::to_string).ok_or(Error::Static(PROGRAM_PATH_ERROR)))
+        .and_then(|program| process::user_command(system_user, &program))
+        .and_then(|mut command| {
+            command.process_group(0)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+                .map_err(Error::from)
+        });
+
+    let child = match spawned {
+        Ok(c) => c,
+        Err(e) => {
+            channel.send_message(&Message::SubsystemFailure(SubsystemFailure { channel: channel.remote_id() }))?;
+            return Err(e);
+        }
+    };
+
+    channel.send_message(&Message::SubsystemSuccess(SubsystemSuccess { channel: channel.remote_id() }))?;
+
+    exec::relay_process(channel, child)
+}
+
+//The program of the subsystem, at the directory of the server program
+fn program_path(name: &str) -> Result<PathBuf> {
+
+    let program = match name {
+        TRANSFER_SUBSYSTEM => TRANSFER_PROGRAM,
+        _ => return Err(Error::Static(UNKNOWN_SUBSYSTEM_ERROR)),
+    };
+
+    let server = env::current_exe()?;
+    let directory = server.parent().ok_or(Error::Static(PROGRAM_PATH_ERROR))?;
+
+    Ok(directory.join(program))
+}
@@ -0,0 +1,75 @@
+/*
+ * ##############################################
+ * File responsible for the agent forwarding, on
+ * the client side, as -A:
+ *
+ * AgentForward -> AgentForwardSuccess | AgentForwardFailure
+ *
+ * Asked on the session channel before the shell
+ * or command, where a refusal is only warned.
+ *
+ * Then each AuthAgent channel the server opens
+ * is relayed to the agent of SSSH_AUTH_SOCK, so
+ * the private keys never leave the client.
+ * ##############################################
+ */
+
+use std::env;
+use std::os::unix::net::UnixStream;
+use std::thread;
+
+use crate::agent::AGENT_SOCKET_ENV;
+use crate::error::{Error, Result};
+use crate::session::channel::{Channel, IncomingChannel};
+use crate::session::forward;
+use crate::session::message::{self, AgentForward, Message};
+use crate::session::Session;
+
+const AGENT_FORWARD_WARNING: &str = "The server refused the agent forwarding";
+const NO_AGENT_ERROR: &str = "The client has no agent";
+
+impl Session {
+
+    //Asks the server to forward the agent to the shell or command of the channel, if it is forwarded
+    pub(crate) fn request_agent(&self, channel: &mut Channel) -> Result<()> {
+
+        if !self.forward_agent {
+            return Ok(());
+        }
+
+        channel.send_message(&Message::AgentForward(AgentForward { channel: channel.remote_id() }))?;
+
+        match channel.receive() {
+            Some(Message::AgentForwardSuccess(_)) => crate::utils::debug(1, "Forwarding the agent"),
+            Some(Message::AgentForwardFailure(_)) => eprintln!("{}", AGENT_FORWARD_WARNING),
+            _ => return Err(Error::Static(message::UNEXPECTED_MESSAGE_ERROR)),
+        }
+
+        Ok(())
+    }
+}
+
+//Connects the channel to the local agent on his own thread, rejecting it without an agent
+pub fn connect_agent(incoming: IncomingChannel) {
+
+    thread::spawn(move || {
+
+        let Some(path) = env::var_os(AGENT_SOCKET_ENV) else {
+            let _ = incoming.reject(NO_AGENT_ERROR);
+            return;
+        };
+
+        let stream = match UnixStream::connect(&path) {
+            Ok(stream) => stream,
+            Err(e) => {
+                crate::utils::debug(1, &format!("Could not connect to the agent, {}", e));
+                let _ = incoming.reject(&e.to_string());
+                return;
+            },
+        };
+
+        if let Ok(channel) = incoming.accept() {
+            forward::relay(channel, stream);
+        }
+    });
+}
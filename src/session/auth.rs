@@ -0,0 +1,135 @@
+/*
+ * ##############################################
+ * File responsible for the user authentication,
+ * where the user proves to have the private key
+ * of one of the keys at the server
+ * ~/.sssh/authorized_keys, by signing the
+ * session hash with his ~/.sssh/id_ed25519 or
+ * ~/.sssh/id_rsa, each key is tried by order
+ *
+ * Without an identity file, the keys of the agent
+ * of SSSH_AUTH_SOCK are tried first, where the
+ * agent signs, so the key files are not read.
+ * ##############################################
+ */
+
+use std::env;
+use std::path::Path;
+
+use crate::agent::client::AgentClient;
+use crate::agent::AGENT_SOCKET_ENV;
+use crate::crypto;
+use crate::crypto::algorithms::{Algorithm, HostKeyAlgorithm};
+use crate::crypto::key_type::KeyType;
+use crate::error::{Error, Result};
+use crate::file_sys;
+use crate::session::message::{self, Auth, Message};
+use crate::session::transport::SecureChannel;
+
+const AUTH_FAILURE_ERROR: &str = "Permission denied, the key is not authorized for the user";
+const NEW_KEYS_WARNING: &str = "A new key was created at ~/.sssh/id_ed25519, add ~/.sssh/id_ed25519.pub to the server ~/.sssh/authorized_keys";
+
+/*
+ * Authenticates with the identity file, or the default keys if None,
+ * each key signs with the first algorithm of its type
+ */
+pub fn authenticate(channel: &mut SecureChannel, user: &str, session_hash: &[u8], identity_file: Option<&Path>, algorithms: &[HostKeyAlgorithm]) -> Result<()> {
+
+    //The keys tried, so a key of the agent and of his file is not tried twice
+    let mut tried = Vec::new();
+
+    if identity_file.is_none() && authenticate_with_agent(channel, user, session_hash, algorithms, &mut tried)? {
+        return Ok(());
+    }
+
+    //The first time the default user keys are created
+    if identity_file.is_none() && file_sys::keys::ensure_user_keys()? {
+        eprintln!("{}", NEW_KEYS_WARNING);
+    }
+
+    for private_key_pem in file_sys::keys::read_user_private_keys(identity_file)? {
+
+        if authenticate_with_key(channel, user, session_hash, &private_key_pem, algorithms, &tried)? {
+            return Ok(());
+        }
+    }
+
+    Err(Error::Static(AUTH_FAILURE_ERROR))
+}
+
+//Tries each key of the agent, returning if one was accepted, without an agent none is tried
+fn authenticate_with_agent(channel: &mut SecureChannel, user: &str, session_hash: &[u8], algorithms: &[HostKeyAlgorithm], tried: &mut Vec<String>) -> Result<bool> {
+
+    if env::var_os(AGENT_SOCKET_ENV).is_none() {
+        return Ok(false);
+    }
+
+    //An agent which is gone is not a failure, the key files are still tried
+    let mut agent = match AgentClient::connect() {
+        Ok(agent) => agent,
+        Err(e) => {
+            crate::utils::debug(1, &format!("Could not connect to the agent, {}", e));
+            return Ok(false);
+        },
+    };
+
+    for key in agent.list()? {
+
+        crate::utils::debug(2, &format!("Trying the agent key {}", key.comment));
+
+        let public_key_pem = key.public_key_pem.clone();
+        let sign = |algorithm| agent.sign(&public_key_pem, algorithm, session_hash);
+
+        if try_key(channel, user, key.public_key_pem.clone(), algorithms, sign)? {
+            return Ok(true);
+        }
+
+        tried.push(key.public_key_pem);
+    }
+
+    Ok(false)
+}
+
+//Tries a key file, unless the agent tried it, returning if it was accepted
+fn authenticate_with_key(channel: &mut SecureChannel, user: &str, session_hash: &[u8], private_key_pem: &str, algorithms: &[HostKeyAlgorithm], tried: &[String]) -> Result<bool> {
+
+    let public_key_pem = crypto::public_key_pem_from_private(private_key_pem)?;
+
+    if tried.contains(&public_key_pem) {
+        return Ok(false);
+    }
+
+    try_key(channel, user, public_key_pem, algorithms, |algorithm| crypto::sign(algorithm, private_key_pem, session_hash))
+}
+
+/*
+ * Sends the public key with the signature of the session hash, by
+ * the first algorithm of his type, returning if it was accepted
+ */
+fn try_key<F>(channel: &mut SecureChannel, user: &str, public_key_pem: String, algorithms: &[HostKeyAlgorithm], sign: F) -> Result<bool>
+where
+    F: FnOnce(HostKeyAlgorithm) -> Result<Vec<u8>>,
+{
+    let Some(key_type) = KeyType::of_public_pem(&public_key_pem) else {
+        return Ok(false);
+    };
+
+    //The server would not accept it, so it is not sent
+    let Some(algorithm) = algorithms.iter().find(|algorithm| algorithm.key_type() == key_type) else {
+        crate::utils::debug(2, &format!("Skipping the {} key, no mutual signature algorithm", key_type.name()));
+        return Ok(false);
+    };
+
+    crate::utils::debug(2, &format!("Trying the {} key with {}", key_type.name(), algorithm.name()));
+
+    //The session hash is unique, so the signature cannot be used on other session
+    let signature = sign(*algorithm)?;
+
+    channel.send_message(&Message::Auth(Auth { user: user.to_string(), public_key_pem, algorithm: algorithm.name().to_string(), signature }))?;
+
+    match channel.receive_message()? {
+        Message::AuthSuccess(_) => Ok(true),
+        Message::AuthFailure(_) => Ok(false),
+        _ => Err(Error::Static(message::UNEXPECTED_MESSAGE_ERROR)),
+    }
+}
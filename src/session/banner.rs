@@ -0,0 +1,111 @@
+/*
+ * ###################################################
+ * File responsible for the banner, the first frame
+ * sent by both machines, in the following format:
+ *
+ * sssh_<major>.<minor>-<software> <comments>
+ *
+ * As sssh_0.1-sssh0.1.0 where the comments are optional,
+ * and the software has no spaces or '-'.
+ *
+ * Machines with the same major version work together,
+ * the minor version only adds compatible changes, so
+ * both machines may be upgraded in any order.
+ * ###################################################
+ */
+
+use std::fmt;
+
+use crate::error::{Error, Result};
+use crate::session::protocol;
+
+const BANNER_MAX_SIZE: usize = 255;
+const NAME_SEPARATOR: char = '_';
+const VERSION_SEPARATOR: char = '.';
+const SOFTWARE_SEPARATOR: char = '-';
+const COMMENTS_SEPARATOR: char = ' ';
+
+const INVALID_BANNER_ERROR: &str = "The other machine is working with a different protocol on the same port";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Banner {
+    pub major: u16,
+    pub minor: u16,
+    pub software: String,
+    pub comments: Option<String>,
+}
+
+impl Banner {
+
+    //The banner of this machine
+    pub fn ours() -> Self {
+        Self {
+            major: protocol::PROTOCOL_MAJOR_VERSION,
+            minor: protocol::PROTOCOL_MINOR_VERSION,
+            software: protocol::SOFTWARE_VERSION.to_string(),
+            comments: None,
+        }
+    }
+
+    //Parses a received banner, which must be of the sssh protocol
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+
+        if bytes.len() > BANNER_MAX_SIZE {
+            return Err(Error::Static(INVALID_BANNER_ERROR));
+        }
+
+        let banner = std::str::from_utf8(bytes).map_err(|_| Error::Static(INVALID_BANNER_ERROR))?.trim();
+
+        let (identification, comments) = match banner.split_once(COMMENTS_SEPARATOR) {
+            Some((identification, comments)) => (identification, Some(comments.trim().to_string())),
+            None => (banner, None),
+        };
+
+        let parsed = identification.strip_prefix(protocol::PROTOCOL_NAME)
+            .and_then(|rest| rest.strip_prefix(NAME_SEPARATOR))
+            .and_then(|rest| rest.split_once(SOFTWARE_SEPARATOR))
+            .and_then(|(version, software)| {
+                let (major, minor) = version.split_once(VERSION_SEPARATOR)?;
+                Some((major.parse().ok()?, minor.parse().ok()?, software))
+            });
+
+        match parsed {
+            Some((major, minor, software)) if !software.is_empty() && !software.contains(SOFTWARE_SEPARATOR) => {
+                Ok(Self { major, minor, software: software.to_string(), comments })
+            },
+            _ => Err(Error::Static(INVALID_BANNER_ERROR)),
+        }
+    }
+
+    //Only the same major version works together
+    pub fn is_compatible(&self, other: &Banner) -> bool {
+        self.major == other.major
+    }
+
+    //Fails with both versions, if the other machine is not compatible
+    pub fn check_compatible(&self, other: &Banner) -> Result<()> {
+
+        if !self.is_compatible(other) {
+            return Err(Error::IncompatibleVersion(self.version(), other.version()));
+        }
+
+        Ok(())
+    }
+
+    pub fn version(&self) -> String {
+        format!("{}{}{}", self.major, VERSION_SEPARATOR, self.minor)
+    }
+}
+
+impl fmt::Display for Banner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+
+        write!(f, "{}{}{}{}{}", protocol::PROTOCOL_NAME, NAME_SEPARATOR, self.version(), SOFTWARE_SEPARATOR, self.software)?;
+
+        if let Some(comments) = &self.comments {
+            write!(f, "{}{}", COMMENTS_SEPARATOR, comments)?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,74 +1,44 @@
-use std::{io::{Read, Write}, net::{Ipv4Addr, SocketAddrV4, TcpStream}};
-use crate::{crypto, file_sys, session::{protocol, utils}};
+use std::net::TcpStream;
+use crate::{crypto, file_sys, session::utils};
+use crate::crypto::algorithms::HostKeyAlgorithm;
+use crate::crypto::key_type::KeyType;
 use crate::error::{Result, Error};
-use std::str;
+use crate::session::message::{self, Message, PublicKeyRequest};
 
-const CHALLENGE_STRING_SIZE: usize = 32;
+const HOST_KEY_TYPE_ERROR: &str = "The server sent a host key of a different type from the negotiated one";
 
-pub fn handle_public_key_verification(socket: &SocketAddrV4, stream: &mut TcpStream) -> Result<()> {
+// the names are the host known names, as in utils::known_host_names,
+// returns the verified server public key
+pub fn handle_public_key_verification(names: &[String], stream: &mut TcpStream, batch_mode: bool, algorithm: HostKeyAlgorithm) -> Result<String> {
     // asks for the other machine public key
     let public_key_pem = ask_public_key(stream)?;
 
-    let address: Ipv4Addr = *socket.ip();
-
-    // gets the server stored key
-    let stored_public_key_pem = file_sys::get_known_host_key(&address)?;
-
-    // verifies if the keys match, and if not, will warn the user
-    file_sys::handle_key_verification_on_known_hosts(&stored_public_key_pem, &public_key_pem, &address)?;
+    // the key must be of the negotiated type
+    if KeyType::of_public_pem(&public_key_pem) != Some(algorithm.key_type()) {
+        return Err(Error::Static(HOST_KEY_TYPE_ERROR));
+    }
 
-    // tests if the server actually has the private key
-    verify_server_private_key_with_challenge(stream, &public_key_pem)?;
+    // verifies if the keys match the stored ones, and if not, will warn the user
+    file_sys::handle_key_verification_on_known_hosts(names, &public_key_pem, batch_mode)?;
 
-    Ok(())
+    Ok(public_key_pem)
 }
 
 
 // asks the other machine for their public key, for comparing
 fn ask_public_key(stream: &mut TcpStream) -> Result<String> {
     // requests the server a public key
-    let byte = protocol::SsshMessages::PublicKey as u8;
-    stream.write_all(&[byte])?;
+    utils::write_message(stream, &Message::PublicKey(PublicKeyRequest))?;
 
-    let buffer = utils::read_from_tcp(stream)?;
-
-    match str::from_utf8(&buffer) {
-        Ok(public_key_pem) => Ok(public_key_pem.trim().to_string()),
-        Err(e) => Err(Error::from(e)),
+    match utils::read_message(stream)? {
+        Message::PublicKeyReply(reply) => Ok(reply.public_key_pem.trim().to_string()),
+        _ => Err(Error::Static(message::UNEXPECTED_MESSAGE_ERROR)),
     }
 }
 
-// will create a public key challenge to test if the other machine has the private key 
-// of the public key, where this function will ask the server to sign with their private key 
-// a random 32 bytes string, and we must verify it with the public key.
-fn verify_server_private_key_with_challenge(stream: &mut TcpStream, public_key_pem: &str) -> Result<()> {
-    // generates the 32 random string
-    let binding = crypto::generate_random_string(CHALLENGE_STRING_SIZE);
-    let random_str = binding.as_bytes();
-
-    // sends a byte for starting the challenge
-    let byte = protocol::SsshMessages::Challenge as u8;
-    stream.write_all(&[byte])?;
-    stream.write_all(random_str)?;
-
-    // then read the signature
-    let buffer = utils::read_from_tcp(stream)?;
-    let signature = &buffer[..];
-
-    crypto::is_valid_signature_sha256(public_key_pem, random_str, signature)
+// verifies the server signature of the session hash, sent on the key exchange reply,
+// where the hash covers the shared key and both key exchange public values, so only
+// the owner of the verified private key could have made this key exchange with us
+pub fn verify_host_signature(public_key_pem: &str, algorithm: HostKeyAlgorithm, session_hash: &[u8], signature: &[u8]) -> Result<()> {
+    crypto::is_valid_signature(algorithm, public_key_pem, session_hash, signature)
 }
-
-
-/// Sends a string to the server, receives a signed response, and validates it
-pub fn send_and_verify_signed_message(stream: &mut TcpStream,public_key_pem: &str,message: &str,) -> Result<()> {
-    // Send the original message
-    let msg_bytes = message.as_bytes();
-    stream.write_all(&(msg_bytes.len() as u8).to_be_bytes())?; // send size first
-    stream.write_all(msg_bytes)?;
-
-    let sig_buf = utils::read_from_tcp(stream)?;
-
-    // Validate the signature with the provided public key
-    crypto::is_valid_signature_sha256(public_key_pem, msg_bytes, &sig_buf)
-
-}
\ No newline at end of file
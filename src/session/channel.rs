@@ -0,0 +1,644 @@
+/*
+ * ###################################################
+ * File responsible for the channels, so one
+ * authenticated session carries several shells,
+ * commands, transfers and forwarded ports at once.
+ *
+ * Either machine opens a channel, with the id it
+ * chose and his receive window:
+ *
+ * ChannelOpen -> ChannelOpenConfirmation | ChannelOpenFailure
+ *
+ * Then every message of the channel has the id of
+ * the receiver, as Data, Eof or the requests, as Exec.
+ *
+ * Flow control: the sender may only send Data and
+ * ErrorData up to the window of the receiver, which
+ * grows with ChannelWindowAdjust once the receiver
+ * consumed the bytes, so a slow channel never stops
+ * the other ones, and data over our window is a
+ * protocol error, which ends the session.
+ *
+ * A channel ends once both machines sent ChannelClose,
+ * and the session ends with End, or when the socket
+ * closes, which closes every channel.
+ *
+ * The client may also ask the server to listen on a
+ * port, which the server answers in order:
+ *
+ * ForwardRequest -> ForwardSuccess | ForwardFailure
+ *
+ * A thread dispatches the received messages to each
+ * channel, and the channels opened or the forwards
+ * asked by the other machine to a handler, which
+ * accepts or rejects them.
+ * ###################################################
+ */
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+
+use crate::error::{Error, Result};
+use crate::session::message::{
+    self, ChannelClose, ChannelKind, ChannelOpen, ChannelOpenConfirmation, ChannelOpenFailure,
+    ChannelWindowAdjust, Data, End, Eof, ErrorData, ForwardFailure, ForwardRequest, ForwardSuccess, Message,
+};
+use crate::session::transport::{PacketReceiver, PacketSender, SecureChannel};
+use crate::session::utils;
+
+//The bytes the other machine may send before we consume them
+pub const CHANNEL_WINDOW_SIZE: u32 = 2 * 1024 * 1024;
+//The biggest data of a message, so it always fits on a packet
+pub const CHANNEL_MAX_PACKET: u32 = 32 * 1024;
+
+const CHANNEL_CLOSED_ERROR: &str = "The channel is closed";
+const WINDOW_EXCEEDED_ERROR: &str = "The other machine sent more data than the channel window";
+
+//Shared by the dispatcher and every channel
+struct Shared {
+    sender: Mutex<PacketSender>,
+    channels: Mutex<HashMap<u32, Slot>>,
+    next_id: AtomicU32,
+    //Who waits each reply of a forward request, in the order they were sent
+    replies: Mutex<VecDeque<Sender<Message>>>,
+}
+
+//What the dispatcher knows of a channel, by our id
+struct Slot {
+    queue: Sender<Message>,
+    window: Arc<Window>,
+    //The bytes the other machine may still send, so the queue never holds more than the window
+    receive_window: u32,
+    close_sent: bool,
+    close_received: bool,
+}
+
+//The bytes we may still send on a channel
+struct Window {
+    state: Mutex<WindowState>,
+    changed: Condvar,
+}
+
+struct WindowState {
+    available: u64,
+    closed: bool,
+}
+
+//The session, after the authentication, as channels
+pub struct Mux {
+    shared: Arc<Shared>,
+    dispatcher: JoinHandle<Result<()>>,
+}
+
+//Opens channels on the session, may be cloned to each thread which opens them
+#[derive(Clone)]
+pub struct ChannelOpener {
+    shared: Arc<Shared>,
+}
+
+//What the other machine asks, for the handler
+pub enum Incoming {
+    Channel(IncomingChannel),
+    Forward(IncomingForward),
+}
+
+//A forward asked by the other machine, until it is accepted or rejected
+pub struct IncomingForward {
+    pub bind: String,
+    pub port: u16,
+    shared: Arc<Shared>,
+}
+
+//A channel opened by the other machine, until it is accepted or rejected
+pub struct IncomingChannel {
+    pub kind: ChannelKind,
+    remote_id: u32,
+    window: u32,
+    max_packet: u32,
+    shared: Arc<Shared>,
+}
+
+//Sends on a channel, may be cloned to each thread which sends
+#[derive(Clone)]
+pub struct ChannelWriter {
+    shared: Arc<Shared>,
+    local_id: u32,
+    remote_id: u32,
+    max_packet: u32,
+    window: Arc<Window>,
+}
+
+//A channel, which receives his messages and closes it when dropped
+pub struct Channel {
+    writer: ChannelWriter,
+    queue: Receiver<Message>,
+    consumed: u32,
+}
+
+impl Mux {
+
+    //Starts dispatching the received messages, where the handler receives what the other machine asks
+    pub fn start<F>(channel: SecureChannel, handler: F) -> Self
+    where
+        F: FnMut(Incoming) + Send + 'static,
+    {
+        let (sender, receiver) = channel.split();
+
+        let shared = Arc::new(Shared {
+            sender: Mutex::new(sender),
+            channels: Mutex::new(HashMap::new()),
+            next_id: AtomicU32::new(0),
+            replies: Mutex::new(VecDeque::new()),
+        });
+
+        let dispatcher_shared = Arc::clone(&shared);
+        let dispatcher = thread::spawn(move || {
+            let result = dispatch(&dispatcher_shared, receiver, handler);
+            dispatcher_shared.close_all();
+            result
+        });
+
+        Self { shared, dispatcher }
+    }
+
+    pub fn open(&self, kind: ChannelKind) -> Result<Channel> {
+        self.opener().open(kind)
+    }
+
+    pub fn opener(&self) -> ChannelOpener {
+        ChannelOpener { shared: Arc::clone(&self.shared) }
+    }
+
+    //Asks the other machine to listen on the port, waiting for his answer
+    pub fn request_forward(&self, bind: &str, port: u16) -> Result<()> {
+
+        let (reply, queue) = mpsc::channel();
+
+        //The replies come in the order of the requests
+        {
+            let mut replies = self.shared.replies.lock().unwrap();
+            self.shared.send(&Message::ForwardRequest(ForwardRequest { bind: bind.to_string(), port }))?;
+            replies.push_back(reply);
+        }
+
+        match queue.recv() {
+            Ok(Message::ForwardSuccess(_)) => Ok(()),
+            Ok(Message::ForwardFailure(failure)) => Err(Error::ForwardFailure(failure.reason)),
+            Ok(_) => Err(Error::Static(message::UNEXPECTED_MESSAGE_ERROR)),
+            Err(_) => Err(Error::Static(CHANNEL_CLOSED_ERROR)),
+        }
+    }
+
+    //Tells the other machine the session is over
+    pub fn end(&self) -> Result<()> {
+        self.shared.send(&Message::End(End))
+    }
+
+    //Waits until the other machine ends the session or leaves
+    pub fn wait(self) -> Result<()> {
+        self.dispatcher.join().unwrap_or(Err(Error::Static(CHANNEL_CLOSED_ERROR)))
+    }
+}
+
+impl ChannelOpener {
+
+    //Opens a channel, waiting until the other machine confirms it
+    pub fn open(&self, kind: ChannelKind) -> Result<Channel> {
+
+        let (local_id, queue, window) = self.shared.register();
+
+        self.shared.send(&Message::ChannelOpen(ChannelOpen {
+            sender_channel: local_id,
+            kind,
+            window: CHANNEL_WINDOW_SIZE,
+            max_packet: CHANNEL_MAX_PACKET,
+        }))?;
+
+        match queue.recv() {
+            Ok(Message::ChannelOpenConfirmation(confirmation)) => {
+                window.add(confirmation.window);
+                Ok(Channel::new(&self.shared, local_id, confirmation.sender_channel, confirmation.max_packet, window, queue))
+            },
+            Ok(Message::ChannelOpenFailure(failure)) => Err(Error::ChannelOpenFailure(failure.reason)),
+            Ok(_) => Err(Error::Static(message::UNEXPECTED_MESSAGE_ERROR)),
+            Err(_) => Err(Error::Static(CHANNEL_CLOSED_ERROR)),
+        }
+    }
+}
+
+impl IncomingForward {
+
+    //The forwarded connections are opened as channels, with the opener
+    pub fn accept(self) -> Result<ChannelOpener> {
+        self.shared.send(&Message::ForwardSuccess(ForwardSuccess))?;
+        Ok(ChannelOpener { shared: self.shared })
+    }
+
+    pub fn reject(self, reason: &str) -> Result<()> {
+        self.shared.send(&Message::ForwardFailure(ForwardFailure { reason: reason.to_string() }))
+    }
+}
+
+impl IncomingChannel {
+
+    pub fn accept(self) -> Result<Channel> {
+
+        let (local_id, queue, window) = self.shared.register();
+        window.add(self.window);
+
+        self.shared.send(&Message::ChannelOpenConfirmation(ChannelOpenConfirmation {
+            channel: self.remote_id,
+            sender_channel: local_id,
+            window: CHANNEL_WINDOW_SIZE,
+            max_packet: CHANNEL_MAX_PACKET,
+        }))?;
+
+        Ok(Channel::new(&self.shared, local_id, self.remote_id, self.max_packet, window, queue))
+    }
+
+    pub fn reject(self, reason: &str) -> Result<()> {
+        self.shared.send(&Message::ChannelOpenFailure(ChannelOpenFailure { channel: self.remote_id, reason: reason.to_string() }))
+    }
+}
+
+impl Channel {
+
+    fn new(shared: &Arc<Shared>, local_id: u32, remote_id: u32, max_packet: u32, window: Arc<Window>, queue: Receiver<Message>) -> Self {
+
+        //The data of a message never goes over our packet size
+        let max_packet = max_packet.clamp(1, CHANNEL_MAX_PACKET);
+
+        let writer = ChannelWriter { shared: Arc::clone(shared), local_id, remote_id, max_packet, window };
+
+        Self { writer, queue, consumed: 0 }
+    }
+
+    /*
+     * Receives the next message of the channel, None once the other
+     * machine closed it, or the session ended, where the channel
+     * is also closed by us
+     */
+    pub fn receive(&mut self) -> Option<Message> {
+
+        let message = match self.queue.recv() {
+            Ok(Message::ChannelClose(_)) | Err(_) => {
+                let _ = self.writer.close();
+                return None;
+            },
+            Ok(message) => message,
+        };
+
+        if let Message::Data(Data { data, .. }) | Message::ErrorData(ErrorData { data, .. }) = &message {
+            self.consume(data.len());
+        }
+
+        Some(message)
+    }
+
+    pub fn writer(&self) -> ChannelWriter {
+        self.writer.clone()
+    }
+
+    //Opens other channels on the same session, as for the connections of his forwarded agent
+    pub fn opener(&self) -> ChannelOpener {
+        ChannelOpener { shared: Arc::clone(&self.writer.shared) }
+    }
+
+    //The channel id of the other machine, which the messages sent must have
+    pub fn remote_id(&self) -> u32 {
+        self.writer.remote_id
+    }
+
+    pub fn send_message(&self, message: &Message) -> Result<()> {
+        self.writer.send_message(message)
+    }
+
+    pub fn send_data(&self, data: &[u8]) -> Result<()> {
+        self.writer.send_data(data)
+    }
+
+    pub fn send_eof(&self) -> Result<()> {
+        self.writer.send_eof()
+    }
+
+    pub fn close(&self) -> Result<()> {
+        self.writer.close()
+    }
+
+    //Once half of the window is consumed, the other machine may send it again
+    fn consume(&mut self, size: usize) {
+
+        self.consumed = self.consumed.saturating_add(size as u32);
+
+        if self.consumed >= CHANNEL_WINDOW_SIZE / 2 {
+
+            //The window grows before the other machine knows it, so his next data always fits
+            if let Some(slot) = self.writer.shared.channels.lock().unwrap().get_mut(&self.writer.local_id) {
+                slot.receive_window = slot.receive_window.saturating_add(self.consumed);
+            }
+
+            let adjust = ChannelWindowAdjust { channel: self.writer.remote_id, bytes: self.consumed };
+            let _ = self.writer.send_message(&Message::ChannelWindowAdjust(adjust));
+            self.consumed = 0;
+        }
+    }
+}
+
+impl Drop for Channel {
+    fn drop(&mut self) {
+        let _ = self.writer.close();
+    }
+}
+
+impl ChannelWriter {
+
+    pub fn remote_id(&self) -> u32 {
+        self.remote_id
+    }
+
+    pub fn send_message(&self, message: &Message) -> Result<()> {
+        self.shared.send(message)
+    }
+
+    //Sends the data, waiting for the window of the other machine
+    pub fn send_data(&self, data: &[u8]) -> Result<()> {
+        self.send_bytes(data, |channel, data| Message::Data(Data { channel, data }))
+    }
+
+    //Sends the data as stderr, also limited by the window
+    pub fn send_error_data(&self, data: &[u8]) -> Result<()> {
+        self.send_bytes(data, |channel, data| Message::ErrorData(ErrorData { channel, data }))
+    }
+
+    pub fn send_eof(&self) -> Result<()> {
+        self.send_message(&Message::Eof(Eof { channel: self.remote_id }))
+    }
+
+    //Sends ChannelClose once, the channel is forgotten after both machines closed it
+    pub fn close(&self) -> Result<()> {
+
+        self.window.close();
+
+        {
+            let mut channels = self.shared.channels.lock().unwrap();
+
+            let Some(slot) = channels.get_mut(&self.local_id) else {
+                return Ok(());
+            };
+
+            if slot.close_sent {
+                return Ok(());
+            }
+
+            slot.close_sent = true;
+
+            if slot.close_received {
+                channels.remove(&self.local_id);
+            }
+        }
+
+        self.send_message(&Message::ChannelClose(ChannelClose { channel: self.remote_id }))
+    }
+
+    fn send_bytes<F: Fn(u32, Vec<u8>) -> Message>(&self, mut data: &[u8], to_message: F) -> Result<()> {
+
+        while !data.is_empty() {
+
+            let size = self.window.reserve(data.len().min(self.max_packet as usize))?;
+
+            self.send_message(&to_message(self.remote_id, data[..size].to_vec()))?;
+
+            data = &data[size..];
+        }
+
+        Ok(())
+    }
+}
+
+impl Shared {
+
+    fn send(&self, message: &Message) -> Result<()> {
+        self.sender.lock().unwrap().send_message(message)
+    }
+
+    //Creates a channel with a new id of ours, without a window until the other machine says it
+    fn register(&self) -> (u32, Receiver<Message>, Arc<Window>) {
+
+        let local_id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (queue, receiver) = mpsc::channel();
+        let window = Arc::new(Window::new());
+
+        let slot = Slot { queue, window: Arc::clone(&window), receive_window: CHANNEL_WINDOW_SIZE, close_sent: false, close_received: false };
+        self.channels.lock().unwrap().insert(local_id, slot);
+
+        (local_id, receiver, window)
+    }
+
+    //Once the session ends, every channel is closed, so no one waits forever
+    fn close_all(&self) {
+
+        for (_, slot) in self.channels.lock().unwrap().drain() {
+            slot.window.close();
+        }
+
+        self.replies.lock().unwrap().clear();
+    }
+}
+
+impl Window {
+
+    fn new() -> Self {
+        Self { state: Mutex::new(WindowState { available: 0, closed: false }), changed: Condvar::new() }
+    }
+
+    fn add(&self, bytes: u32) {
+        self.state.lock().unwrap().available += bytes as u64;
+        self.changed.notify_all();
+    }
+
+    fn close(&self) {
+        self.state.lock().unwrap().closed = true;
+        self.changed.notify_all();
+    }
+
+    //Waits until some bytes may be sent, returning how many of the wanted ones
+    fn reserve(&self, wanted: usize) -> Result<usize> {
+
+        let mut state = self.state.lock().unwrap();
+
+        while state.available == 0 && !state.closed {
+            state = self.changed.wait(state).unwrap();
+        }
+
+        if state.closed {
+            return Err(Error::Static(CHANNEL_CLOSED_ERROR));
+        }
+
+        let size = state.available.min(wanted as u64);
+        state.available -= size;
+
+        Ok(size as usize)
+    }
+}
+
+//Reads every message, sending each one to his channel, until the session ends
+fn dispatch<F: FnMut(Incoming)>(shared: &Arc<Shared>, mut receiver: PacketReceiver, mut handler: F) -> Result<()> {
+
+    loop {
+
+        //The other machine closing the socket is the same as End
+        let message = match receiver.receive_message() {
+            Ok(m) => m,
+            Err(e) if utils::is_connection_closed(&e) => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        match message {
+            Message::End(_) => return Ok(()),
+            Message::ChannelOpen(open) => handler(Incoming::Channel(IncomingChannel {
+                kind: open.kind,
+                remote_id: open.sender_channel,
+                window: open.window,
+                max_packet: open.max_packet,
+                shared: Arc::clone(shared),
+            })),
+            Message::ForwardRequest(request) => handler(Incoming::Forward(IncomingForward {
+                bind: request.bind,
+                port: request.port,
+                shared: Arc::clone(shared),
+            })),
+            reply @ (Message::ForwardSuccess(_) | Message::ForwardFailure(_)) => {
+                if let Some(queue) = shared.replies.lock().unwrap().pop_front() {
+                    let _ = queue.send(reply);
+                }
+            },
+            Message::ChannelWindowAdjust(adjust) => {
+                if let Some(slot) = shared.channels.lock().unwrap().get(&adjust.channel) {
+                    slot.window.add(adjust.bytes);
+                }
+            },
+            Message::ChannelClose(close) => {
+
+                let id = close.channel;
+                let mut channels = shared.channels.lock().unwrap();
+
+                let Some(slot) = channels.get_mut(&id) else {
+                    continue;
+                };
+
+                slot.close_received = true;
+                slot.window.close();
+                let _ = slot.queue.send(Message::ChannelClose(close));
+
+                if slot.close_sent {
+                    channels.remove(&id);
+                }
+            },
+            Message::ChannelOpenFailure(failure) => {
+                //The channel was never opened, so it is forgotten
+                if let Some(slot) = shared.channels.lock().unwrap().remove(&failure.channel) {
+                    let _ = slot.queue.send(Message::ChannelOpenFailure(failure));
+                }
+            },
+            other => {
+
+                let Some(id) = other.channel() else {
+                    return Err(Error::Static(message::UNEXPECTED_MESSAGE_ERROR));
+                };
+
+                //A message of a closed channel, sent before the other machine knew it, is dropped
+                let mut channels = shared.channels.lock().unwrap();
+
+                let Some(slot) = channels.get_mut(&id) else {
+                    continue;
+                };
+
+                if let Message::Data(Data { data, .. }) | Message::ErrorData(ErrorData { data, .. }) = &other {
+                    slot.receive_window = u32::try_from(data.len()).ok()
+                        .and_then(|size| slot.receive_window.checked_sub(size))
+                        .ok_or(Error::Static(WINDOW_EXCEEDED_ERROR))?;
+                }
+
+                let _ = slot.queue.send(other);
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::rekey::RekeyLimits;
+    use crate::session::transport::tests::secure_pair;
+
+    //Accepts every channel the other machine opens, giving it to the test
+    fn accepting_mux(channel: SecureChannel) -> (Mux, Receiver<Channel>) {
+        let (accepted, queue) = mpsc::channel();
+
+        let mux = Mux::start(channel, move |incoming| {
+            if let Incoming::Channel(incoming) = incoming {
+                let _ = accepted.send(incoming.accept().unwrap());
+            }
+        });
+
+        (mux, queue)
+    }
+
+    //The window grows as the data is consumed, so the data of several windows arrives whole
+    #[test]
+    fn data_over_several_windows_is_received() {
+        let (client, server) = secure_pair("aes256-ctr", RekeyLimits::default());
+        let (server_mux, accepted) = accepting_mux(server);
+        let client_mux = Mux::start(client, |_| {});
+
+        let channel = client_mux.open(ChannelKind::Session).unwrap();
+        let mut received = accepted.recv().unwrap();
+
+        let size = 3 * CHANNEL_WINDOW_SIZE as usize + 1;
+        let sender = thread::spawn(move || channel.send_data(&vec![7u8; size]));
+
+        let mut total = 0;
+        while let Some(message) = received.receive() {
+            let Message::Data(data) = message else { panic!("Expected only data") };
+            assert!(data.data.iter().all(|byte| *byte == 7));
+            total += data.data.len();
+        }
+
+        sender.join().unwrap().unwrap();
+        assert_eq!(total, size);
+
+        client_mux.end().unwrap();
+        server_mux.wait().unwrap();
+    }
+
+    //Data the receiver never allowed is a protocol error, even if the channel is not read
+    #[test]
+    fn data_over_the_window_ends_the_session() {
+        let (mut client, server) = secure_pair("aes256-ctr", RekeyLimits::default());
+        let (server_mux, accepted) = accepting_mux(server);
+
+        client.send_message(&Message::ChannelOpen(ChannelOpen {
+            sender_channel: 0,
+            kind: ChannelKind::Session,
+            window: CHANNEL_WINDOW_SIZE,
+            max_packet: CHANNEL_MAX_PACKET,
+        })).unwrap();
+
+        let Message::ChannelOpenConfirmation(confirmation) = client.receive_message().unwrap() else { panic!("Expected the confirmation") };
+        assert_eq!(confirmation.window, CHANNEL_WINDOW_SIZE);
+
+        let _unread = accepted.recv().unwrap();
+        let data = vec![0u8; CHANNEL_MAX_PACKET as usize];
+
+        for _ in 0..CHANNEL_WINDOW_SIZE / CHANNEL_MAX_PACKET {
+            client.send_message(&Message::Data(Data { channel: confirmation.sender_channel, data: data.clone() })).unwrap();
+        }
+
+        client.send_message(&Message::ErrorData(ErrorData { channel: confirmation.sender_channel, data: vec![0u8] })).unwrap();
+
+        assert!(matches!(server_mux.wait(), Err(Error::Static(WINDOW_EXCEEDED_ERROR))));
+    }
+}
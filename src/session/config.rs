@@ -0,0 +1,44 @@
+/*
+ * ##############################################
+ * File responsible for the session configuration,
+ * chosen by the user on the command line.
+ *
+ * port - The server port, DEFAULT_PORT if None
+ * identity_file - The user private key, ~/.sssh/id_rsa if None
+ * batch_mode - Never asks the user, failing instead
+ * algorithms - The algorithms lists, by order of preference
+ * rekey_limits - When the transport keys are renewed
+ * local_forwards - The local ports forwarded by the server, as -L
+ * remote_forwards - The server ports forwarded by the client, as -R
+ * dynamic_forwards - The local SOCKS proxies, as -D
+ * forward_agent - If the agent is forwarded to the shell or command, as -A
+ * ##############################################
+ */
+
+use std::path::PathBuf;
+
+use crate::crypto::algorithms::AlgorithmLists;
+use crate::session::forward::Forward;
+use crate::session::protocol;
+use crate::session::rekey::RekeyLimits;
+use crate::session::socks::DynamicForward;
+
+#[derive(Clone, Default)]
+pub struct SessionConfig {
+    pub port : Option<u16>,
+    pub identity_file : Option<PathBuf>,
+    pub batch_mode : bool,
+    pub algorithms : AlgorithmLists,
+    pub rekey_limits : RekeyLimits,
+    pub local_forwards : Vec<Forward>,
+    pub remote_forwards : Vec<Forward>,
+    pub dynamic_forwards : Vec<DynamicForward>,
+    pub forward_agent : bool,
+}
+
+impl SessionConfig {
+
+    pub fn get_port(&self) -> u16 {
+        self.port.unwrap_or(protocol::DEFAULT_PORT)
+    }
+}
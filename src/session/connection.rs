@@ -6,56 +6,148 @@
  *
  */
 
-use std::{io::{Read, Write}, net::{SocketAddrV4, TcpStream}};
-
-use num_bigint::BigUint;
-
-use crate::session::{challenge, dhkeys, protocol, utils};
+use std::net::{SocketAddr, TcpStream};
+
+use crate::crypto::session_keys::SessionKeys;
+use crate::crypto::algorithms::{Algorithm, AlgorithmLists, Algorithms, HostKeyAlgorithm};
+use crate::{crypto, file_sys};
+use crate::session::config::SessionConfig;
+use crate::session::message::{self, Message, Negotiation};
+use crate::session::banner::Banner;
+use crate::session::{challenge, dhkeys, utils};
 use crate::error::{Result,Error};
 
 const CONNECTION_ERROR : &str = "Cannot connect to the given address and port";
-const PROTOCOL_ERROR: &str = "The server, is working with a different protocol on the same port";
+//The connection after the handshake, before the encryption, where
+//the keys only exist once the server signature of the exchange is verified
+pub struct Connection {
+    pub stream : TcpStream,
+    pub address : SocketAddr,
+    pub keys : SessionKeys,
+    //Signed by the server host key, and verified
+    pub session_hash : Vec<u8>,
+    pub algorithms : Algorithms,
+    //The algorithms to sign the user keys, by preference
+    pub signature_algorithms : Vec<HostKeyAlgorithm>,
+}
+
 /*
- * The function will verify if the port is valid,
- * also will verify if the host is known.
+ * The function will connect to the first address which
+ * answers, of the resolved addresses of the host, and
+ * will verify if the port is valid, then negotiates
+ * the algorithms,
+ * also will verify if the host is known, by his names.
  * This verification is made, by requesting a public key,
- * and 
+ * and comparing it with the known hosts.
  * 
- * Then will request a DH Key exchange to encrypt messages.
+ * Then will request a DH Key exchange to encrypt messages,
+ * with the user, so both compute the session hash, which
+ * the server signs with the private key of the verified one.
+ * Only after this signature is verified the session keys
+ * are derived, so no channel is keyed by an unsigned exchange.
  */
-pub fn start_connection(socket: &SocketAddrV4) -> Result<(TcpStream,BigUint)> {
+pub fn start_connection(host: &str, addresses: &[SocketAddr], user: &str, config: &SessionConfig) -> Result<Connection> {
+
+    let (mut stream, address) = connect_any(addresses)?;
+
+    verify_port(&mut stream)?;
+
+    let names = utils::known_host_names(host, &address);
+
+    let lists = prefer_known_host_keys(&config.algorithms, &names)?;
+
+    let (algorithms, negotiation, signature_algorithms) = negotiate_algorithms(&mut stream, &lists)?;
 
-    let mut stream = verify_port(socket)?;
+    crate::utils::debug(1, &format!("Negotiated {}", algorithms));
 
-    challenge::handle_public_key_verification(socket, &mut stream)?;
+    let host_key_pem = challenge::handle_public_key_verification(&names, &mut stream, config.batch_mode, algorithms.host_key)?;
     
-    //Stats an DH Key exchange and calculates the shared key
-    let shared_key = dhkeys::handle_dh_keys_exchange(&mut stream)?;
-    Ok((stream,shared_key))
+    crate::utils::debug(1, "Server host key known");
+
+    //Stats the key exchange and calculates the shared key
+    let exchange = dhkeys::handle_dh_keys_exchange(&mut stream, user, algorithms.kex)?;
+
+    let session_hash = crypto::compute_session_hash(&exchange.shared_key, &host_key_pem, user, &negotiation, &exchange.client_public_key, &exchange.server_public_key);
+
+    //Only the owner of the host key could sign this key exchange
+    challenge::verify_host_signature(&host_key_pem, algorithms.host_key, &session_hash, &exchange.signature)?;
+
+    crate::utils::debug(1, "Key exchange done, signed by the server host key");
+
+    let keys = crypto::generate_session_keys(&session_hash, exchange.shared_key, &algorithms);
+
+    Ok(Connection { stream, address, keys, session_hash, algorithms, signature_algorithms })
 
 }
 
-//Verifies if the other machine is on, and if is the same protocol
-fn verify_port(socket: &SocketAddrV4) -> Result<TcpStream>{
+/*
+ * Moves the host key algorithms, of the key types already
+ * known for the host, to the start of the list, so a server
+ * with a known key is not verified by a new unknown key,
+ * a new type is only accepted as a changed key, at hosts.rs
+ */
+fn prefer_known_host_keys(lists: &AlgorithmLists, names: &[String]) -> Result<AlgorithmLists>{
 
+    let known_types = file_sys::get_known_host_key_types(names)?;
 
-    if let Ok(mut stream) = TcpStream::connect(socket){
+    let is_known = |name: &String| {
+        HostKeyAlgorithm::from_name(name).is_some_and(|algorithm| known_types.contains(&algorithm.key_type()))
+    };
 
-        let banner_as_bytes = protocol::PROTOCOL_BANNER.as_bytes();
-        let _ = stream.write_all(banner_as_bytes); //Sends the banner as bytes
-        
-        let buffer = utils::read_from_tcp(&mut stream)?;
+    let mut lists = lists.clone();
+    let (known, unknown): (Vec<String>, Vec<String>) = lists.host_key.into_iter().partition(is_known);
+    lists.host_key = known.into_iter().chain(unknown).collect();
 
-        //Verifies if the banners match
-        if buffer[..].trim_ascii() != banner_as_bytes{
-            return Err(Error::Static(PROTOCOL_ERROR));
-        }
+    Ok(lists)
+}
+
+/*
+ * Sends our algorithms lists and reads the server ones,
+ * both choose the same algorithms, returning also both
+ * messages as sent and the user signature algorithms
+ */
+fn negotiate_algorithms(stream: &mut TcpStream, lists: &AlgorithmLists) -> Result<(Algorithms, Vec<u8>, Vec<HostKeyAlgorithm>)>{
+
+    let mut negotiation = Message::Negotiation(Negotiation { lists: lists.clone() }).encode()?;
+    utils::write_to_tcp(stream, &negotiation)?;
+
+    let reply = utils::read_from_tcp(stream)?;
+
+    let server_lists = match Message::decode(&reply)? {
+        Message::Negotiation(server) => server.lists,
+        _ => return Err(Error::Static(message::UNEXPECTED_MESSAGE_ERROR)),
+    };
+
+    negotiation.extend_from_slice(&reply);
+
+    Ok((lists.negotiate(&server_lists)?, negotiation, lists.signature_algorithms(&server_lists)))
+}
+
+//Tries each address, by order, until one is connected
+fn connect_any(addresses: &[SocketAddr]) -> Result<(TcpStream,SocketAddr)>{
+
+    for address in addresses {
 
-        Ok(stream)
+        crate::utils::debug(2, &format!("Trying {}", address));
 
-    }else{
-        Err(Error::Static(CONNECTION_ERROR))
+        match TcpStream::connect(address) {
+            Ok(stream) => return Ok((stream, *address)),
+            Err(e) => crate::utils::debug(2, &format!("Connection to {} failed: {}", address, e)),
+        }
     }
 
+    Err(Error::Static(CONNECTION_ERROR))
+}
+
+//Verifies if the other machine is the same protocol, on a compatible version
+fn verify_port(stream: &mut TcpStream) -> Result<()>{
+
+    let ours = Banner::ours();
+    utils::write_to_tcp(stream, ours.to_string().as_bytes())?; //Sends the banner as bytes
+    
+    let theirs = Banner::parse(&utils::read_from_tcp(stream)?)?;
+
+    crate::utils::debug(1, &format!("Server protocol version {}, software {}", theirs.version(), theirs.software));
 
+    ours.check_compatible(&theirs)
 }
@@ -1,54 +1,55 @@
 /*
  * ####################################
- * File responsible for DH key exchange
+ * File responsible for the key exchange,
+ * with the negotiated algorithm
  * ####################################
  *
  */
 
-use std::io::{Write,Read};
 use std::net::TcpStream;
 
 use num_bigint::BigUint;
 
-use crate::crypto::dhkeys::DHKeys;
-use crate::error::Result;
-use crate::session::{protocol, utils};
+use crate::crypto::algorithms::KexAlgorithm;
+use crate::crypto::kex::KexKeys;
+use crate::error::{Error, Result};
+use crate::session::message::{self, KeyExchange, KeyExchangeReply, Message};
+use crate::session::utils;
+
+//The key exchange result, where the server signature of the session hash is not verified yet
+pub struct Exchange {
+    pub shared_key : BigUint,
+    pub client_public_key : Vec<u8>,
+    pub server_public_key : Vec<u8>,
+    pub signature : Vec<u8>,
+}
 
-pub fn handle_dh_keys_exchange(stream : &mut TcpStream) -> Result<BigUint>{
+pub fn handle_dh_keys_exchange(stream : &mut TcpStream, user : &str, algorithm : KexAlgorithm) -> Result<Exchange>{
 
-    let keys = DHKeys::new(); //Generates a new set of keys 
+    let keys = KexKeys::new(algorithm); //Generates a new set of keys 
 
-    send_keys(stream, &keys)?; //sends the keys 
+    send_keys(stream, &keys, user)?; //sends the keys 
                               
-    let public_key = receive_public_key(stream)?;
-
-    keys.compute_shared_key(&public_key)
-}  
-//Sends the keys as prime, generator and this public key for the other machine
-fn send_keys(stream : &mut TcpStream,keys: &DHKeys) -> Result<()>{
+    let reply = receive_reply(stream)?;
+    let client_public_key = keys.get_public_key();
 
+    let shared_key = keys.compute_shared_key(&reply.public_key)?;
 
-    let byte = protocol::SsshMessages::KeyExchange as u8; //sends the byte for key exchange 
-                                                    
-    stream.write_all(&[byte])?;
-
-    let exchanged_keys = keys.get_exchanged_keys(); //Gets the shared keys
-
-    let shared_key_bytes = bincode::serialize(&exchanged_keys).unwrap();
-    stream.write_all(&shared_key_bytes)?;
+    Ok(Exchange { shared_key, client_public_key, server_public_key: reply.public_key, signature: reply.signature })
+}  
+//Sends the public values, which say the algorithm, for the other machine, with the user
+fn send_keys(stream : &mut TcpStream,keys: &KexKeys, user : &str) -> Result<()>{
 
-    Ok(())
+    let client_keys = keys.get_client_keys(); //Gets the shared keys
 
+    utils::write_message(stream, &Message::KeyExchange(KeyExchange { keys: client_keys, user: user.to_string() }))
 }
 
-//First reads, the key size, then the key
-fn receive_public_key(stream :&mut TcpStream) -> Result<BigUint>{
-
-
-    let key_data = utils::read_from_tcp(stream)?;
-
-    Ok(BigUint::from_bytes_be(&key_data)) 
-
-
+//Reads the other machine public key, with his signature of the session hash
+fn receive_reply(stream :&mut TcpStream) -> Result<KeyExchangeReply>{
 
+    match utils::read_message(stream)? {
+        Message::KeyExchangeReply(reply) => Ok(reply),
+        _ => Err(Error::Static(message::UNEXPECTED_MESSAGE_ERROR)),
+    }
 }
@@ -0,0 +1,38 @@
+/*
+ * ##############################################
+ * File responsible for the command execution,
+ * on the client side, where the command runs
+ * without a terminal, so stdout and stderr are
+ * kept apart, as in relay.rs.
+ * ##############################################
+ */
+
+use crate::error::{Error, Result};
+use crate::session::message::{self, ChannelKind, Exec, Message};
+use crate::session::relay;
+use crate::session::Session;
+
+const EXEC_FAILURE_ERROR: &str = "The server could not start the command";
+
+impl Session {
+
+    //Runs the command on the server, on a new channel, relaying the stdio until it ends, returning his exit code
+    pub fn exec(&self, command: &str) -> Result<i32> {
+
+        let mut channel = self.open_channel(ChannelKind::Session)?;
+
+        self.request_agent(&mut channel)?;
+
+        channel.send_message(&Message::Exec(Exec { channel: channel.remote_id(), command: command.to_string() }))?;
+
+        match channel.receive() {
+            Some(Message::ExecSuccess(_)) => {},
+            Some(Message::ExecFailure(_)) => return Err(Error::Static(EXEC_FAILURE_ERROR)),
+            _ => return Err(Error::Static(message::UNEXPECTED_MESSAGE_ERROR)),
+        }
+
+        relay::relay_stdin(channel.writer());
+
+        relay::relay_output(&mut channel)
+    }
+}
@@ -0,0 +1,213 @@
+/*
+ * ##############################################
+ * File responsible for the port forwarding, where
+ * each forwarded TCP connection is a channel:
+ *
+ * -L [bind:]port:host:hostport - The client listens
+ *   on the port, the server connects to the host
+ * -R [bind:]port:host:hostport - The server listens
+ *   on the port, the client connects to the host
+ *
+ * Each accepted connection opens a DirectTcpip
+ * channel, or a ForwardedTcpip one from the server,
+ * and the machine receiving it connects onward,
+ * rejecting the channel if it can not.
+ *
+ * Both machines relay the bytes the same way, a
+ * closed socket is Eof, and the channel is closed
+ * once both directions ended.
+ * ##############################################
+ */
+
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::os::unix::net::UnixStream;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::error::Result;
+use crate::session::channel::{Channel, ChannelOpener, ChannelWriter, IncomingChannel};
+use crate::session::message::{ChannelKind, Message};
+use crate::utils;
+
+const DEFAULT_BIND_ADDRESS: &str = "localhost";
+const BUFFER_SIZE: usize = 32 * 1024;
+
+const UNKNOWN_FORWARD_ERROR: &str = "The client did not ask to forward the port";
+
+//Both directions of a forwarded connection
+const DIRECTIONS: u8 = 2;
+
+//A socket relayed on a channel, a TCP connection or a Unix one, as the agent socket
+pub trait RelaySocket: Read + Write + Send + Sized + 'static {
+
+    fn try_clone(&self) -> io::Result<Self>;
+
+    fn shutdown(&self, how: Shutdown) -> io::Result<()>;
+}
+
+impl RelaySocket for TcpStream {
+
+    fn try_clone(&self) -> io::Result<Self> {
+        TcpStream::try_clone(self)
+    }
+
+    fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        TcpStream::shutdown(self, how)
+    }
+}
+
+impl RelaySocket for UnixStream {
+
+    fn try_clone(&self) -> io::Result<Self> {
+        UnixStream::try_clone(self)
+    }
+
+    fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        UnixStream::shutdown(self, how)
+    }
+}
+
+//A forwarded port, as [bind:]port:host:hostport
+#[derive(Clone, Debug)]
+pub struct Forward {
+    pub bind: Option<String>,
+    pub port: u16,
+    pub host: String,
+    pub host_port: u16,
+}
+
+impl Forward {
+
+    //Where the forwarded port listens, localhost if no bind address was given
+    pub fn bind_address(&self) -> &str {
+        self.bind.as_deref().unwrap_or(DEFAULT_BIND_ADDRESS)
+    }
+}
+
+/*
+ * Listens on the local port, opening a channel for each accepted
+ * connection, which the server connects to the forward host
+ */
+pub fn listen_local(opener: ChannelOpener, forward: &Forward) -> Result<()> {
+
+    let listener = TcpListener::bind((forward.bind_address(), forward.port))?;
+
+    utils::debug(1, &format!("Forwarding {}:{} to {}:{}", forward.bind_address(), forward.port, forward.host, forward.host_port));
+
+    let forward = forward.clone();
+
+    thread::spawn(move || {
+
+        let on_error = |e: &std::io::Error| utils::debug(1, &format!("Failed to accept on the forwarded port {}, {}", forward.port, e));
+
+        utils::accept_connections(listener.incoming(), || false, on_error, |stream| {
+
+            let opener = opener.clone();
+            let forward = forward.clone();
+
+            //Opening waits for the server, so each connection has his own thread
+            thread::spawn(move || {
+
+                let originator = stream.peer_addr().map(|address| address.to_string()).unwrap_or_default();
+                let kind = ChannelKind::DirectTcpip { host: forward.host.clone(), port: forward.host_port, originator };
+
+                match opener.open(kind) {
+                    Ok(channel) => relay(channel, stream),
+                    Err(e) => eprintln!("sssh: Failed to forward to {}:{}, {}", forward.host, forward.host_port, crate::cli::error_message(&e)),
+                }
+            });
+        });
+    });
+
+    Ok(())
+}
+
+/*
+ * Connects a connection the server forwarded to the host of the remote
+ * forward it came from, on his own thread, rejecting it if there is not
+ * such forward or the host does not answer
+ */
+pub fn connect_forwarded(incoming: IncomingChannel, forwards: &Mutex<Vec<Forward>>, bind: &str, port: u16) {
+
+    let forward = forwards.lock().unwrap().iter().find(|forward| forward.bind_address() == bind && forward.port == port).cloned();
+
+    let Some(forward) = forward else {
+        let _ = incoming.reject(UNKNOWN_FORWARD_ERROR);
+        return;
+    };
+
+    thread::spawn(move || match TcpStream::connect((forward.host.as_str(), forward.host_port)) {
+        Ok(stream) => {
+            if let Ok(channel) = incoming.accept() {
+                relay(channel, stream);
+            }
+        },
+        Err(e) => {
+            let _ = incoming.reject(&e.to_string());
+        },
+    });
+}
+
+//Relays a forwarded connection both ways, until both directions end or the channel closes
+pub fn relay<S: RelaySocket>(mut channel: Channel, mut stream: S) {
+
+    let ended = Arc::new(AtomicU8::new(0));
+
+    let output = match stream.try_clone() {
+        Ok(reader) => relay_socket(reader, channel.writer(), Arc::clone(&ended)),
+        Err(_) => return,
+    };
+
+    while let Some(message) = channel.receive() {
+        match message {
+            Message::Data(data) => {
+                if stream.write_all(&data.data).is_err() {
+                    break;
+                }
+            },
+            Message::Eof(_) => {
+                let _ = stream.shutdown(Shutdown::Write);
+                end_direction(&ended, &channel.writer());
+            },
+            _ => break,
+        }
+    }
+
+    //Stops the socket reader, if the channel closed first
+    let _ = stream.shutdown(Shutdown::Both);
+    let _ = channel.close();
+    let _ = output.join();
+}
+
+//Sends what the socket receives, and Eof once it closes
+fn relay_socket<S: RelaySocket>(mut reader: S, writer: ChannelWriter, ended: Arc<AtomicU8>) -> thread::JoinHandle<()> {
+
+    thread::spawn(move || {
+
+        let mut buffer = [0u8; BUFFER_SIZE];
+
+        loop {
+            match reader.read(&mut buffer) {
+                Ok(0) | Err(_) => break,
+                Ok(size) => {
+                    if writer.send_data(&buffer[..size]).is_err() {
+                        return;
+                    }
+                },
+            }
+        }
+
+        if writer.send_eof().is_ok() {
+            end_direction(&ended, &writer);
+        }
+    })
+}
+
+//The last direction to end closes the channel
+fn end_direction(ended: &AtomicU8, writer: &ChannelWriter) {
+    if ended.fetch_add(1, Ordering::SeqCst) + 1 == DIRECTIONS {
+        let _ = writer.close();
+    }
+}
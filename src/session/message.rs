@@ -0,0 +1,643 @@
+/*
+ * ###################################################
+ * File responsible for the content of each message,
+ * shared by the client and the server, so the wire
+ * format is only defined here.
+ *
+ * A message is encoded as:
+ *
+ * MESSAGE_VERSION || message type || content
+ *
+ * Where the content is the message struct serialized
+ * with bincode, and the message type is SsshMessages.
+ *
+ * To add a new message, create his struct, his type
+ * at SsshMessages and his variant on Message.
+ *
+ * The messages of a channel have his id on the
+ * receiver, as the channel field, see channel.rs.
+ * ###################################################
+ */
+
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+
+use crate::crypto::algorithms::AlgorithmLists;
+use crate::crypto::kex::ClientKexKeys;
+use crate::error::{Error, Result};
+use crate::session::protocol::{self, SsshMessages};
+
+const UNSUPPORTED_VERSION_ERROR: &str = "Received a message with an unsupported version";
+const EMPTY_MESSAGE_ERROR: &str = "Received an empty message";
+pub const UNEXPECTED_MESSAGE_ERROR: &str = "Received an unexpected message";
+
+//Asks for the server public key
+#[derive(Serialize, Deserialize)]
+pub struct PublicKeyRequest;
+
+#[derive(Serialize, Deserialize)]
+pub struct PublicKeyReply {
+    pub public_key_pem: String,
+}
+
+//The client key exchange keys, which say the algorithm, with the user for the session hash
+#[derive(Serialize, Deserialize)]
+pub struct KeyExchange {
+    pub keys: ClientKexKeys,
+    pub user: String,
+}
+
+//The server public key, and his host key signature of the session hash, which proves the exchange is his
+#[derive(Serialize, Deserialize)]
+pub struct KeyExchangeReply {
+    pub public_key: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+//The user proves he has the private key, by signing the session hash
+#[derive(Serialize, Deserialize)]
+pub struct Auth {
+    pub user: String,
+    pub public_key_pem: String,
+    pub algorithm: String,
+    pub signature: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AuthSuccess;
+
+#[derive(Serialize, Deserialize)]
+pub struct AuthFailure;
+
+#[derive(Serialize, Deserialize)]
+pub struct End;
+
+//Asks for the user shell, with the client terminal type and size
+#[derive(Serialize, Deserialize)]
+pub struct Shell {
+    pub channel: u32,
+    pub terminal: String,
+    pub columns: u16,
+    pub rows: u16,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ShellSuccess {
+    pub channel: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ShellFailure {
+    pub channel: u32,
+}
+
+//Bytes typed by the user, or written by the shell
+#[derive(Serialize, Deserialize)]
+pub struct Data {
+    pub channel: u32,
+    pub data: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct WindowChange {
+    pub channel: u32,
+    pub columns: u16,
+    pub rows: u16,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Eof {
+    pub channel: u32,
+}
+
+//Asks to run a command with the user shell, without a terminal
+#[derive(Serialize, Deserialize)]
+pub struct Exec {
+    pub channel: u32,
+    pub command: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ExecSuccess {
+    pub channel: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ExecFailure {
+    pub channel: u32,
+}
+
+//Bytes written by the command on stderr, while Data is stdout
+#[derive(Serialize, Deserialize)]
+pub struct ErrorData {
+    pub channel: u32,
+    pub data: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ExitStatus {
+    pub channel: u32,
+    pub code: i32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ExitSignal {
+    pub channel: u32,
+    pub signal: i32,
+}
+
+//The supported algorithms, by order of preference, sent by both machines
+#[derive(Serialize, Deserialize)]
+pub struct Negotiation {
+    pub lists: AlgorithmLists,
+}
+
+//A new key exchange, of the negotiated algorithm, sent by both machines
+#[derive(Serialize, Deserialize)]
+pub struct Rekey {
+    pub keys: ClientKexKeys,
+}
+
+//The sender uses the new keys from the next packet
+#[derive(Serialize, Deserialize)]
+pub struct NewKeys;
+
+//What a channel carries
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChannelKind {
+    //A shell or a command
+    Session,
+    //A TCP connection the receiver makes to the host, for the originator address
+    DirectTcpip { host: String, port: u16, originator: String },
+    //A connection to a port the receiver asked to forward, by his bind address and port
+    ForwardedTcpip { bind: String, port: u16, originator: String },
+    //A connection to the agent of the receiver, from the forwarded agent socket
+    AuthAgent,
+}
+
+//Opens a channel, where the sender channel is the id chosen by the sender
+#[derive(Serialize, Deserialize)]
+pub struct ChannelOpen {
+    pub sender_channel: u32,
+    pub kind: ChannelKind,
+    pub window: u32,
+    pub max_packet: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ChannelOpenConfirmation {
+    pub channel: u32,
+    pub sender_channel: u32,
+    pub window: u32,
+    pub max_packet: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ChannelOpenFailure {
+    pub channel: u32,
+    pub reason: String,
+}
+
+//The receiver of the channel may read more bytes
+#[derive(Serialize, Deserialize)]
+pub struct ChannelWindowAdjust {
+    pub channel: u32,
+    pub bytes: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ChannelClose {
+    pub channel: u32,
+}
+
+//Asks the server to listen on the bind address and port, each connection is a ForwardedTcpip channel
+#[derive(Serialize, Deserialize)]
+pub struct ForwardRequest {
+    pub bind: String,
+    pub port: u16,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ForwardSuccess;
+
+#[derive(Serialize, Deserialize)]
+pub struct ForwardFailure {
+    pub reason: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Subsystem {
+    pub channel: u32,
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SubsystemSuccess {
+    pub channel: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SubsystemFailure {
+    pub channel: u32,
+}
+
+//Asks for an agent socket on the server, told to the shell or command of the channel
+#[derive(Serialize, Deserialize)]
+pub struct AgentForward {
+    pub channel: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AgentForwardSuccess {
+    pub channel: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AgentForwardFailure {
+    pub channel: u32,
+}
+
+pub enum Message {
+    PublicKey(PublicKeyRequest),
+    PublicKeyReply(PublicKeyReply),
+    KeyExchange(KeyExchange),
+    KeyExchangeReply(KeyExchangeReply),
+    Auth(Auth),
+    AuthSuccess(AuthSuccess),
+    AuthFailure(AuthFailure),
+    End(End),
+    Shell(Shell),
+    ShellSuccess(ShellSuccess),
+    ShellFailure(ShellFailure),
+    Data(Data),
+    WindowChange(WindowChange),
+    Eof(Eof),
+    Exec(Exec),
+    ExecSuccess(ExecSuccess),
+    ExecFailure(ExecFailure),
+    ErrorData(ErrorData),
+    ExitStatus(ExitStatus),
+    ExitSignal(ExitSignal),
+    Negotiation(Negotiation),
+    Rekey(Rekey),
+    NewKeys(NewKeys),
+    ChannelOpen(ChannelOpen),
+    ChannelOpenConfirmation(ChannelOpenConfirmation),
+    ChannelOpenFailure(ChannelOpenFailure),
+    ChannelWindowAdjust(ChannelWindowAdjust),
+    ChannelClose(ChannelClose),
+    ForwardRequest(ForwardRequest),
+    ForwardSuccess(ForwardSuccess),
+    ForwardFailure(ForwardFailure),
+    Subsystem(Subsystem),
+    SubsystemSuccess(SubsystemSuccess),
+    SubsystemFailure(SubsystemFailure),
+    AgentForward(AgentForward),
+    AgentForwardSuccess(AgentForwardSuccess),
+    AgentForwardFailure(AgentForwardFailure),
+}
+
+impl Message {
+
+    pub fn get_type(&self) -> SsshMessages {
+        match self {
+            Message::PublicKey(_) => SsshMessages::PublicKey,
+            Message::PublicKeyReply(_) => SsshMessages::PublicKeyReply,
+            Message::KeyExchange(_) => SsshMessages::KeyExchange,
+            Message::KeyExchangeReply(_) => SsshMessages::KeyExchangeReply,
+            Message::Auth(_) => SsshMessages::Auth,
+            Message::AuthSuccess(_) => SsshMessages::AuthSuccess,
+            Message::AuthFailure(_) => SsshMessages::AuthFailure,
+            Message::End(_) => SsshMessages::End,
+            Message::Shell(_) => SsshMessages::Shell,
+            Message::ShellSuccess(_) => SsshMessages::ShellSuccess,
+            Message::ShellFailure(_) => SsshMessages::ShellFailure,
+            Message::Data(_) => SsshMessages::Data,
+            Message::WindowChange(_) => SsshMessages::WindowChange,
+            Message::Eof(_) => SsshMessages::Eof,
+            Message::Exec(_) => SsshMessages::Exec,
+            Message::ExecSuccess(_) => SsshMessages::ExecSuccess,
+            Message::ExecFailure(_) => SsshMessages::ExecFailure,
+            Message::ErrorData(_) => SsshMessages::ErrorData,
+            Message::ExitStatus(_) => SsshMessages::ExitStatus,
+            Message::ExitSignal(_) => SsshMessages::ExitSignal,
+            Message::Negotiation(_) => SsshMessages::Negotiation,
+            Message::Rekey(_) => SsshMessages::Rekey,
+            Message::NewKeys(_) => SsshMessages::NewKeys,
+            Message::ChannelOpen(_) => SsshMessages::ChannelOpen,
+            Message::ChannelOpenConfirmation(_) => SsshMessages::ChannelOpenConfirmation,
+            Message::ChannelOpenFailure(_) => SsshMessages::ChannelOpenFailure,
+            Message::ChannelWindowAdjust(_) => SsshMessages::ChannelWindowAdjust,
+            Message::ChannelClose(_) => SsshMessages::ChannelClose,
+            Message::ForwardRequest(_) => SsshMessages::ForwardRequest,
+            Message::ForwardSuccess(_) => SsshMessages::ForwardSuccess,
+            Message::ForwardFailure(_) => SsshMessages::ForwardFailure,
+            Message::Subsystem(_) => SsshMessages::Subsystem,
+            Message::SubsystemSuccess(_) => SsshMessages::SubsystemSuccess,
+            Message::SubsystemFailure(_) => SsshMessages::SubsystemFailure,
+            Message::AgentForward(_) => SsshMessages::AgentForward,
+            Message::AgentForwardSuccess(_) => SsshMessages::AgentForwardSuccess,
+            Message::AgentForwardFailure(_) => SsshMessages::AgentForwardFailure,
+        }
+    }
+
+    //Converts the message to bytes, with the version and type first
+    pub fn encode(&self) -> Result<Vec<u8>> {
+
+        let mut bytes = vec![protocol::MESSAGE_VERSION, self.get_type() as u8];
+
+        match self {
+            Message::PublicKey(m) => encode_content(&mut bytes, m)?,
+            Message::PublicKeyReply(m) => encode_content(&mut bytes, m)?,
+            Message::KeyExchange(m) => encode_content(&mut bytes, m)?,
+            Message::KeyExchangeReply(m) => encode_content(&mut bytes, m)?,
+            Message::Auth(m) => encode_content(&mut bytes, m)?,
+            Message::AuthSuccess(m) => encode_content(&mut bytes, m)?,
+            Message::AuthFailure(m) => encode_content(&mut bytes, m)?,
+            Message::End(m) => encode_content(&mut bytes, m)?,
+            Message::Shell(m) => encode_content(&mut bytes, m)?,
+            Message::ShellSuccess(m) => encode_content(&mut bytes, m)?,
+            Message::ShellFailure(m) => encode_content(&mut bytes, m)?,
+            Message::Data(m) => encode_content(&mut bytes, m)?,
+            Message::WindowChange(m) => encode_content(&mut bytes, m)?,
+            Message::Eof(m) => encode_content(&mut bytes, m)?,
+            Message::Exec(m) => encode_content(&mut bytes, m)?,
+            Message::ExecSuccess(m) => encode_content(&mut bytes, m)?,
+            Message::ExecFailure(m) => encode_content(&mut bytes, m)?,
+            Message::ErrorData(m) => encode_content(&mut bytes, m)?,
+            Message::ExitStatus(m) => encode_content(&mut bytes, m)?,
+            Message::ExitSignal(m) => encode_content(&mut bytes, m)?,
+            Message::Negotiation(m) => encode_content(&mut bytes, m)?,
+            Message::Rekey(m) => encode_content(&mut bytes, m)?,
+            Message::NewKeys(m) => encode_content(&mut bytes, m)?,
+            Message::ChannelOpen(m) => encode_content(&mut bytes, m)?,
+            Message::ChannelOpenConfirmation(m) => encode_content(&mut bytes, m)?,
+            Message::ChannelOpenFailure(m) => encode_content(&mut bytes, m)?,
+            Message::ChannelWindowAdjust(m) => encode_content(&mut bytes, m)?,
+            Message::ChannelClose(m) => encode_content(&mut bytes, m)?,
+            Message::ForwardRequest(m) => encode_content(&mut bytes, m)?,
+            Message::ForwardSuccess(m) => encode_content(&mut bytes, m)?,
+            Message::ForwardFailure(m) => encode_content(&mut bytes, m)?,
+            Message::Subsystem(m) => encode_content(&mut bytes, m)?,
+            Message::SubsystemSuccess(m) => encode_content(&mut bytes, m)?,
+            Message::SubsystemFailure(m) => encode_content(&mut bytes, m)?,
+            Message::AgentForward(m) => encode_content(&mut bytes, m)?,
+            Message::AgentForwardSuccess(m) => encode_content(&mut bytes, m)?,
+            Message::AgentForwardFailure(m) => encode_content(&mut bytes, m)?,
+        }
+
+        Ok(bytes)
+    }
+
+    //The receiver id of the channel, for the messages of a channel
+    pub fn channel(&self) -> Option<u32> {
+        match self {
+            Message::Shell(m) => Some(m.channel),
+            Message::ShellSuccess(m) => Some(m.channel),
+            Message::ShellFailure(m) => Some(m.channel),
+            Message::Data(m) => Some(m.channel),
+            Message::WindowChange(m) => Some(m.channel),
+            Message::Eof(m) => Some(m.channel),
+            Message::Exec(m) => Some(m.channel),
+            Message::ExecSuccess(m) => Some(m.channel),
+            Message::ExecFailure(m) => Some(m.channel),
+            Message::ErrorData(m) => Some(m.channel),
+            Message::ExitStatus(m) => Some(m.channel),
+            Message::ExitSignal(m) => Some(m.channel),
+            Message::ChannelOpenConfirmation(m) => Some(m.channel),
+            Message::ChannelOpenFailure(m) => Some(m.channel),
+            Message::ChannelWindowAdjust(m) => Some(m.channel),
+            Message::ChannelClose(m) => Some(m.channel),
+            Message::Subsystem(m) => Some(m.channel),
+            Message::SubsystemSuccess(m) => Some(m.channel),
+            Message::SubsystemFailure(m) => Some(m.channel),
+            Message::AgentForward(m) => Some(m.channel),
+            Message::AgentForwardSuccess(m) => Some(m.channel),
+            Message::AgentForwardFailure(m) => Some(m.channel),
+            _ => None,
+        }
+    }
+
+    //The type of an encoded message, without decoding his content
+    pub fn message_type(bytes: &[u8]) -> Result<SsshMessages> {
+        match bytes {
+            [version, message_type, ..] if *version == protocol::MESSAGE_VERSION => SsshMessages::try_from(*message_type),
+            [_, _, ..] => Err(Error::Static(UNSUPPORTED_VERSION_ERROR)),
+            _ => Err(Error::Static(EMPTY_MESSAGE_ERROR)),
+        }
+    }
+
+    //Converts bytes to a message, failing on other versions and unknown types
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+
+        let [version, message_type, content @ ..] = bytes else {
+            return Err(Error::Static(EMPTY_MESSAGE_ERROR));
+        };
+
+        if *version != protocol::MESSAGE_VERSION {
+            return Err(Error::Static(UNSUPPORTED_VERSION_ERROR));
+        }
+
+        let message = match SsshMessages::try_from(*message_type)? {
+            SsshMessages::PublicKey => Message::PublicKey(decode_content(content)?),
+            SsshMessages::PublicKeyReply => Message::PublicKeyReply(decode_content(content)?),
+            SsshMessages::KeyExchange => Message::KeyExchange(decode_content(content)?),
+            SsshMessages::KeyExchangeReply => Message::KeyExchangeReply(decode_content(content)?),
+            SsshMessages::Auth => Message::Auth(decode_content(content)?),
+            SsshMessages::AuthSuccess => Message::AuthSuccess(decode_content(content)?),
+            SsshMessages::AuthFailure => Message::AuthFailure(decode_content(content)?),
+            SsshMessages::End => Message::End(decode_content(content)?),
+            SsshMessages::Shell => Message::Shell(decode_content(content)?),
+            SsshMessages::ShellSuccess => Message::ShellSuccess(decode_content(content)?),
+            SsshMessages::ShellFailure => Message::ShellFailure(decode_content(content)?),
+            SsshMessages::Data => Message::Data(decode_content(content)?),
+            SsshMessages::WindowChange => Message::WindowChange(decode_content(content)?),
+            SsshMessages::Eof => Message::Eof(decode_content(content)?),
+            SsshMessages::Exec => Message::Exec(decode_content(content)?),
+            SsshMessages::ExecSuccess => Message::ExecSuccess(decode_content(content)?),
+            SsshMessages::ExecFailure => Message::ExecFailure(decode_content(content)?),
+            SsshMessages::ErrorData => Message::ErrorData(decode_content(content)?),
+            SsshMessages::ExitStatus => Message::ExitStatus(decode_content(content)?),
+            SsshMessages::ExitSignal => Message::ExitSignal(decode_content(content)?),
+            SsshMessages::Negotiation => Message::Negotiation(decode_content(content)?),
+            SsshMessages::Rekey => Message::Rekey(decode_content(content)?),
+            SsshMessages::NewKeys => Message::NewKeys(decode_content(content)?),
+            SsshMessages::ChannelOpen => Message::ChannelOpen(decode_content(content)?),
+            SsshMessages::ChannelOpenConfirmation => Message::ChannelOpenConfirmation(decode_content(content)?),
+            SsshMessages::ChannelOpenFailure => Message::ChannelOpenFailure(decode_content(content)?),
+            SsshMessages::ChannelWindowAdjust => Message::ChannelWindowAdjust(decode_content(content)?),
+            SsshMessages::ChannelClose => Message::ChannelClose(decode_content(content)?),
+            SsshMessages::ForwardRequest => Message::ForwardRequest(decode_content(content)?),
+            SsshMessages::ForwardSuccess => Message::ForwardSuccess(decode_content(content)?),
+            SsshMessages::ForwardFailure => Message::ForwardFailure(decode_content(content)?),
+            SsshMessages::Subsystem => Message::Subsystem(decode_content(content)?),
+            SsshMessages::SubsystemSuccess => Message::SubsystemSuccess(decode_content(content)?),
+            SsshMessages::SubsystemFailure => Message::SubsystemFailure(decode_content(content)?),
+            SsshMessages::AgentForward => Message::AgentForward(decode_content(content)?),
+            SsshMessages::AgentForwardSuccess => Message::AgentForwardSuccess(decode_content(content)?),
+            SsshMessages::AgentForwardFailure => Message::AgentForwardFailure(decode_content(content)?),
+        };
+
+        Ok(message)
+    }
+}
+
+fn encode_content<T: Serialize>(bytes: &mut Vec<u8>, content: &T) -> Result<()> {
+    bincode_options().serialize_into(bytes, content)?;
+    Ok(())
+}
+
+fn decode_content<T: DeserializeOwned>(content: &[u8]) -> Result<T> {
+    Ok(bincode_options().deserialize(content)?)
+}
+
+//Fixed size integers, limited to a packet, and without trailing bytes
+fn bincode_options() -> impl Options {
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .with_limit(protocol::PACKET_MAX_SIZE as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::algorithms::KexAlgorithm;
+    use crate::crypto::kex::KexKeys;
+
+    //One message of each type, with every field set
+    fn every_message() -> Vec<Message> {
+        let kex_keys = || KexKeys::for_rekey(KexAlgorithm::X25519).get_client_keys();
+
+        vec![
+            Message::PublicKey(PublicKeyRequest),
+            Message::PublicKeyReply(PublicKeyReply { public_key_pem: "public key".to_string() }),
+            Message::KeyExchange(KeyExchange { keys: kex_keys(), user: "user".to_string() }),
+            Message::KeyExchangeReply(KeyExchangeReply { public_key: vec![6; 32], signature: vec![8; 64] }),
+            Message::Auth(Auth { user: "user".to_string(), public_key_pem: "key".to_string(), algorithm: "ed25519".to_string(), signature: vec![7; 64] }),
+            Message::AuthSuccess(AuthSuccess),
+            Message::AuthFailure(AuthFailure),
+            Message::End(End),
+            Message::Shell(Shell { channel: 1, terminal: "xterm".to_string(), columns: 80, rows: 24 }),
+            Message::ShellSuccess(ShellSuccess { channel: 2 }),
+            Message::ShellFailure(ShellFailure { channel: 3 }),
+            Message::Data(Data { channel: 4, data: b"data".to_vec() }),
+            Message::WindowChange(WindowChange { channel: 5, columns: 120, rows: 40 }),
+            Message::Eof(Eof { channel: 6 }),
+            Message::Exec(Exec { channel: 7, command: "ls -l".to_string() }),
+            Message::ExecSuccess(ExecSuccess { channel: 8 }),
+            Message::ExecFailure(ExecFailure { channel: 9 }),
+            Message::ErrorData(ErrorData { channel: 10, data: b"error".to_vec() }),
+            Message::ExitStatus(ExitStatus { channel: 11, code: -1 }),
+            Message::ExitSignal(ExitSignal { channel: 12, signal: 15 }),
+            Message::Negotiation(Negotiation { lists: AlgorithmLists::default() }),
+            Message::Rekey(Rekey { keys: kex_keys() }),
+            Message::NewKeys(NewKeys),
+            Message::ChannelOpen(ChannelOpen {
+                sender_channel: 13,
+                kind: ChannelKind::DirectTcpip { host: "localhost".to_string(), port: 80, originator: "127.0.0.1:5000".to_string() },
+                window: 1024,
+                max_packet: 512,
+            }),
+            Message::ChannelOpenConfirmation(ChannelOpenConfirmation { channel: 14, sender_channel: 15, window: 2048, max_packet: 256 }),
+            Message::ChannelOpenFailure(ChannelOpenFailure { channel: 16, reason: "refused".to_string() }),
+            Message::ChannelWindowAdjust(ChannelWindowAdjust { channel: 17, bytes: 4096 }),
+            Message::ChannelClose(ChannelClose { channel: 18 }),
+            Message::ForwardRequest(ForwardRequest { bind: "0.0.0.0".to_string(), port: 8080 }),
+            Message::ForwardSuccess(ForwardSuccess),
+            Message::ForwardFailure(ForwardFailure { reason: "in use".to_string() }),
+            Message::Subsystem(Subsystem { channel: 19, name: "transfer".to_string() }),
+            Message::SubsystemSuccess(SubsystemSuccess { channel: 20 }),
+            Message::SubsystemFailure(SubsystemFailure { channel: 21 }),
+            Message::AgentForward(AgentForward { channel: 22 }),
+            Message::AgentForwardSuccess(AgentForwardSuccess { channel: 23 }),
+            Message::AgentForwardFailure(AgentForwardFailure { channel: 24 }),
+        ]
+    }
+
+    #[test]
+    fn every_message_type_round_trips() {
+        let messages = every_message();
+
+        //Each known type byte has his message on the list
+        let known_types: Vec<u8> = (0..=u8::MAX).filter(|byte| SsshMessages::try_from(*byte).is_ok()).collect();
+        let mut types: Vec<u8> = messages.iter().map(|message| message.get_type() as u8).collect();
+        types.sort_unstable();
+        assert_eq!(types, known_types);
+
+        for (index, message) in messages.iter().enumerate() {
+            let bytes = message.encode().unwrap();
+            assert_eq!(&bytes[..2], [protocol::MESSAGE_VERSION, message.get_type() as u8]);
+            assert_eq!(Message::message_type(&bytes).unwrap(), message.get_type());
+
+            //Encoded again the same, so every field was decoded
+            let decoded = Message::decode(&bytes).unwrap();
+            assert_eq!(decoded.get_type(), message.get_type(), "message {}", index);
+            assert_eq!(decoded.channel(), message.channel());
+            assert_eq!(decoded.encode().unwrap(), bytes);
+        }
+    }
+
+    #[test]
+    fn decoded_fields_are_kept() {
+        let open = Message::ChannelOpen(ChannelOpen { sender_channel: 3, kind: ChannelKind::AuthAgent, window: 10, max_packet: 20 });
+
+        let Message::ChannelOpen(decoded) = Message::decode(&open.encode().unwrap()).unwrap() else { panic!("Expected ChannelOpen") };
+        assert_eq!((decoded.sender_channel, decoded.kind, decoded.window, decoded.max_packet), (3, ChannelKind::AuthAgent, 10, 20));
+
+        let data = Message::Data(Data { channel: 9, data: vec![0, 255, 1] });
+
+        let Message::Data(decoded) = Message::decode(&data.encode().unwrap()).unwrap() else { panic!("Expected Data") };
+        assert_eq!((decoded.channel, decoded.data), (9, vec![0, 255, 1]));
+    }
+
+    #[test]
+    fn unknown_type_is_rejected() {
+        for message_type in [SsshMessages::AgentForwardFailure as u8 + 1, u8::MAX] {
+            let bytes = [protocol::MESSAGE_VERSION, message_type, 0, 0, 0, 0];
+
+            assert!(matches!(Message::decode(&bytes), Err(Error::UnknownMessage(byte)) if byte == message_type));
+            assert!(matches!(Message::message_type(&bytes), Err(Error::UnknownMessage(byte)) if byte == message_type));
+        }
+    }
+
+    #[test]
+    fn other_version_and_empty_are_rejected() {
+        let mut bytes = Message::End(End).encode().unwrap();
+        bytes[0] = protocol::MESSAGE_VERSION + 1;
+
+        assert!(matches!(Message::decode(&bytes), Err(Error::Static(UNSUPPORTED_VERSION_ERROR))));
+        assert!(matches!(Message::decode(&[protocol::MESSAGE_VERSION]), Err(Error::Static(EMPTY_MESSAGE_ERROR))));
+        assert!(matches!(Message::decode(&[]), Err(Error::Static(EMPTY_MESSAGE_ERROR))));
+    }
+
+    #[test]
+    fn trailing_bytes_are_rejected() {
+        for message in every_message() {
+            let mut bytes = message.encode().unwrap();
+            bytes.push(0);
+
+            assert!(matches!(Message::decode(&bytes), Err(Error::Codec(_))), "type {:?}", message.get_type());
+        }
+    }
+
+    #[test]
+    fn truncated_content_is_rejected() {
+        let bytes = Message::Exec(Exec { channel: 1, command: "command".to_string() }).encode().unwrap();
+
+        assert!(matches!(Message::decode(&bytes[..bytes.len() - 1]), Err(Error::Codec(_))));
+    }
+
+    #[test]
+    fn oversized_content_is_rejected() {
+        let data = Message::Data(Data { channel: 1, data: vec![0; protocol::PACKET_MAX_SIZE] });
+        assert!(matches!(data.encode(), Err(Error::Codec(_))));
+
+        //A length far over the packet, refused before the data is allocated
+        let mut bytes = vec![protocol::MESSAGE_VERSION, SsshMessages::Data as u8];
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&(1u64 << 40).to_le_bytes());
+
+        assert!(matches!(Message::decode(&bytes), Err(Error::Codec(_))));
+    }
+}
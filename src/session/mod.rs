@@ -1,19 +1,38 @@
-use std::fmt::format;
-use std::net::{SocketAddr, SocketAddrV4};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
 
 pub mod protocol;
-mod utils;
+pub mod message;
+pub mod utils;
+pub mod transport;
+pub mod config;
+pub mod banner;
+pub mod rekey;
+pub mod channel;
+pub mod forward;
+pub mod socks;
+mod auth;
+mod relay;
+mod shell;
+mod exec;
+mod subsystem;
+mod agent;
 mod connection;
 mod challenge;
 mod dhkeys;
 
-use num_bigint::BigUint;
-use rsa::sha2::{Digest, Sha256};
-
-use crate::crypto::session_keys::SessionKeys;
 use crate::error::Result;
-use crate::crypto::dhkeys::DHKeys;
-use crate::{crypto, file_sys};
+use crate::session::config::SessionConfig;
+use crate::session::channel::{Channel, Incoming, Mux};
+use crate::session::forward::Forward;
+use crate::session::socks::DynamicForward;
+use crate::session::message::ChannelKind;
+use crate::session::transport::SecureChannel;
+
+const UNEXPECTED_CHANNEL_ERROR: &str = "The client did not ask for a channel";
+const AGENT_NOT_FORWARDED_ERROR: &str = "The client does not forward his agent";
+const UNEXPECTED_FORWARD_ERROR: &str = "The client does not forward ports for the server";
+
 /*
  *#########################################################
  *File responsible for the SSSH Session, where
@@ -25,12 +44,25 @@ use crate::{crypto, file_sys};
  *
  * The hash is computed with:
  *
- * H = HASH(shared key || Server public key || user)
+ * H = HASH(shared key || Server public key || user || negotiation || client kex public || server kex public)
+ *
+ * Where the negotiation are both algorithms lists, so
+ * a changed list, to force a weak algorithm, is detected,
+ * and the server signs H with his host key, so a machine
+ * in the middle cannot make its own key exchange.
+ *
+ * Only once the client verifies this signature, we may
+ * derive all necessary keys, as in session_keys.rs, for
+ * the cipher and the MAC, or the AEAD, of each direction
+ *
+ * MAC = HMAC(mac key, sequence number || length || payload)
+ *
+ * Which are used by the SecureChannel, at transport.rs
+ *
+ * Then the user authenticates, signing H with his key.
  *
- * Now we may derive all necessary keys.
- * We may use a key for the HMAC and one for the message    
- * 
- * HMAC = MAC(key, sequence number || uncrypted packet)
+ * After it, the session carries channels, as in channel.rs,
+ * where each shell or command has his own channel.
  *
  *#########################################################
  *
@@ -40,52 +72,115 @@ pub struct Session{
     user : String,
     socket : SocketAddr,
     session_hash : Vec<u8>,
-    keys : SessionKeys,
-    sequence_number : usize,
+    mux : Mux,
+    remote_forwards : Arc<Mutex<Vec<Forward>>>,
+    forward_agent : bool,
 }
 
 impl Session {
 
     /* 
-     * The identifier is a string which has an user and the ip, in the following format 
-     * user#ip , also has the port, if it is not the default one
+     * The identifier is a string which has an user and the host, in the following format 
+     * user#host , where the host may be a name or an address, as in utils::identifier_to_user_host,
+     * the port and the other options are at the config
      */
-    pub fn connect(identifier: &str, _port: Option<u16>)-> Result<()>{
+    pub fn connect(identifier: &str, config: &SessionConfig)-> Result<Self>{
 
-        //If a different port is selected different than the default
-        let port = _port.unwrap_or(protocol::DEFAULT_PORT);
-        
-        let identifier_splitted = utils::identifier_to_user_ip(identifier)?;
+        let (user, host, port) = utils::identifier_to_user_host(identifier)?;
 
-        let _user = identifier_splitted.0;//Gets the user 
-        let _socket = SocketAddrV4::new(identifier_splitted.1, port); //Creates a socket 
+        //A port on the identifier wins over the config one
+        let port = port.unwrap_or(config.get_port());
 
-        let (stream,shared_key) = connection::start_connection(&_socket)?;
+        let addresses = utils::resolve_host(&host, port)?;
 
-        let session_hash: Vec<u8> = Session::compute_session_hash(&shared_key,&_user, &_socket)?;
+        crate::utils::debug(1, &format!("Connecting to {} port {} as {}", host, port, user));
 
-        let keys : SessionKeys = crypto::generate_session_keys(&session_hash, shared_key);
+        let connection = connection::start_connection(&host, &addresses, &user, config)?;
+        let socket = connection.address;
 
-        Ok(())
+        let session_hash: Vec<u8> = connection.session_hash;
+
+        //From now on every message is encrypted, with keys of the exchange the server host key signed
+        let mut channel = SecureChannel::client(connection.stream, &connection.keys, &connection.algorithms, &session_hash, config.rekey_limits)?;
+
+        auth::authenticate(&mut channel, &user, &session_hash, config.identity_file.as_deref(), &connection.signature_algorithms)?;
+
+        crate::utils::debug(1, &format!("Authenticated to {} as {}", socket, user));
+
+        let remote_forwards = Arc::new(Mutex::new(Vec::new()));
+        let handler_forwards = Arc::clone(&remote_forwards);
+        let forward_agent = config.forward_agent;
+
+        //The server only opens the connections of the ports the client asked to forward, and of his agent
+        let mux = Mux::start(channel, move |incoming| match incoming {
+            Incoming::Channel(incoming) => match incoming.kind.clone() {
+                ChannelKind::ForwardedTcpip { bind, port, .. } => forward::connect_forwarded(incoming, &handler_forwards, &bind, port),
+                ChannelKind::AuthAgent if forward_agent => agent::connect_agent(incoming),
+                ChannelKind::AuthAgent => {
+                    let _ = incoming.reject(AGENT_NOT_FORWARDED_ERROR);
+                },
+                _ => {
+                    let _ = incoming.reject(UNEXPECTED_CHANNEL_ERROR);
+                },
+            },
+            Incoming::Forward(request) => {
+                let _ = request.reject(UNEXPECTED_FORWARD_ERROR);
+            },
+        });
+
+        Ok(Self { user, socket, session_hash, mux, remote_forwards, forward_agent })
+    }
+
+    pub fn get_user(&self) -> &str{
+        &self.user
     }
 
-    fn compute_session_hash(shared_key : &BigUint,  user : &str, socket : &SocketAddrV4) -> Result<Vec<u8>>{
+    pub fn get_socket(&self) -> &SocketAddr{
+        &self.socket
+    }
 
-        let address = *socket.ip();
-        
-        let public_key_pem = match file_sys::get_known_host_key(&address){
-            Ok(p) => p.unwrap(),
-            Err(e) => return Err(e),
-        };
+    pub fn get_session_hash(&self) -> &[u8]{
+        &self.session_hash
+    }
 
+    //Opens a channel on the session, as a shell, a command or a forwarded port
+    pub fn open_channel(&self, kind: ChannelKind) -> Result<Channel>{
+        self.mux.open(kind)
+    }
 
-        let mut hasher = Sha256::new();
-        hasher.update(shared_key.to_bytes_be());
-        hasher.update(public_key_pem.as_bytes());
-        hasher.update(user.as_bytes());
+    //Listens on the local port of the forward, each connection forwarded by the server
+    pub fn forward_local(&self, forward: &Forward) -> Result<()>{
+        forward::listen_local(self.mux.opener(), forward)
+    }
 
-        Ok(hasher.finalize().to_vec())
+    //Runs a SOCKS proxy on the local port, each connection forwarded by the server
+    pub fn forward_dynamic(&self, proxy: &DynamicForward) -> Result<()>{
+        socks::listen_dynamic(self.mux.opener(), proxy)
     }
-}
 
+    //Asks the server to listen on the port of the forward, each connection forwarded to the client
+    pub fn forward_remote(&self, forward: &Forward) -> Result<()>{
+
+        //Known before the request, as the server may forward a connection before his answer arrives
+        self.remote_forwards.lock().unwrap().push(forward.clone());
+
+        if let Err(e) = self.mux.request_forward(forward.bind_address(), forward.port) {
+            self.remote_forwards.lock().unwrap().pop();
+            return Err(e);
+        }
 
+        crate::utils::debug(1, &format!("Remote forwarding {}:{} to {}:{}", forward.bind_address(), forward.port, forward.host, forward.host_port));
+
+        Ok(())
+    }
+
+    //Waits until the server ends the session or leaves, as when only forwarding
+    pub fn wait(self) -> Result<()>{
+        self.mux.wait()
+    }
+
+    //Tells the server the session is over
+    pub fn close(self) -> Result<()>{
+        self.mux.end()
+    }
+}
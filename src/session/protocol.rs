@@ -5,35 +5,155 @@
  *machines during the connection
  *
  * BANNER - Is used when the connection is
- * made to confirm the protocol
+ * made to confirm the protocol and his version
  *
  * SsshMessages - The trype of connections made.
  *
  *  PublicKey - Asks for the servers public key
- *  Challenge - Verifies if the server is owner of the private key
- *  KeyExchange - Starts the Diffie-Hellman.
- *  Auth - Sends the user and id_rsa.
+ *  KeyExchange - Starts the Diffie-Hellman, with the user.
+ *  Auth - Sends the user, his id_rsa.pub and the session hash signed.
  *  AuthSuccess - If the Auth was successful
  *  AuthFailure - If the Auth was a AuthFailure
  *  End - To end the connection between points
+ *  PublicKeyReply - The server public key
+ *  KeyExchangeReply - The server Diffie-Hellman public key, with his signature of the session hash
+ *  Shell - Asks for the user shell, on a terminal
+ *  ShellSuccess - If the shell was started
+ *  ShellFailure - If the shell could not be started
+ *  Data - Bytes of the terminal, on both directions
+ *  WindowChange - The client terminal was resized
+ *  Eof - The client has no more input
+ *  Exec - Asks to run a command, without a terminal
+ *  ExecSuccess - If the command was started
+ *  ExecFailure - If the command could not be started
+ *  ErrorData - Bytes written by the command on stderr
+ *  ExitStatus - The exit code of the shell or command
+ *  ExitSignal - The signal which killed the shell or command
+ *  Negotiation - The supported algorithms lists, sent by both machines after the banner
+ *  Rekey - Starts or answers a new key exchange, by either machine, on the transport
+ *  NewKeys - The packets after it use the new keys
+ *  ChannelOpen - Opens a channel of a kind, with the sender channel id and his receive window
+ *  ChannelOpenConfirmation - The channel was opened, with the id and window of the other side
+ *  ChannelOpenFailure - The channel could not be opened, with the reason
+ *  ChannelWindowAdjust - The receiver consumed bytes, so more data may be sent
+ *  ChannelClose - The sender will not use the channel anymore
+ *  ForwardRequest - Asks the server to listen on a port, forwarding his connections to the client
+ *  ForwardSuccess - The server listens on the port
+ *  ForwardFailure - The server does not listen on the port, with the reason
+ *  Subsystem - Asks the server to start a subsystem, as transfer, on a session channel
+ *  SubsystemSuccess - The subsystem started
+ *  SubsystemFailure - The subsystem is unknown or could not start
+ *  AgentForward - Asks the server to forward the agent to the shell or command of the channel
+ *  AgentForwardSuccess - The server forwards the agent
+ *  AgentForwardFailure - The server does not forward the agent
+ *
+ * Each message content is defined at message.rs
+ *
+ * After the KeyExchange every message is sent through
+ * the encrypted transport, up to PACKET_MAX_SIZE bytes.
  *
  *###################################################################
  */
 
-//Protocol banner
-pub const PROTOCOL_BANNER : &str = "sssh_0.1";
+use crate::error::{Error, Result};
+
+//Protocol banner, as in banner.rs
+pub const PROTOCOL_NAME : &str = "sssh";
+pub const PROTOCOL_MAJOR_VERSION : u16 = 2;
+pub const PROTOCOL_MINOR_VERSION : u16 = 0;
+pub const SOFTWARE_VERSION : &str = concat!("sssh", env!("CARGO_PKG_VERSION"));
 pub const DEFAULT_PORT : u16 = 69;
 pub const BUFFER_MAX_SIZE : usize = 2048;
+pub const PACKET_MAX_SIZE : usize = 35000;
+pub const MESSAGE_VERSION : u8 = 1;
 //The sssh types of connection
 #[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SsshMessages {
     PublicKey = 0,
-    Challenge = 1,
     KeyExchange = 2,
     Auth = 3,
     AuthSuccess = 4,
     AuthFailure = 5,
     End = 6,
+    PublicKeyReply = 7,
+    KeyExchangeReply = 9,
+    Shell = 10,
+    ShellSuccess = 11,
+    ShellFailure = 12,
+    Data = 13,
+    WindowChange = 14,
+    Eof = 15,
+    Exec = 16,
+    ExecSuccess = 17,
+    ExecFailure = 18,
+    ErrorData = 19,
+    ExitStatus = 20,
+    ExitSignal = 21,
+    Negotiation = 22,
+    Rekey = 23,
+    NewKeys = 24,
+    ChannelOpen = 25,
+    ChannelOpenConfirmation = 26,
+    ChannelOpenFailure = 27,
+    ChannelWindowAdjust = 28,
+    ChannelClose = 29,
+    ForwardRequest = 30,
+    ForwardSuccess = 31,
+    ForwardFailure = 32,
+    Subsystem = 33,
+    SubsystemSuccess = 34,
+    SubsystemFailure = 35,
+    AgentForward = 36,
+    AgentForwardSuccess = 37,
+    AgentForwardFailure = 38,
+}
+
+impl TryFrom<u8> for SsshMessages {
+    type Error = Error;
+
+    fn try_from(byte: u8) -> Result<Self> {
+        match byte {
+            0 => Ok(SsshMessages::PublicKey),
+            2 => Ok(SsshMessages::KeyExchange),
+            3 => Ok(SsshMessages::Auth),
+            4 => Ok(SsshMessages::AuthSuccess),
+            5 => Ok(SsshMessages::AuthFailure),
+            6 => Ok(SsshMessages::End),
+            7 => Ok(SsshMessages::PublicKeyReply),
+            9 => Ok(SsshMessages::KeyExchangeReply),
+            10 => Ok(SsshMessages::Shell),
+            11 => Ok(SsshMessages::ShellSuccess),
+            12 => Ok(SsshMessages::ShellFailure),
+            13 => Ok(SsshMessages::Data),
+            14 => Ok(SsshMessages::WindowChange),
+            15 => Ok(SsshMessages::Eof),
+            16 => Ok(SsshMessages::Exec),
+            17 => Ok(SsshMessages::ExecSuccess),
+            18 => Ok(SsshMessages::ExecFailure),
+            19 => Ok(SsshMessages::ErrorData),
+            20 => Ok(SsshMessages::ExitStatus),
+            21 => Ok(SsshMessages::ExitSignal),
+            22 => Ok(SsshMessages::Negotiation),
+            23 => Ok(SsshMessages::Rekey),
+            24 => Ok(SsshMessages::NewKeys),
+            25 => Ok(SsshMessages::ChannelOpen),
+            26 => Ok(SsshMessages::ChannelOpenConfirmation),
+            27 => Ok(SsshMessages::ChannelOpenFailure),
+            28 => Ok(SsshMessages::ChannelWindowAdjust),
+            29 => Ok(SsshMessages::ChannelClose),
+            30 => Ok(SsshMessages::ForwardRequest),
+            31 => Ok(SsshMessages::ForwardSuccess),
+            32 => Ok(SsshMessages::ForwardFailure),
+            33 => Ok(SsshMessages::Subsystem),
+            34 => Ok(SsshMessages::SubsystemSuccess),
+            35 => Ok(SsshMessages::SubsystemFailure),
+            36 => Ok(SsshMessages::AgentForward),
+            37 => Ok(SsshMessages::AgentForwardSuccess),
+            38 => Ok(SsshMessages::AgentForwardFailure),
+            _ => Err(Error::UnknownMessage(byte)),
+        }
+    }
 }
 
 
@@ -41,6 +161,8 @@ pub const CLIENT_SERVER_ENCRYPTION_BYTE : u8= b'A';
 pub const SERVER_CLIENT_ENCRYPTION_BYTE : u8 = b'B';
 pub const CLIENT_SERVER_INTEGRITY_BYTE : u8 = b'C';
 pub const SERVER_CLIENT_INTEGIRTY_BYTE : u8 = b'D';
+pub const CLIENT_SERVER_IV_BYTE : u8 = b'E';
+pub const SERVER_CLIENT_IV_BYTE : u8 = b'F';
 
 
 
@@ -0,0 +1,140 @@
+/*
+ * ##############################################
+ * File responsible for when the transport keys
+ * are renewed, by the limits of each direction:
+ *
+ * bytes - The bytes sent or received, as RekeyBytes 1G
+ * packets - The packets sent or received, as RekeyPackets 2147483648
+ * interval - The minutes since the last keys, as RekeyMinutes 60
+ *
+ * Once a limit is reached, on a packet sent or
+ * received, a new key exchange is started, as in
+ * transport.rs, and a zero limit is disabled.
+ *
+ * Whatever the limits, the keys are always renewed
+ * after 2^31 packets, so the 32 bits sequence number
+ * is never exhausted while the rekey finishes.
+ * ##############################################
+ */
+
+use std::time::{Duration, Instant};
+
+use crate::error::{Error, Result};
+
+const DEFAULT_BYTES: u64 = 1 << 30;
+const DEFAULT_PACKETS: u64 = 1 << 31;
+const DEFAULT_MINUTES: u64 = 60;
+
+//Half of the sequence numbers, the other half is left for the packets sent until the new keys
+const MAX_PACKETS: u64 = 1 << 31;
+
+const SECONDS_PER_MINUTE: u64 = 60;
+
+const INVALID_LIMIT_ERROR: &str = "The rekey limit must be a number, the bytes may end with K, M or G";
+
+#[derive(Clone, Copy, Debug)]
+pub struct RekeyLimits {
+    pub bytes: u64,
+    pub packets: u64,
+    pub interval: Duration,
+}
+
+//What one direction used of the current keys
+pub struct KeysUsage {
+    bytes: u64,
+    packets: u64,
+    since: Instant,
+}
+
+impl Default for RekeyLimits {
+    fn default() -> Self {
+        Self {
+            bytes: DEFAULT_BYTES,
+            packets: DEFAULT_PACKETS,
+            interval: Duration::from_secs(DEFAULT_MINUTES * SECONDS_PER_MINUTE),
+        }
+    }
+}
+
+impl RekeyLimits {
+
+    /*
+     * Sets a limit by his option name, case insensitive, as
+     * RekeyBytes, RekeyPackets or RekeyMinutes, returning
+     * false if the option is not a rekey limit
+     */
+    pub fn set_option(&mut self, option: &str, value: &str) -> Result<bool> {
+
+        match option.to_lowercase().as_str() {
+            "rekeybytes" => self.bytes = parse_bytes(value)?,
+            "rekeypackets" => self.packets = parse_number(value)?,
+            "rekeyminutes" => self.interval = Duration::from_secs(parse_number(value)?.saturating_mul(SECONDS_PER_MINUTE)),
+            _ => return Ok(false),
+        }
+
+        Ok(true)
+    }
+}
+
+impl KeysUsage {
+
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self { bytes: 0, packets: 0, since: Instant::now() }
+    }
+
+    pub fn add_packet(&mut self, size: usize) {
+        self.bytes = self.bytes.saturating_add(size as u64);
+        self.packets = self.packets.saturating_add(1);
+    }
+
+    pub fn exceeds(&self, limits: &RekeyLimits) -> bool {
+        self.packets >= MAX_PACKETS
+            || (limits.bytes != 0 && self.bytes >= limits.bytes)
+            || (limits.packets != 0 && self.packets >= limits.packets)
+            || (!limits.interval.is_zero() && self.since.elapsed() >= limits.interval)
+    }
+}
+
+fn parse_number(value: &str) -> Result<u64> {
+    value.trim().parse::<u64>().map_err(|_| Error::Static(INVALID_LIMIT_ERROR))
+}
+
+//A number of bytes, which may end with K, M or G, as 512M
+fn parse_bytes(value: &str) -> Result<u64> {
+
+    let value = value.trim();
+
+    let (number, shift) = match value.chars().last().map(|c| c.to_ascii_uppercase()) {
+        Some('K') => (&value[..value.len() - 1], 10),
+        Some('M') => (&value[..value.len() - 1], 20),
+        Some('G') => (&value[..value.len() - 1], 30),
+        _ => (value, 0),
+    };
+
+    parse_number(number)?.checked_mul(1 << shift).ok_or(Error::Static(INVALID_LIMIT_ERROR))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage_of(packets: u64) -> KeysUsage {
+        KeysUsage { bytes: 0, packets, since: Instant::now() }
+    }
+
+    #[test]
+    fn disabled_packets_limit_still_rekeys_before_the_sequence_number_ends() {
+        let limits = RekeyLimits { bytes: 0, packets: 0, interval: Duration::ZERO };
+
+        assert!(!usage_of(MAX_PACKETS - 1).exceeds(&limits));
+        assert!(usage_of(MAX_PACKETS).exceeds(&limits));
+    }
+
+    #[test]
+    fn packets_limit_above_the_sequence_numbers_still_rekeys() {
+        let limits = RekeyLimits { packets: 1 << 40, ..RekeyLimits::default() };
+
+        assert!(usage_of(MAX_PACKETS).exceeds(&limits));
+    }
+}
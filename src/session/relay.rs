@@ -0,0 +1,81 @@
+/*
+ * ##############################################
+ * File responsible for relaying the local
+ * stdio, on the client side, used by the shell
+ * and the command execution, on their channel.
+ *
+ * stdin -> Data, until Eof
+ * Data -> stdout, ErrorData -> stderr
+ *
+ * Until the channel closes, returning the remote exit code.
+ * ##############################################
+ */
+
+use std::io::{self, Read, Write};
+use std::thread;
+
+use crate::error::{Error, Result};
+use crate::session::channel::{Channel, ChannelWriter};
+use crate::session::message::{self, Message};
+use crate::session::protocol;
+
+//The exit code when the server ends without an exit status, as the connection failed
+const NO_EXIT_STATUS_CODE: i32 = 255;
+//A process killed by a signal exits with this plus the signal, as on shells
+const SIGNAL_EXIT_CODE: i32 = 128;
+
+//Sends everything read from stdin, and Eof when the stdin closes
+pub fn relay_stdin(writer: ChannelWriter) {
+
+    thread::spawn(move || {
+
+        let mut stdin = io::stdin();
+        let mut buffer = [0u8; protocol::BUFFER_MAX_SIZE];
+
+        loop {
+
+            let size = match stdin.read(&mut buffer) {
+                Ok(0) | Err(_) => {
+                    let _ = writer.send_eof();
+                    return;
+                },
+                Ok(size) => size,
+            };
+
+            //The channel closed, so nothing else can be sent
+            if writer.send_data(&buffer[..size]).is_err() {
+                return;
+            }
+        }
+    });
+}
+
+//Writes the received output, until the channel closes, returning the exit code
+pub fn relay_output(channel: &mut Channel) -> Result<i32> {
+
+    let mut stdout = io::stdout();
+    let mut stderr = io::stderr();
+
+    let mut exit_code = NO_EXIT_STATUS_CODE;
+
+    while let Some(message) = channel.receive() {
+        match message {
+            Message::Data(data) => {
+                stdout.write_all(&data.data)?;
+                stdout.flush()?;
+            },
+            Message::ErrorData(data) => {
+                stderr.write_all(&data.data)?;
+                stderr.flush()?;
+            },
+            Message::ExitStatus(status) => exit_code = status.code,
+            Message::ExitSignal(exit) => {
+                eprintln!("Remote process killed by signal {}", exit.signal);
+                exit_code = SIGNAL_EXIT_CODE + exit.signal;
+            },
+            _ => return Err(Error::Static(message::UNEXPECTED_MESSAGE_ERROR)),
+        }
+    }
+
+    Ok(exit_code)
+}
@@ -0,0 +1,79 @@
+/*
+ * ##############################################
+ * File responsible for the interactive shell,
+ * on the client side.
+ *
+ * After the server starts the shell, the local
+ * terminal is put on raw mode and relayed, as
+ * in relay.rs, with the terminal resizes:
+ *
+ * SIGWINCH -> WindowChange
+ * ##############################################
+ */
+
+use std::env;
+use std::thread;
+
+use signal_hook::consts::SIGWINCH;
+use signal_hook::iterator::Signals;
+
+use crate::error::{Error, Result};
+use crate::session::channel::ChannelWriter;
+use crate::session::message::{self, ChannelKind, Message, Shell, WindowChange};
+use crate::session::relay;
+use crate::session::Session;
+use crate::terminal::{self, RawMode};
+
+const DEFAULT_TERMINAL: &str = "xterm";
+const SHELL_FAILURE_ERROR: &str = "The server could not start the shell";
+
+impl Session {
+
+    //Starts the user shell on the server, on a new channel, and relays the terminal until it ends, returning his exit code
+    pub fn shell(&self) -> Result<i32> {
+
+        let mut channel = self.open_channel(ChannelKind::Session)?;
+
+        self.request_agent(&mut channel)?;
+
+        let terminal = env::var("TERM").unwrap_or(DEFAULT_TERMINAL.to_string());
+        let (columns, rows) = terminal::window_size();
+
+        channel.send_message(&Message::Shell(Shell { channel: channel.remote_id(), terminal, columns, rows }))?;
+
+        match channel.receive() {
+            Some(Message::ShellSuccess(_)) => {},
+            Some(Message::ShellFailure(_)) => return Err(Error::Static(SHELL_FAILURE_ERROR)),
+            _ => return Err(Error::Static(message::UNEXPECTED_MESSAGE_ERROR)),
+        }
+
+        //Restored when the shell ends, even with an error
+        let _raw_mode = RawMode::enable()?;
+
+        relay::relay_stdin(channel.writer());
+        relay_window_changes(channel.writer())?;
+
+        relay::relay_output(&mut channel)
+    }
+}
+
+//Sends the new size each time the terminal is resized
+fn relay_window_changes(writer: ChannelWriter) -> Result<()> {
+
+    let mut signals = Signals::new([SIGWINCH])?;
+
+    thread::spawn(move || {
+
+        for _ in signals.forever() {
+
+            let (columns, rows) = terminal::window_size();
+            let message = Message::WindowChange(WindowChange { channel: writer.remote_id(), columns, rows });
+
+            if writer.send_message(&message).is_err() {
+                return;
+            }
+        }
+    });
+
+    Ok(())
+}
@@ -0,0 +1,259 @@
+/*
+ * ##############################################
+ * File responsible for the dynamic forwarding,
+ * -D [bind:]port, where the client is a SOCKS
+ * proxy and each CONNECT is a DirectTcpip channel,
+ * so the server resolves and connects the host.
+ *
+ * SOCKS5 (RFC 1928), without authentication:
+ *
+ * 5 | methods count | methods -> 5 | 0
+ * 5 | CONNECT | 0 | address type | address | port -> 5 | reply | 0 | 1 | 0.0.0.0 | 0
+ *
+ * The address is an IPv4, a domain name or an IPv6.
+ *
+ * SOCKS4 and SOCKS4a, where an IPv4 0.0.0.x means
+ * the domain name comes after the user:
+ *
+ * 4 | CONNECT | port | IPv4 | user | 0 [| domain | 0] -> 0 | reply | port | IPv4
+ *
+ * The proxy replies once the server opened the channel,
+ * then the connection is relayed as in forward.rs.
+ * ##############################################
+ */
+
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, Ipv6Addr, TcpListener, TcpStream};
+use std::thread;
+
+use crate::error::{Error, Result};
+use crate::session::channel::ChannelOpener;
+use crate::session::forward;
+use crate::session::message::ChannelKind;
+use crate::utils;
+
+const DEFAULT_BIND_ADDRESS: &str = "localhost";
+
+const SOCKS4_VERSION: u8 = 4;
+const SOCKS5_VERSION: u8 = 5;
+const CONNECT_COMMAND: u8 = 1;
+
+const NO_AUTHENTICATION: u8 = 0;
+const NO_ACCEPTABLE_METHODS: u8 = 0xFF;
+
+const ADDRESS_IPV4: u8 = 1;
+const ADDRESS_DOMAIN: u8 = 3;
+const ADDRESS_IPV6: u8 = 4;
+
+const SOCKS5_SUCCEEDED: u8 = 0;
+const SOCKS5_FAILURE: u8 = 1;
+const SOCKS5_COMMAND_NOT_SUPPORTED: u8 = 7;
+const SOCKS5_ADDRESS_NOT_SUPPORTED: u8 = 8;
+
+const SOCKS4_REPLY_VERSION: u8 = 0;
+const SOCKS4_GRANTED: u8 = 0x5A;
+const SOCKS4_REJECTED: u8 = 0x5B;
+
+//The longest user or domain of SOCKS4, as they end with a zero
+const SOCKS4_MAX_STRING: usize = 255;
+
+const SOCKS_VERSION_ERROR: &str = "The proxy client uses an unknown SOCKS version";
+const SOCKS_METHOD_ERROR: &str = "The proxy client needs an authentication";
+const SOCKS_COMMAND_ERROR: &str = "The proxy client asked for a command other than CONNECT";
+const SOCKS_ADDRESS_ERROR: &str = "The proxy client sent an invalid address";
+
+//A local SOCKS proxy, as [bind:]port
+#[derive(Clone, Debug)]
+pub struct DynamicForward {
+    pub bind: Option<String>,
+    pub port: u16,
+}
+
+impl DynamicForward {
+
+    //Where the proxy listens, localhost if no bind address was given
+    pub fn bind_address(&self) -> &str {
+        self.bind.as_deref().unwrap_or(DEFAULT_BIND_ADDRESS)
+    }
+}
+
+//What a SOCKS client asked, and how to answer it
+struct ConnectRequest {
+    version: u8,
+    host: String,
+    port: u16,
+}
+
+//Listens on the proxy port, answering each SOCKS client on his own thread
+pub fn listen_dynamic(opener: ChannelOpener, proxy: &DynamicForward) -> Result<()> {
+
+    let listener = TcpListener::bind((proxy.bind_address(), proxy.port))?;
+
+    utils::debug(1, &format!("SOCKS proxy on {}:{}", proxy.bind_address(), proxy.port));
+
+    thread::spawn(move || {
+
+        let on_error = |e: &std::io::Error| utils::debug(1, &format!("Failed to accept a SOCKS client, {}", e));
+
+        utils::accept_connections(listener.incoming(), || false, on_error, |stream| {
+
+            let opener = opener.clone();
+
+            thread::spawn(move || {
+                if let Err(e) = handle_client(&opener, stream) {
+                    utils::debug(1, &format!("SOCKS client failed, {}", e));
+                }
+            });
+        });
+    });
+
+    Ok(())
+}
+
+//Reads the request, opens his channel and replies, then relays the connection
+fn handle_client(opener: &ChannelOpener, mut stream: TcpStream) -> Result<()> {
+
+    let request = match read_u8(&mut stream)? {
+        SOCKS5_VERSION => read_socks5_request(&mut stream)?,
+        SOCKS4_VERSION => read_socks4_request(&mut stream)?,
+        _ => return Err(Error::Static(SOCKS_VERSION_ERROR)),
+    };
+
+    let originator = stream.peer_addr().map(|address| address.to_string()).unwrap_or_default();
+    let kind = ChannelKind::DirectTcpip { host: request.host.clone(), port: request.port, originator };
+
+    match opener.open(kind) {
+        Ok(channel) => {
+            send_reply(&mut stream, request.version, true)?;
+            forward::relay(channel, stream);
+            Ok(())
+        },
+        Err(e) => {
+            send_reply(&mut stream, request.version, false)?;
+            Err(e)
+        },
+    }
+}
+
+//Chooses no authentication, then reads the CONNECT request, the version was already read
+fn read_socks5_request(stream: &mut TcpStream) -> Result<ConnectRequest> {
+
+    let methods_count = read_u8(stream)?;
+    let mut methods = vec![0u8; methods_count as usize];
+    stream.read_exact(&mut methods)?;
+
+    if !methods.contains(&NO_AUTHENTICATION) {
+        stream.write_all(&[SOCKS5_VERSION, NO_ACCEPTABLE_METHODS])?;
+        return Err(Error::Static(SOCKS_METHOD_ERROR));
+    }
+
+    stream.write_all(&[SOCKS5_VERSION, NO_AUTHENTICATION])?;
+
+    let mut header = [0u8; 4];
+    stream.read_exact(&mut header)?;
+
+    let [version, command, _, address_type] = header;
+
+    if version != SOCKS5_VERSION {
+        return Err(Error::Static(SOCKS_VERSION_ERROR));
+    }
+
+    if command != CONNECT_COMMAND {
+        send_socks5_reply(stream, SOCKS5_COMMAND_NOT_SUPPORTED)?;
+        return Err(Error::Static(SOCKS_COMMAND_ERROR));
+    }
+
+    let host = match address_type {
+        ADDRESS_IPV4 => {
+            let mut address = [0u8; 4];
+            stream.read_exact(&mut address)?;
+            Ipv4Addr::from(address).to_string()
+        },
+        ADDRESS_IPV6 => {
+            let mut address = [0u8; 16];
+            stream.read_exact(&mut address)?;
+            Ipv6Addr::from(address).to_string()
+        },
+        ADDRESS_DOMAIN => {
+            let mut domain = vec![0u8; read_u8(stream)? as usize];
+            stream.read_exact(&mut domain)?;
+            String::from_utf8(domain).map_err(|_| Error::Static(SOCKS_ADDRESS_ERROR))?
+        },
+        _ => {
+            send_socks5_reply(stream, SOCKS5_ADDRESS_NOT_SUPPORTED)?;
+            return Err(Error::Static(SOCKS_ADDRESS_ERROR));
+        },
+    };
+
+    Ok(ConnectRequest { version: SOCKS5_VERSION, host, port: read_port(stream)? })
+}
+
+//Reads the CONNECT request, where a 0.0.0.x address means a SOCKS4a domain, the version was already read
+fn read_socks4_request(stream: &mut TcpStream) -> Result<ConnectRequest> {
+
+    if read_u8(stream)? != CONNECT_COMMAND {
+        send_socks4_reply(stream, SOCKS4_REJECTED)?;
+        return Err(Error::Static(SOCKS_COMMAND_ERROR));
+    }
+
+    let port = read_port(stream)?;
+
+    let mut address = [0u8; 4];
+    stream.read_exact(&mut address)?;
+
+    //The user is not used, the server authenticated us already
+    read_zero_terminated(stream)?;
+
+    let host = match address {
+        [0, 0, 0, last] if last != 0 => String::from_utf8(read_zero_terminated(stream)?).map_err(|_| Error::Static(SOCKS_ADDRESS_ERROR))?,
+        _ => Ipv4Addr::from(address).to_string(),
+    };
+
+    Ok(ConnectRequest { version: SOCKS4_VERSION, host, port })
+}
+
+fn send_reply(stream: &mut TcpStream, version: u8, succeeded: bool) -> Result<()> {
+    match (version, succeeded) {
+        (SOCKS5_VERSION, true) => send_socks5_reply(stream, SOCKS5_SUCCEEDED),
+        (SOCKS5_VERSION, false) => send_socks5_reply(stream, SOCKS5_FAILURE),
+        (_, true) => send_socks4_reply(stream, SOCKS4_GRANTED),
+        (_, false) => send_socks4_reply(stream, SOCKS4_REJECTED),
+    }
+}
+
+//The bound address is not known by the client, so it is always 0.0.0.0:0
+fn send_socks5_reply(stream: &mut TcpStream, reply: u8) -> Result<()> {
+    stream.write_all(&[SOCKS5_VERSION, reply, 0, ADDRESS_IPV4, 0, 0, 0, 0, 0, 0])?;
+    Ok(())
+}
+
+fn send_socks4_reply(stream: &mut TcpStream, reply: u8) -> Result<()> {
+    stream.write_all(&[SOCKS4_REPLY_VERSION, reply, 0, 0, 0, 0, 0, 0])?;
+    Ok(())
+}
+
+fn read_u8(stream: &mut TcpStream) -> Result<u8> {
+    let mut byte = [0u8; 1];
+    stream.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+fn read_port(stream: &mut TcpStream) -> Result<u16> {
+    let mut port = [0u8; 2];
+    stream.read_exact(&mut port)?;
+    Ok(u16::from_be_bytes(port))
+}
+
+//Reads byte by byte, so nothing after the zero is consumed
+fn read_zero_terminated(stream: &mut TcpStream) -> Result<Vec<u8>> {
+
+    let mut bytes = Vec::new();
+
+    loop {
+        match read_u8(stream)? {
+            0 => return Ok(bytes),
+            _ if bytes.len() >= SOCKS4_MAX_STRING => return Err(Error::Static(SOCKS_ADDRESS_ERROR)),
+            byte => bytes.push(byte),
+        }
+    }
+}
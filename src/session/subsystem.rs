@@ -0,0 +1,31 @@
+/*
+ * ##############################################
+ * File responsible for starting a subsystem, on
+ * the client side, as the file transfer one,
+ * whose program talks on his own channel.
+ * ##############################################
+ */
+
+use crate::error::{Error, Result};
+use crate::session::channel::Channel;
+use crate::session::message::{self, ChannelKind, Message, Subsystem};
+use crate::session::Session;
+
+const SUBSYSTEM_FAILURE_ERROR: &str = "The server could not start the subsystem";
+
+impl Session {
+
+    //Starts the subsystem on a new channel, returning it once the server started it
+    pub fn subsystem(&self, name: &str) -> Result<Channel> {
+
+        let mut channel = self.open_channel(ChannelKind::Session)?;
+
+        channel.send_message(&Message::Subsystem(Subsystem { channel: channel.remote_id(), name: name.to_string() }))?;
+
+        match channel.receive() {
+            Some(Message::SubsystemSuccess(_)) => Ok(channel),
+            Some(Message::SubsystemFailure(_)) => Err(Error::Static(SUBSYSTEM_FAILURE_ERROR)),
+            _ => Err(Error::Static(message::UNEXPECTED_MESSAGE_ERROR)),
+        }
+    }
+}
@@ -0,0 +1,819 @@
+/*
+ * ###################################################
+ * File responsible for the secure transport, every
+ * packet sent after the key exchange goes through
+ * the SecureChannel, which the client only creates
+ * after verifying the server host key signature of
+ * the session hash, so its keys are the server ones.
+ *
+ * With a negotiated cipher and MAC, as AES-256 in CTR
+ * mode and HMAC-SHA256, each packet is written as:
+ *
+ * Encrypt(length || payload) || MAC
+ *
+ * MAC = HMAC(mac key, sequence number || length || payload)
+ *
+ * Where the counter starts at the direction IV and
+ * continues between packets.
+ *
+ * With an AEAD cipher, as ChaCha20-Poly1305 or
+ * AES-256-GCM, the MAC is not used and each packet is:
+ *
+ * length || AEAD(payload, associated data = length) || tag
+ *
+ * nonce = IV XOR (0 (8 bytes) || sequence number (4 bytes))
+ *
+ * So the length is sent in plain text but authenticated,
+ * and the nonce never repeats with the same key.
+ *
+ * Each direction has his own keys and sequence number,
+ * which is never sent, so a replayed, reordered or
+ * dropped packet will fail the MAC or the tag.
+ *
+ * Once a direction reaches a limit, as in rekey.rs,
+ * either machine sends Rekey with new kex keys, and
+ * the other answers with his own, without stopping
+ * the other packets. Both compute the new keys as
+ * the SessionKeys of the new K, with the original
+ * session hash, and each one sends NewKeys, where
+ * his direction starts using them, with the sequence
+ * number back to 0.
+ * ###################################################
+ */
+
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::sync::{Arc, Mutex};
+
+use aes::cipher::{KeyIvInit, StreamCipher};
+use aes_gcm::Aes256Gcm;
+use chacha20poly1305::aead::{Aead, KeyInit, Nonce, Payload};
+use chacha20poly1305::ChaCha20Poly1305;
+use hmac::{Hmac, Mac};
+use rsa::sha2::Sha256;
+
+use crate::crypto;
+use crate::crypto::algorithms::{Algorithms, CipherAlgorithm, MacAlgorithm};
+use crate::crypto::kex::KexKeys;
+use crate::crypto::session_keys::SessionKeys;
+use crate::error::{Error, Result};
+use crate::session::message::{self, Message, NewKeys, Rekey};
+use crate::session::protocol::{self, SsshMessages};
+use crate::session::rekey::{KeysUsage, RekeyLimits};
+
+type Aes256Ctr = ctr::Ctr128BE<aes::Aes256>;
+type HmacSha256 = Hmac<Sha256>;
+
+const MAC_SIZE: usize = 32;
+const TAG_SIZE: usize = 16;
+const LENGTH_SIZE: usize = 4;
+
+const INVALID_KEY_ERROR: &str = "The session keys have an invalid size";
+const INVALID_MAC_ERROR: &str = "Received a packet with an invalid MAC, it was tampered or replayed";
+const INVALID_TAG_ERROR: &str = "Received a packet with an invalid tag, it was tampered or replayed";
+const ENCRYPTION_ERROR: &str = "Could not encrypt the packet";
+const PACKET_TOO_LONG_ERROR: &str = "Received a packet bigger than the maximum size";
+const SEQUENCE_NUMBER_EXHAUSTED_ERROR: &str = "The sequence number is exhausted, the session must end";
+const REKEY_ALGORITHM_ERROR: &str = "Received a rekey with a different key exchange algorithm";
+const REKEY_IN_PROGRESS_ERROR: &str = "Received a rekey before the new keys of the last one";
+const UNEXPECTED_NEW_KEYS_ERROR: &str = "Received new keys without a rekey";
+
+//The encryption key, IV and MAC key of one direction
+type DirectionKeys<'a> = (&'a [u8], &'a [u8], &'a [u8]);
+
+//The keys of one direction, by the negotiated cipher
+enum PacketCipher {
+    Aes256Ctr { cipher: Aes256Ctr, mac_key: Vec<u8> },
+    ChaCha20Poly1305 { cipher: ChaCha20Poly1305, iv: Vec<u8> },
+    Aes256Gcm { cipher: Aes256Gcm, iv: Vec<u8> },
+}
+
+//What a direction needs to compute his next keys
+struct Rekeying {
+    session_hash: Vec<u8>,
+    algorithms: Algorithms,
+    is_client: bool,
+    limits: RekeyLimits,
+}
+
+//The sending direction, shared with the receiver, which also sends on a rekey
+struct SendState {
+    stream: TcpStream,
+    cipher: PacketCipher,
+    sequence_number: u32,
+    usage: KeysUsage,
+    //Our keys of a rekey started, until the other machine answers
+    pending: Option<KexKeys>,
+}
+
+//Writes packets in one direction
+pub struct PacketSender {
+    state: Arc<Mutex<SendState>>,
+    rekeying: Arc<Rekeying>,
+}
+
+//Reads packets in the other direction
+pub struct PacketReceiver {
+    stream: TcpStream,
+    cipher: PacketCipher,
+    sequence_number: u32,
+    usage: KeysUsage,
+    //The keys computed on a rekey, used once NewKeys is received
+    next_cipher: Option<PacketCipher>,
+    sender: PacketSender,
+}
+
+pub struct SecureChannel {
+    sender: PacketSender,
+    receiver: PacketReceiver,
+}
+
+impl SecureChannel {
+
+    //The client sends with the client -> server keys, and receives with the server -> client ones
+    pub fn client(stream: TcpStream, keys: &SessionKeys, algorithms: &Algorithms, session_hash: &[u8], limits: RekeyLimits) -> Result<Self> {
+        Self::new(stream, keys, Rekeying { session_hash: session_hash.to_vec(), algorithms: *algorithms, is_client: true, limits })
+    }
+
+    //The server sends with the server -> client keys, and receives with the client -> server ones
+    pub fn server(stream: TcpStream, keys: &SessionKeys, algorithms: &Algorithms, session_hash: &[u8], limits: RekeyLimits) -> Result<Self> {
+        Self::new(stream, keys, Rekeying { session_hash: session_hash.to_vec(), algorithms: *algorithms, is_client: false, limits })
+    }
+
+    fn new(stream: TcpStream, keys: &SessionKeys, rekeying: Rekeying) -> Result<Self> {
+
+        let (send_keys, receive_keys) = direction_keys(keys, rekeying.is_client);
+
+        //A request waiting for his response, as a file transfer one, must not wait for the delayed ACK
+        stream.set_nodelay(true)?;
+
+        let state = SendState {
+            stream: stream.try_clone()?,
+            cipher: PacketCipher::new(&rekeying.algorithms, send_keys)?,
+            sequence_number: 0,
+            usage: KeysUsage::new(),
+            pending: None,
+        };
+
+        let sender = PacketSender { state: Arc::new(Mutex::new(state)), rekeying: Arc::new(rekeying) };
+
+        let receiver = PacketReceiver {
+            stream,
+            cipher: PacketCipher::new(&sender.rekeying.algorithms, receive_keys)?,
+            sequence_number: 0,
+            usage: KeysUsage::new(),
+            next_cipher: None,
+            sender: sender.share(),
+        };
+
+        Ok(Self { sender, receiver })
+    }
+
+    pub fn send(&mut self, payload: &[u8]) -> Result<()> {
+        self.sender.send(payload)
+    }
+
+    pub fn receive(&mut self) -> Result<Vec<u8>> {
+        self.receiver.receive()
+    }
+
+    pub fn send_message(&mut self, message: &Message) -> Result<()> {
+        self.sender.send_message(message)
+    }
+
+    pub fn receive_message(&mut self) -> Result<Message> {
+        self.receiver.receive_message()
+    }
+
+    //Divides the channel, so each direction may be used by a different thread
+    pub fn split(self) -> (PacketSender, PacketReceiver) {
+        (self.sender, self.receiver)
+    }
+}
+
+impl PacketSender {
+
+    //Encrypts and sends a packet, with his MAC or tag, starting a rekey once a limit is reached
+    pub fn send(&mut self, payload: &[u8]) -> Result<()> {
+
+        let mut state = self.state.lock().unwrap();
+
+        state.write(payload)?;
+
+        if state.usage.exceeds(&self.rekeying.limits) {
+            state.start_rekey(&self.rekeying)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn send_message(&mut self, message: &Message) -> Result<()> {
+        self.send(&message.encode()?)
+    }
+
+    //The same direction, for the receiver
+    fn share(&self) -> Self {
+        Self { state: Arc::clone(&self.state), rekeying: Arc::clone(&self.rekeying) }
+    }
+}
+
+impl SendState {
+
+    fn write(&mut self, payload: &[u8]) -> Result<()> {
+
+        if payload.len() > protocol::PACKET_MAX_SIZE {
+            return Err(Error::Static(PACKET_TOO_LONG_ERROR));
+        }
+
+        let packet = self.cipher.seal(self.sequence_number, payload)?;
+        self.stream.write_all(&packet)?;
+
+        self.sequence_number = next_sequence_number(self.sequence_number)?;
+        self.usage.add_packet(payload.len());
+
+        Ok(())
+    }
+
+    //Sends our keys of a new key exchange, if one was not started yet
+    fn start_rekey(&mut self, rekeying: &Rekeying) -> Result<()> {
+
+        if self.pending.is_some() {
+            return Ok(());
+        }
+
+        crate::utils::debug(1, "Starting a rekey");
+
+        let keys = KexKeys::for_rekey(rekeying.algorithms.kex);
+        self.write(&Message::Rekey(Rekey { keys: keys.get_client_keys() }).encode()?)?;
+        self.pending = Some(keys);
+
+        Ok(())
+    }
+
+    //From the next packet, every packet uses the new keys
+    fn use_new_keys(&mut self, cipher: PacketCipher) -> Result<()> {
+
+        self.write(&Message::NewKeys(NewKeys).encode()?)?;
+
+        self.cipher = cipher;
+        self.sequence_number = 0;
+        self.usage = KeysUsage::new();
+
+        Ok(())
+    }
+}
+
+impl PacketReceiver {
+
+    /*
+     * Reads a packet, decrypts it and verifies his MAC or tag, returning the payload,
+     * the rekey messages are answered here, so they are never returned
+     */
+    pub fn receive(&mut self) -> Result<Vec<u8>> {
+
+        loop {
+
+            let payload = self.cipher.open(&mut self.stream, self.sequence_number)?;
+
+            self.sequence_number = next_sequence_number(self.sequence_number)?;
+            self.usage.add_packet(payload.len());
+
+            match Message::message_type(&payload) {
+                Ok(SsshMessages::Rekey) => self.answer_rekey(&payload)?,
+                Ok(SsshMessages::NewKeys) => self.use_new_keys()?,
+                _ => {
+                    self.check_limits()?;
+                    return Ok(payload);
+                },
+            }
+        }
+    }
+
+    pub fn receive_message(&mut self) -> Result<Message> {
+        Message::decode(&self.receive()?)
+    }
+
+    /*
+     * Computes the new keys with the other machine keys, sending ours first
+     * if the rekey was started by him, then our direction uses the new keys
+     */
+    fn answer_rekey(&mut self, payload: &[u8]) -> Result<()> {
+
+        let Message::Rekey(rekey) = Message::decode(payload)? else {
+            return Err(Error::Static(message::UNEXPECTED_MESSAGE_ERROR));
+        };
+
+        let rekeying = Arc::clone(&self.sender.rekeying);
+
+        if rekey.keys.get_algorithm() != rekeying.algorithms.kex {
+            return Err(Error::Static(REKEY_ALGORITHM_ERROR));
+        }
+
+        if self.next_cipher.is_some() {
+            return Err(Error::Static(REKEY_IN_PROGRESS_ERROR));
+        }
+
+        let mut state = self.sender.state.lock().unwrap();
+
+        let keys = match state.pending.take() {
+            Some(keys) => keys,
+            None => {
+                let keys = KexKeys::from_client_keys(&rekey.keys)?;
+                state.write(&Message::Rekey(Rekey { keys: keys.get_client_keys() }).encode()?)?;
+                keys
+            },
+        };
+
+        //The original session hash is mixed into the new keys
+        let shared_key = keys.compute_shared_key(&rekey.keys.get_public_key())?;
+        let session_keys = crypto::generate_session_keys(&rekeying.session_hash, shared_key, &rekeying.algorithms);
+
+        let (send_keys, receive_keys) = direction_keys(&session_keys, rekeying.is_client);
+
+        self.next_cipher = Some(PacketCipher::new(&rekeying.algorithms, receive_keys)?);
+        state.use_new_keys(PacketCipher::new(&rekeying.algorithms, send_keys)?)?;
+
+        crate::utils::debug(1, "Rekey done, sending with the new keys");
+
+        Ok(())
+    }
+
+    //The packets after NewKeys use the keys computed on the rekey
+    fn use_new_keys(&mut self) -> Result<()> {
+
+        self.cipher = self.next_cipher.take().ok_or(Error::Static(UNEXPECTED_NEW_KEYS_ERROR))?;
+        self.sequence_number = 0;
+        self.usage = KeysUsage::new();
+
+        Ok(())
+    }
+
+    //The received packets may also reach a limit, unless a rekey is already running
+    fn check_limits(&mut self) -> Result<()> {
+
+        if self.next_cipher.is_none() && self.usage.exceeds(&self.sender.rekeying.limits) {
+            self.sender.state.lock().unwrap().start_rekey(&self.sender.rekeying)?;
+        }
+
+        Ok(())
+    }
+}
+
+//The keys which a machine sends with, and the ones it receives with
+fn direction_keys(keys: &SessionKeys, is_client: bool) -> (DirectionKeys<'_>, DirectionKeys<'_>) {
+
+    let client_server = (&keys.client_server_enc_key[..], &keys.client_server_iv[..], &keys.client_server_mac_key[..]);
+    let server_client = (&keys.server_client_enc_key[..], &keys.server_client_iv[..], &keys.server_client_mac_key[..]);
+
+    if is_client {
+        (client_server, server_client)
+    } else {
+        (server_client, client_server)
+    }
+}
+
+impl PacketCipher {
+
+    //The MAC key is only used without an AEAD cipher
+    fn new(algorithms: &Algorithms, (enc_key, iv, mac_key): DirectionKeys) -> Result<Self> {
+
+        if iv.len() != algorithms.cipher.iv_size() {
+            return Err(Error::Static(INVALID_KEY_ERROR));
+        }
+
+        match algorithms.cipher {
+            CipherAlgorithm::Aes256Ctr => Ok(PacketCipher::Aes256Ctr {
+                cipher: Aes256Ctr::new_from_slices(enc_key, iv).map_err(|_| Error::Static(INVALID_KEY_ERROR))?,
+                mac_key: new_mac_key(algorithms.mac, mac_key),
+            }),
+            CipherAlgorithm::ChaCha20Poly1305 => Ok(PacketCipher::ChaCha20Poly1305 {
+                cipher: ChaCha20Poly1305::new_from_slice(enc_key).map_err(|_| Error::Static(INVALID_KEY_ERROR))?,
+                iv: iv.to_vec(),
+            }),
+            CipherAlgorithm::Aes256Gcm => Ok(PacketCipher::Aes256Gcm {
+                cipher: Aes256Gcm::new_from_slice(enc_key).map_err(|_| Error::Static(INVALID_KEY_ERROR))?,
+                iv: iv.to_vec(),
+            }),
+        }
+    }
+
+    //Builds the packet as written on the stream
+    fn seal(&mut self, sequence_number: u32, payload: &[u8]) -> Result<Vec<u8>> {
+
+        let length = (payload.len() as u32).to_be_bytes();
+
+        match self {
+            PacketCipher::Aes256Ctr { cipher, mac_key } => {
+
+                let mac = compute_mac(mac_key, sequence_number, &length, payload)?;
+
+                let mut packet = Vec::with_capacity(length.len() + payload.len() + MAC_SIZE);
+                packet.extend_from_slice(&length);
+                packet.extend_from_slice(payload);
+
+                cipher.apply_keystream(&mut packet); //Only the length and payload are encrypted
+
+                packet.extend_from_slice(&mac);
+                Ok(packet)
+            },
+            PacketCipher::ChaCha20Poly1305 { cipher, iv } => seal_aead(cipher, iv, sequence_number, &length, payload),
+            PacketCipher::Aes256Gcm { cipher, iv } => seal_aead(cipher, iv, sequence_number, &length, payload),
+        }
+    }
+
+    //Reads a packet from the stream, returning the verified payload
+    fn open(&mut self, stream: &mut TcpStream, sequence_number: u32) -> Result<Vec<u8>> {
+
+        //First reads the length, to know how much to read
+        let mut length = [0u8; LENGTH_SIZE];
+        stream.read_exact(&mut length)?;
+
+        if let PacketCipher::Aes256Ctr { cipher, .. } = self {
+            cipher.apply_keystream(&mut length);
+        }
+
+        let size = u32::from_be_bytes(length) as usize;
+
+        //A tampered length is only detected after reading, so it is limited first
+        if size > protocol::PACKET_MAX_SIZE {
+            return Err(Error::Static(PACKET_TOO_LONG_ERROR));
+        }
+
+        match self {
+            PacketCipher::Aes256Ctr { cipher, mac_key } => {
+
+                let mut payload = vec![0u8; size];
+                stream.read_exact(&mut payload)?;
+                cipher.apply_keystream(&mut payload);
+
+                let mut mac = [0u8; MAC_SIZE];
+                stream.read_exact(&mut mac)?;
+
+                verify_mac(mac_key, sequence_number, &length, &payload, &mac)?;
+
+                Ok(payload)
+            },
+            PacketCipher::ChaCha20Poly1305 { cipher, iv } => open_aead(cipher, iv, stream, sequence_number, &length, size),
+            PacketCipher::Aes256Gcm { cipher, iv } => open_aead(cipher, iv, stream, sequence_number, &length, size),
+        }
+    }
+}
+
+//The nonce is unique for each packet of a direction, as the sequence number
+fn aead_nonce<A: Aead>(iv: &[u8], sequence_number: u32) -> Nonce<A> {
+
+    let mut nonce = Nonce::<A>::clone_from_slice(iv);
+    let size = nonce.len();
+
+    for (byte, counter) in nonce[size - 4..].iter_mut().zip(sequence_number.to_be_bytes()) {
+        *byte ^= counter;
+    }
+
+    nonce
+}
+
+fn seal_aead<A: Aead>(cipher: &A, iv: &[u8], sequence_number: u32, length: &[u8], payload: &[u8]) -> Result<Vec<u8>> {
+
+    let sealed = cipher.encrypt(&aead_nonce::<A>(iv, sequence_number), Payload { msg: payload, aad: length })
+        .map_err(|_| Error::Static(ENCRYPTION_ERROR))?;
+
+    let mut packet = Vec::with_capacity(length.len() + sealed.len());
+    packet.extend_from_slice(length);
+    packet.extend_from_slice(&sealed); //The encrypted payload and the tag
+
+    Ok(packet)
+}
+
+fn open_aead<A: Aead>(cipher: &A, iv: &[u8], stream: &mut TcpStream, sequence_number: u32, length: &[u8], size: usize) -> Result<Vec<u8>> {
+
+    let mut sealed = vec![0u8; size + TAG_SIZE];
+    stream.read_exact(&mut sealed)?;
+
+    cipher.decrypt(&aead_nonce::<A>(iv, sequence_number), Payload { msg: &sealed, aad: length })
+        .map_err(|_| Error::Static(INVALID_TAG_ERROR))
+}
+
+fn new_mac_key(algorithm: MacAlgorithm, key: &[u8]) -> Vec<u8> {
+    match algorithm {
+        MacAlgorithm::HmacSha256 => key.to_vec(),
+    }
+}
+
+fn new_mac(key: &[u8], sequence_number: u32, length: &[u8], payload: &[u8]) -> Result<HmacSha256> {
+
+    let mut mac = <HmacSha256 as Mac>::new_from_slice(key).map_err(|_| Error::Static(INVALID_KEY_ERROR))?;
+
+    mac.update(&sequence_number.to_be_bytes());
+    mac.update(length);
+    mac.update(payload);
+
+    Ok(mac)
+}
+
+fn compute_mac(key: &[u8], sequence_number: u32, length: &[u8], payload: &[u8]) -> Result<Vec<u8>> {
+    Ok(new_mac(key, sequence_number, length, payload)?.finalize().into_bytes().to_vec())
+}
+
+//Compares the MAC in constant time
+fn verify_mac(key: &[u8], sequence_number: u32, length: &[u8], payload: &[u8], mac: &[u8]) -> Result<()> {
+    new_mac(key, sequence_number, length, payload)?
+        .verify_slice(mac)
+        .map_err(|_| Error::Static(INVALID_MAC_ERROR))
+}
+
+//The sequence number can never repeat with the same keys
+fn next_sequence_number(sequence_number: u32) -> Result<u32> {
+    sequence_number.checked_add(1).ok_or(Error::Static(SEQUENCE_NUMBER_EXHAUSTED_ERROR))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use num_bigint::BigUint;
+    use crate::crypto::algorithms::{Algorithm, AlgorithmLists};
+
+    const SESSION_HASH: [u8; 32] = [0x42; 32];
+
+    fn session_keys(cipher: &str) -> (SessionKeys, Algorithms) {
+        let lists = AlgorithmLists { cipher: vec![cipher.to_string()], ..Default::default() };
+        let algorithms = lists.negotiate(&AlgorithmLists::default()).unwrap();
+
+        (SessionKeys::new(&SESSION_HASH, BigUint::from(0x0102030405u64), &algorithms), algorithms)
+    }
+
+    fn loopback() -> (TcpStream, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _) = listener.accept().unwrap();
+
+        (client, server)
+    }
+
+    //A client and a server on the loopback, with the same session keys of the cipher
+    pub(crate) fn secure_pair(cipher: &str, limits: RekeyLimits) -> (SecureChannel, SecureChannel) {
+        secure_pair_with_limits(cipher, limits, limits)
+    }
+
+    fn secure_pair_with_limits(cipher: &str, client_limits: RekeyLimits, server_limits: RekeyLimits) -> (SecureChannel, SecureChannel) {
+        let (keys, algorithms) = session_keys(cipher);
+        let (client_stream, server_stream) = loopback();
+
+        let client = SecureChannel::client(client_stream, &keys, &algorithms, &SESSION_HASH, client_limits).unwrap();
+        let server = SecureChannel::server(server_stream, &keys, &algorithms, &SESSION_HASH, server_limits).unwrap();
+
+        (client, server)
+    }
+
+    //A server, and the socket and client keys to write him any packet, as a tampered one
+    fn raw_client(cipher: &str) -> (TcpStream, PacketCipher, SecureChannel) {
+        let (keys, algorithms) = session_keys(cipher);
+        let (client_stream, server_stream) = loopback();
+
+        let (client_keys, _) = direction_keys(&keys, true);
+        let client_cipher = PacketCipher::new(&algorithms, client_keys).unwrap();
+        let server = SecureChannel::server(server_stream, &keys, &algorithms, &SESSION_HASH, RekeyLimits::default()).unwrap();
+
+        (client_stream, client_cipher, server)
+    }
+
+    #[test]
+    fn packets_round_trip() {
+        let (mut client, mut server) = secure_pair("aes256-ctr", RekeyLimits::default());
+
+        for payload in [&b"first"[..], &[0xfe; 1000][..], &vec![0x7f; protocol::PACKET_MAX_SIZE][..]] {
+            client.send(payload).unwrap();
+            assert_eq!(server.receive().unwrap(), payload);
+        }
+
+        server.send(b"answer").unwrap();
+        assert_eq!(client.receive().unwrap(), b"answer");
+    }
+
+    #[test]
+    fn flipped_ciphertext_fails_the_mac() {
+        let (mut stream, mut cipher, mut server) = raw_client("aes256-ctr");
+
+        let mut packet = cipher.seal(0, b"a payload").unwrap();
+        packet[LENGTH_SIZE + 2] ^= 0x01;
+        stream.write_all(&packet).unwrap();
+
+        assert!(matches!(server.receive(), Err(Error::Static(INVALID_MAC_ERROR))));
+    }
+
+    #[test]
+    fn flipped_mac_fails_the_mac() {
+        let (mut stream, mut cipher, mut server) = raw_client("aes256-ctr");
+
+        let mut packet = cipher.seal(0, b"a payload").unwrap();
+        *packet.last_mut().unwrap() ^= 0x80;
+        stream.write_all(&packet).unwrap();
+
+        assert!(matches!(server.receive(), Err(Error::Static(INVALID_MAC_ERROR))));
+    }
+
+    //The keystream is right for the position, so only the sequence number of the MAC tells the replay
+    #[test]
+    fn replayed_sequence_number_fails_the_mac() {
+        let (mut stream, mut cipher, mut server) = raw_client("aes256-ctr");
+
+        stream.write_all(&cipher.seal(0, b"once").unwrap()).unwrap();
+        assert_eq!(server.receive().unwrap(), b"once");
+
+        stream.write_all(&cipher.seal(0, b"once").unwrap()).unwrap();
+        assert!(matches!(server.receive(), Err(Error::Static(INVALID_MAC_ERROR))));
+    }
+
+    #[test]
+    fn reordered_sequence_numbers_fail_the_mac() {
+        let (mut stream, mut cipher, mut server) = raw_client("aes256-ctr");
+
+        stream.write_all(&cipher.seal(1, b"second").unwrap()).unwrap();
+        stream.write_all(&cipher.seal(0, b"first").unwrap()).unwrap();
+
+        assert!(matches!(server.receive(), Err(Error::Static(INVALID_MAC_ERROR))));
+    }
+
+    #[test]
+    fn length_over_the_maximum_is_rejected() {
+        let (mut stream, mut cipher, mut server) = raw_client("aes256-ctr");
+
+        //Only the length is written, it must be refused before reading the rest
+        let packet = cipher.seal(0, &vec![0u8; protocol::PACKET_MAX_SIZE + 1]).unwrap();
+        stream.write_all(&packet[..LENGTH_SIZE]).unwrap();
+
+        assert!(matches!(server.receive(), Err(Error::Static(PACKET_TOO_LONG_ERROR))));
+    }
+
+    #[test]
+    fn payload_over_the_maximum_is_not_sent() {
+        let (mut client, _server) = secure_pair("aes256-ctr", RekeyLimits::default());
+
+        assert!(matches!(client.send(&vec![0u8; protocol::PACKET_MAX_SIZE + 1]), Err(Error::Static(PACKET_TOO_LONG_ERROR))));
+    }
+
+    fn aead_ciphers() -> impl Iterator<Item = &'static str> {
+        CipherAlgorithm::all().iter().filter(|cipher| cipher.is_aead()).map(|cipher| cipher.name())
+    }
+
+    #[test]
+    fn aead_packets_round_trip() {
+        for cipher in aead_ciphers() {
+            let (mut client, mut server) = secure_pair(cipher, RekeyLimits::default());
+
+            for payload in [&b"first"[..], &b""[..], &vec![0x5a; protocol::PACKET_MAX_SIZE][..]] {
+                client.send(payload).unwrap();
+                assert_eq!(server.receive().unwrap(), payload, "{}", cipher);
+
+                server.send(payload).unwrap();
+                assert_eq!(client.receive().unwrap(), payload, "{}", cipher);
+            }
+        }
+    }
+
+    #[test]
+    fn aead_tampered_packet_fails_the_tag() {
+        for cipher in aead_ciphers() {
+            //The tag, the encrypted payload and the length, which is only authenticated
+            for index in [usize::MAX, LENGTH_SIZE + 1, LENGTH_SIZE - 1] {
+                let (mut stream, mut packet_cipher, mut server) = raw_client(cipher);
+
+                let mut packet = packet_cipher.seal(0, b"a payload").unwrap();
+                let index = index.min(packet.len() - 1);
+                packet[index] ^= 0x01;
+                stream.write_all(&packet).unwrap();
+
+                assert!(matches!(server.receive(), Err(Error::Static(INVALID_TAG_ERROR))), "{} byte {}", cipher, index);
+            }
+        }
+    }
+
+    #[test]
+    fn aead_replayed_packet_fails_the_tag() {
+        for cipher in aead_ciphers() {
+            let (mut stream, mut packet_cipher, mut server) = raw_client(cipher);
+
+            let packet = packet_cipher.seal(0, b"once").unwrap();
+
+            stream.write_all(&packet).unwrap();
+            assert_eq!(server.receive().unwrap(), b"once");
+
+            stream.write_all(&packet).unwrap();
+            assert!(matches!(server.receive(), Err(Error::Static(INVALID_TAG_ERROR))), "{}", cipher);
+        }
+    }
+
+    //The nonce is the IV with the sequence number on his last bytes, never the same for two packets
+    fn assert_unique_nonces<A: Aead>(iv: &[u8]) {
+        let nonces: Vec<Nonce<A>> = (0..64).map(|sequence_number| aead_nonce::<A>(iv, sequence_number)).collect();
+
+        for (sequence_number, nonce) in nonces.iter().enumerate() {
+            assert_eq!(nonce[..8], iv[..8]);
+            assert_eq!(u32::from_be_bytes(nonce[8..].try_into().unwrap()) ^ u32::from_be_bytes(iv[8..].try_into().unwrap()), sequence_number as u32);
+            assert!(nonces[sequence_number + 1..].iter().all(|other| other != nonce));
+        }
+    }
+
+    #[test]
+    fn aead_consecutive_packets_use_different_nonces() {
+        let iv: Vec<u8> = (0xa0..0xac).collect();
+
+        assert_unique_nonces::<ChaCha20Poly1305>(&iv);
+        assert_unique_nonces::<Aes256Gcm>(&iv);
+
+        //So the same payload is never sealed the same twice
+        for cipher in aead_ciphers() {
+            let (_, mut packet_cipher, _) = raw_client(cipher);
+
+            let first = packet_cipher.seal(0, b"same").unwrap();
+            let second = packet_cipher.seal(1, b"same").unwrap();
+
+            assert_eq!(first[..LENGTH_SIZE], second[..LENGTH_SIZE]);
+            assert_ne!(first[LENGTH_SIZE..], second[LENGTH_SIZE..], "{}", cipher);
+        }
+    }
+
+    //A rekey after the packets, or never
+    fn packet_limits(packets: u64) -> RekeyLimits {
+        RekeyLimits { bytes: 0, packets, interval: std::time::Duration::ZERO }
+    }
+
+    //The other machine answers the rekey on his next receive, the data before, during and after it arrives
+    fn rekey_from(starter: &mut SecureChannel, answerer: &mut SecureChannel) {
+        starter.send(b"before 1").unwrap();
+        starter.send(b"before 2").unwrap(); //Reaches the limit, so Rekey follows it
+        assert!(starter.sender.state.lock().unwrap().pending.is_some());
+
+        assert_eq!(answerer.receive().unwrap(), b"before 1");
+        assert_eq!(answerer.receive().unwrap(), b"before 2");
+
+        //Still with the old keys, as the answer did not arrive
+        starter.send(b"during").unwrap();
+        assert_eq!(answerer.receive().unwrap(), b"during");
+        assert!(answerer.receiver.next_cipher.is_some());
+
+        answerer.send(b"answerer after").unwrap();
+        assert_eq!(starter.receive().unwrap(), b"answerer after");
+        assert!(starter.sender.state.lock().unwrap().pending.is_none());
+
+        starter.send(b"starter after").unwrap();
+        assert_eq!(answerer.receive().unwrap(), b"starter after");
+        assert!(answerer.receiver.next_cipher.is_none());
+
+        assert_eq!(starter.sender.state.lock().unwrap().sequence_number, 1);
+        assert_eq!(answerer.sender.state.lock().unwrap().sequence_number, 1);
+    }
+
+    #[test]
+    fn rekey_started_by_the_client() {
+        for cipher in CipherAlgorithm::all() {
+            let (mut client, mut server) = secure_pair_with_limits(cipher.name(), packet_limits(2), packet_limits(0));
+            rekey_from(&mut client, &mut server);
+        }
+    }
+
+    #[test]
+    fn rekey_started_by_the_server() {
+        for cipher in CipherAlgorithm::all() {
+            let (mut client, mut server) = secure_pair_with_limits(cipher.name(), packet_limits(0), packet_limits(2));
+            rekey_from(&mut server, &mut client);
+        }
+    }
+
+    //Both machines send Rekey before receiving the other one, so each uses his own pending keys
+    #[test]
+    fn rekey_started_by_both_at_once() {
+        for cipher in CipherAlgorithm::all() {
+            let (mut client, mut server) = secure_pair_with_limits(cipher.name(), packet_limits(2), packet_limits(2));
+
+            client.send(b"client 1").unwrap();
+            client.send(b"client 2").unwrap();
+            server.send(b"server 1").unwrap();
+            server.send(b"server 2").unwrap();
+
+            assert!(client.sender.state.lock().unwrap().pending.is_some());
+            assert!(server.sender.state.lock().unwrap().pending.is_some());
+
+            assert_eq!(client.receive().unwrap(), b"server 1");
+            assert_eq!(client.receive().unwrap(), b"server 2");
+
+            //Sent after his Rekey, with the old keys, the client answers on the way
+            server.send(b"server 3").unwrap();
+            assert_eq!(client.receive().unwrap(), b"server 3");
+
+            assert_eq!(server.receive().unwrap(), b"client 1");
+            assert_eq!(server.receive().unwrap(), b"client 2");
+
+            client.send(b"client after").unwrap();
+            assert_eq!(server.receive().unwrap(), b"client after");
+
+            server.send(b"server after").unwrap();
+            assert_eq!(client.receive().unwrap(), b"server after");
+
+            for side in [&client, &server] {
+                assert!(side.sender.state.lock().unwrap().pending.is_none());
+                assert!(side.receiver.next_cipher.is_none());
+            }
+        }
+    }
+}
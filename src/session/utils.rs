@@ -3,39 +3,150 @@
  * #######################################
  * File responsible for the inclusion
  * of utility functions, for the process
+ *
+ * Every message before the encryption is
+ * sent as a frame:
+ *
+ * length (4 bytes, big endian) || content
  * #######################################
  */
 
-use std::{io::Read, net::{Ipv4Addr, TcpStream}};
+use std::{io::{ErrorKind, Read, Write}, net::{IpAddr, SocketAddr, TcpStream, ToSocketAddrs}};
 use crate::error::{Result,Error};
+use crate::session::message::Message;
+use crate::session::protocol;
 
 const SPLIT_CHAR : char = '#';
-const SPLIT_IDENTIFIER_INVALID_ERROR : &str = "Invalid identifier format use: user#ip";
-const IP_INVALID_ERROR : &str = "Invalid IP address format";
+const SPLIT_IDENTIFIER_INVALID_ERROR : &str = "Invalid identifier format use: user#host or user#[host]:port";
+const HOST_INVALID_ERROR : &str = "Invalid host format, an IPv6 address must be inside []";
+const PORT_INVALID_ERROR : &str = "Invalid port after the host";
+const HOST_RESOLUTION_ERROR : &str = "Could not resolve the host name";
+const MESSAGE_TOO_LONG_ERROR : &str = "The message is bigger than the maximum packet size";
+const FRAME_LENGTH_SIZE : usize = 4;
 
-//Divides an identifier, which is user#ip, into a IPV4 and a String
-pub fn identifier_to_user_ip(identifier: &str) -> Result<(String, Ipv4Addr)>{
+/*
+ * Divides an identifier into the user, the host and the port, if any.
+ * The host may be a name, an IPv4 or an IPv6 address:
+ *
+ * user#example.com, user#10.0.0.1, user#::1, user#[::1]:2222
+ */
+pub fn identifier_to_user_host(identifier: &str) -> Result<(String, String, Option<u16>)>{
+
+    let Some((user, host)) = identifier.split_once(SPLIT_CHAR) else {
+        return Err(Error::Static(SPLIT_IDENTIFIER_INVALID_ERROR));
+    };
+
+    if user.is_empty() || host.is_empty() {
+        return Err(Error::Static(SPLIT_IDENTIFIER_INVALID_ERROR));
+    }
+
+    //Inside [] the host may have a port after it, as [::1]:2222
+    if let Some(bracketed) = host.strip_prefix('[') {
 
+        let Some((host, port)) = bracketed.split_once(']') else {
+            return Err(Error::Static(HOST_INVALID_ERROR));
+        };
+
+        let port = match port {
+            "" => None,
+            port => Some(parse_port(port.strip_prefix(':').unwrap_or(port))?),
+        };
+
+        return Ok((user.to_string(), host.to_string(), port));
+    }
+
+    Ok((user.to_string(), host.to_string(), None))
+}
 
-    if let Some((user,ip_str)) = identifier.split_once(SPLIT_CHAR){
-        
-        let ip : Ipv4Addr = ip_str.parse().map_err(|_| Error::Static(IP_INVALID_ERROR))?;
-        Ok((user.to_string(),ip))    
-    }else {
-        Err(Error::Static(SPLIT_IDENTIFIER_INVALID_ERROR))
+fn parse_port(port: &str) -> Result<u16> {
+    match port.parse::<u16>() {
+        Ok(port) if port != 0 => Ok(port),
+        _ => Err(Error::Static(PORT_INVALID_ERROR)),
     }
 }
 
+//Resolves the host with the system resolver, an address resolves to himself
+pub fn resolve_host(host: &str, port: u16) -> Result<Vec<SocketAddr>> {
+
+    let addresses: Vec<SocketAddr> = (host, port).to_socket_addrs()
+        .map_err(|_| Error::Static(HOST_RESOLUTION_ERROR))?
+        .collect();
+
+    if addresses.is_empty() {
+        return Err(Error::Static(HOST_RESOLUTION_ERROR));
+    }
+
+    Ok(addresses)
+}
+
+/*
+ * The names of a host at the known hosts, the name used by the user
+ * and the connected address, with the port if not the default one:
+ *
+ * example.com,10.0.0.1 or [example.com]:2222,[10.0.0.1]:2222
+ */
+pub fn known_host_names(host: &str, address: &SocketAddr) -> Vec<String> {
+
+    let ip = address.ip().to_string();
+
+    //An address given by the user is only stored once
+    let mut names = match host.parse::<IpAddr>() {
+        Ok(_) => vec![ip],
+        Err(_) => vec![host.to_lowercase(), ip],
+    };
 
-// Reads 1 byte for size, then reads exactly that many bytes from the stream
+    if address.port() != protocol::DEFAULT_PORT {
+        names = names.into_iter().map(|name| format!("[{}]:{}", name, address.port())).collect();
+    }
+
+    names
+}
+
+
+// Reads 4 bytes for size, then reads exactly that many bytes from the stream
 pub fn read_from_tcp(stream: &mut TcpStream) -> Result<Vec<u8>> {
-    let mut size_buf = [0u8; 1];
-    stream.read_exact(&mut size_buf)?;  // read 1 byte for size
+    let mut size_buf = [0u8; FRAME_LENGTH_SIZE];
+    stream.read_exact(&mut size_buf)?;  // read 4 bytes for size
 
-    let size = size_buf[0] as usize;
+    let size = u32::from_be_bytes(size_buf) as usize;
+
+    if size > protocol::PACKET_MAX_SIZE {
+        return Err(Error::Static(MESSAGE_TOO_LONG_ERROR));
+    }
 
     let mut buffer = vec![0u8; size];
     stream.read_exact(&mut buffer)?;    // read exactly 'size' bytes
 
     Ok(buffer)
 }
+
+// Writes 4 bytes for size, then the bytes, the opposite of read_from_tcp
+pub fn write_to_tcp(stream: &mut TcpStream, buffer: &[u8]) -> Result<()> {
+
+    if buffer.len() > protocol::PACKET_MAX_SIZE {
+        return Err(Error::Static(MESSAGE_TOO_LONG_ERROR));
+    }
+
+    let mut frame = Vec::with_capacity(FRAME_LENGTH_SIZE + buffer.len());
+    frame.extend_from_slice(&(buffer.len() as u32).to_be_bytes());
+    frame.extend_from_slice(buffer);
+
+    stream.write_all(&frame)?; //A single write, so the frame is not split in many packets
+
+    Ok(())
+}
+
+// Encodes a message and writes it as a single frame
+pub fn write_message(stream: &mut TcpStream, message: &Message) -> Result<()> {
+    write_to_tcp(stream, &message.encode()?)
+}
+
+// Reads a frame and decodes the message
+pub fn read_message(stream: &mut TcpStream) -> Result<Message> {
+    Message::decode(&read_from_tcp(stream)?)
+}
+
+// Checks if the error is the other machine closing the socket
+pub fn is_connection_closed(error: &Error) -> bool {
+    matches!(error, Error::Io(e) if e.kind() == ErrorKind::UnexpectedEof)
+}
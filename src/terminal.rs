@@ -0,0 +1,67 @@
+use std::io::{self, IsTerminal};
+use std::mem;
+
+use crate::error::Result;
+/*
+ *#################################################
+ * File responsible for the local terminal, which
+ * during a shell must be in raw mode, so every
+ * key, as Ctrl+C, is sent to the server instead of
+ * being handled by the local terminal.
+ *
+ * RawMode restores the terminal when dropped.
+ *#################################################
+ */
+
+const DEFAULT_COLUMNS : u16 = 80;
+const DEFAULT_ROWS : u16 = 24;
+
+pub struct RawMode {
+    original : libc::termios,
+}
+
+impl RawMode {
+
+    //Puts the stdin in raw mode, returning None if the stdin is not a terminal
+    pub fn enable() -> Result<Option<Self>> {
+
+        if !io::stdin().is_terminal() {
+            return Ok(None);
+        }
+
+        // SAFETY: termios is a plain struct filled by tcgetattr
+        let mut original: libc::termios = unsafe { mem::zeroed() };
+
+        if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut original) } < 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+
+        let mut raw = original;
+        unsafe { libc::cfmakeraw(&mut raw) };
+
+        if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) } < 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+
+        Ok(Some(Self { original }))
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original) };
+    }
+}
+
+//Gets the terminal columns and rows, or the default 80x24 if is not a terminal
+pub fn window_size() -> (u16, u16) {
+
+    // SAFETY: winsize is a plain struct filled by the ioctl
+    let mut size: libc::winsize = unsafe { mem::zeroed() };
+
+    if unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } < 0 || size.ws_col == 0 {
+        return (DEFAULT_COLUMNS, DEFAULT_ROWS);
+    }
+
+    (size.ws_col, size.ws_row)
+}
@@ -0,0 +1,316 @@
+/*
+ * ##############################################
+ * File responsible for the transfer client, which
+ * starts the transfer subsystem on a channel of
+ * the session and sends each request, waiting for
+ * his response, as in protocol.rs.
+ *
+ * Also has the whole file transfers, by chunks
+ * of MAX_DATA_SIZE, where a resumed transfer
+ * starts at the size the destination already has,
+ * telling the progress after each chunk.
+ * ##############################################
+ */
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::session::channel::Channel;
+use crate::session::message::Message;
+use crate::session::Session;
+use crate::transfer::progress::Progress;
+use crate::transfer::protocol::{self, DirEntry, FileAttributes, OpenMode, Request, Response, MAX_DATA_SIZE, TRANSFER_SUBSYSTEM};
+
+const SUBSYSTEM_CLOSED_ERROR: &str = "The transfer server closed the channel";
+const UNEXPECTED_RESPONSE_ERROR: &str = "The transfer server sent an unexpected response";
+
+//The permissions, without the file type
+const PERMISSIONS_MASK: u32 = 0o7777;
+
+pub struct TransferClient {
+    stream: SubsystemStream,
+}
+
+//The subsystem channel as a byte stream, for the frames
+struct SubsystemStream {
+    channel: Channel,
+    pending: Vec<u8>,
+    position: usize,
+}
+
+impl TransferClient {
+
+    //Starts the transfer subsystem on a new channel of the session
+    pub fn start(session: &Session) -> Result<Self> {
+
+        let channel = session.subsystem(TRANSFER_SUBSYSTEM)?;
+
+        Ok(Self { stream: SubsystemStream { channel, pending: Vec::new(), position: 0 } })
+    }
+
+    pub fn open(&mut self, path: &str, mode: OpenMode) -> Result<u32> {
+        match self.request(&Request::Open { path: path.to_string(), mode })? {
+            Response::Handle(handle) => Ok(handle),
+            _ => Err(Error::Static(UNEXPECTED_RESPONSE_ERROR)),
+        }
+    }
+
+    //Reads up to the length at the offset, empty at the end of the file
+    pub fn read(&mut self, handle: u32, offset: u64, length: u32) -> Result<Vec<u8>> {
+        match self.request(&Request::Read { handle, offset, length })? {
+            Response::Data(data) => Ok(data),
+            _ => Err(Error::Static(UNEXPECTED_RESPONSE_ERROR)),
+        }
+    }
+
+    pub fn write(&mut self, handle: u32, offset: u64, data: &[u8]) -> Result<()> {
+        self.request_ok(&Request::Write { handle, offset, data: data.to_vec() })
+    }
+
+    pub fn close(&mut self, handle: u32) -> Result<()> {
+        self.request_ok(&Request::Close { handle })
+    }
+
+    pub fn stat(&mut self, path: &str) -> Result<FileAttributes> {
+        match self.request(&Request::Stat { path: path.to_string() })? {
+            Response::Attributes(attributes) => Ok(attributes),
+            _ => Err(Error::Static(UNEXPECTED_RESPONSE_ERROR)),
+        }
+    }
+
+    //The attributes of a symbolic link itself, not of the file it points to
+    pub fn link_stat(&mut self, path: &str) -> Result<FileAttributes> {
+        match self.request(&Request::LinkStat { path: path.to_string() })? {
+            Response::Attributes(attributes) => Ok(attributes),
+            _ => Err(Error::Static(UNEXPECTED_RESPONSE_ERROR)),
+        }
+    }
+
+    //The entries of the directory, sorted by name, read by batches so any directory fits the frames
+    pub fn read_dir(&mut self, path: &str) -> Result<Vec<DirEntry>> {
+
+        let handle = match self.request(&Request::OpenDir { path: path.to_string() })? {
+            Response::Handle(handle) => handle,
+            _ => return Err(Error::Static(UNEXPECTED_RESPONSE_ERROR)),
+        };
+
+        //The handle is closed even if a batch failed
+        let result = self.read_dir_entries(handle);
+        self.close(handle)?;
+
+        result
+    }
+
+    pub fn make_dir(&mut self, path: &str) -> Result<()> {
+        self.request_ok(&Request::MakeDir { path: path.to_string() })
+    }
+
+    pub fn remove(&mut self, path: &str) -> Result<()> {
+        self.request_ok(&Request::Remove { path: path.to_string() })
+    }
+
+    pub fn remove_dir(&mut self, path: &str) -> Result<()> {
+        self.request_ok(&Request::RemoveDir { path: path.to_string() })
+    }
+
+    pub fn rename(&mut self, from: &str, to: &str) -> Result<()> {
+        self.request_ok(&Request::Rename { from: from.to_string(), to: to.to_string() })
+    }
+
+    pub fn set_permissions(&mut self, path: &str, permissions: u32) -> Result<()> {
+        self.request_ok(&Request::SetPermissions { path: path.to_string(), permissions })
+    }
+
+    pub fn set_times(&mut self, path: &str, accessed: i64, modified: i64) -> Result<()> {
+        self.request_ok(&Request::SetTimes { path: path.to_string(), accessed, modified })
+    }
+
+    //The absolute path, without links, as the server sees it
+    pub fn real_path(&mut self, path: &str) -> Result<String> {
+        match self.request(&Request::RealPath { path: path.to_string() })? {
+            Response::Path(path) => Ok(path),
+            _ => Err(Error::Static(UNEXPECTED_RESPONSE_ERROR)),
+        }
+    }
+
+    /*
+     * Copies the remote file to the local one, from the size the local
+     * file has if resuming, returning how many bytes were copied
+     */
+    pub fn download(&mut self, remote: &str, local: &Path, resume: bool, progress: &mut Progress) -> Result<u64> {
+
+        let attributes = self.stat(remote)?;
+
+        let mut file = OpenOptions::new().write(true).create(true).truncate(!resume).open(local)?;
+
+        let mut offset = if resume { file.seek(SeekFrom::End(0))? } else { 0 };
+
+        //A bigger local file is not a part of the remote one
+        if offset > attributes.size {
+            file.set_len(0)?;
+            file.seek(SeekFrom::Start(0))?;
+            offset = 0;
+        }
+
+        let start = offset;
+
+        let handle = self.open(remote, OpenMode::Read)?;
+
+        //The handle is closed even if the copy failed
+        let result = self.read_to_file(handle, offset, &mut file, attributes.size, progress);
+        self.close(handle)?;
+        let end = result?;
+
+        file.set_permissions(std::fs::Permissions::from_mode(attributes.mode & PERMISSIONS_MASK))?;
+
+        Ok(end - start)
+    }
+
+    /*
+     * Copies the local file to the remote one, from the size the remote
+     * file has if resuming, returning how many bytes were copied
+     */
+    pub fn upload(&mut self, local: &Path, remote: &str, resume: bool, progress: &mut Progress) -> Result<u64> {
+
+        let mut file = File::open(local)?;
+        let metadata = file.metadata()?;
+
+        let mut offset = match resume {
+            true => self.stat(remote).map(|attributes| attributes.size).unwrap_or(0),
+            false => 0,
+        };
+
+        //A bigger remote file is not a part of the local one
+        let truncate = !resume || offset > metadata.len();
+
+        if truncate {
+            offset = 0;
+        }
+
+        let start = offset;
+        let permissions = metadata.permissions().mode() & PERMISSIONS_MASK;
+
+        let handle = self.open(remote, OpenMode::Write { truncate, permissions })?;
+
+        let result = self.write_from_file(handle, offset, &mut file, metadata.len(), progress);
+        self.close(handle)?;
+        let end = result?;
+
+        Ok(end - start)
+    }
+
+    //Ends the subsystem, closing his channel
+    pub fn end(self) -> Result<()> {
+        self.stream.channel.close()
+    }
+
+    //Reads the batches of the directory handle, until an empty one
+    fn read_dir_entries(&mut self, handle: u32) -> Result<Vec<DirEntry>> {
+
+        let mut entries = Vec::new();
+
+        loop {
+            match self.request(&Request::ReadDirEntries { handle })? {
+                Response::Entries(batch) if batch.is_empty() => return Ok(entries),
+                Response::Entries(batch) => entries.extend(batch),
+                _ => return Err(Error::Static(UNEXPECTED_RESPONSE_ERROR)),
+            }
+        }
+    }
+
+    //Appends the remote file from the offset to the local file, returning the offset of the end
+    fn read_to_file(&mut self, handle: u32, mut offset: u64, file: &mut File, size: u64, progress: &mut Progress) -> Result<u64> {
+        loop {
+            progress.update(offset, size);
+
+            let data = self.read(handle, offset, MAX_DATA_SIZE)?;
+
+            if data.is_empty() {
+                return Ok(offset);
+            }
+
+            file.write_all(&data)?;
+            offset += data.len() as u64;
+        }
+    }
+
+    //Writes the local file from the offset to the remote file, returning the offset of the end
+    fn write_from_file(&mut self, handle: u32, mut offset: u64, file: &mut File, size: u64, progress: &mut Progress) -> Result<u64> {
+
+        file.seek(SeekFrom::Start(offset))?;
+
+        let mut buffer = vec![0u8; MAX_DATA_SIZE as usize];
+
+        loop {
+            progress.update(offset, size);
+
+            let size = file.read(&mut buffer)?;
+
+            if size == 0 {
+                return Ok(offset);
+            }
+
+            self.write(handle, offset, &buffer[..size])?;
+            offset += size as u64;
+        }
+    }
+
+    fn request(&mut self, request: &Request) -> Result<Response> {
+
+        protocol::write_frame(&mut self.stream, request)?;
+
+        match protocol::read_frame(&mut self.stream)? {
+            Some(Response::Error(reason)) => Err(Error::Transfer(reason)),
+            Some(response) => Ok(response),
+            None => Err(Error::Static(SUBSYSTEM_CLOSED_ERROR)),
+        }
+    }
+
+    fn request_ok(&mut self, request: &Request) -> Result<()> {
+        match self.request(request)? {
+            Response::Ok => Ok(()),
+            _ => Err(Error::Static(UNEXPECTED_RESPONSE_ERROR)),
+        }
+    }
+}
+
+impl Read for SubsystemStream {
+
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+
+        while self.position == self.pending.len() {
+
+            //The transfer server errors are shown, the channel closing is the end of the stream
+            match self.channel.receive() {
+                Some(Message::Data(data)) => {
+                    self.pending = data.data;
+                    self.position = 0;
+                },
+                Some(Message::ErrorData(data)) => io::stderr().write_all(&data.data)?,
+                Some(_) => {},
+                None => return Ok(0),
+            }
+        }
+
+        let size = buffer.len().min(self.pending.len() - self.position);
+        buffer[..size].copy_from_slice(&self.pending[self.position..self.position + size]);
+        self.position += size;
+
+        Ok(size)
+    }
+}
+
+impl Write for SubsystemStream {
+
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        self.channel.send_data(buffer).map_err(|e| io::Error::new(io::ErrorKind::BrokenPipe, e.to_string()))?;
+        Ok(buffer.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
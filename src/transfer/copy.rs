@@ -0,0 +1,379 @@
+/*
+ * ##############################################
+ * File responsible for the one-shot copy client,
+ * sssh-cp:
+ *
+ * sssh-cp [options] source... target
+ *
+ * Where either every source or the target is
+ * remote, as user#host:path, a relative remote
+ * path is from the user home:
+ *
+ * sssh-cp app.tar user#host:releases/
+ * sssh-cp -r user#host:/var/log/app ./logs
+ *
+ * -r - Copies the directories and their content,
+ *   skipping the symbolic links to directories in
+ *   them, as a link to a parent would never end
+ * -p - Preserves the permissions, access and modification times
+ * -q - Does not show the progress
+ * -P port - The server port
+ * -B, -i, -o, -v - As the sssh ones
+ *
+ * As cp, when the target is a directory the
+ * sources are copied into it, otherwise it is
+ * the copy name, for a single source.
+ *
+ * A failed source is shown and the next one is
+ * copied, the exit code tells if any failed.
+ * ##############################################
+ */
+
+use std::fs;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+use crate::cli;
+use crate::error::{Error, Result};
+use crate::file_sys;
+use crate::session::config::SessionConfig;
+use crate::transfer::client::TransferClient;
+use crate::transfer::progress::Progress;
+use crate::transfer::protocol;
+
+pub const USAGE: &str = "Usage: sssh-cp [-Bhpqrv] [-i identity_file] [-o option=value] [-P port] source... target";
+
+const REMOTE_SEPARATOR: char = '#';
+const PATH_SEPARATOR: char = ':';
+const MAX_VERBOSITY: u8 = 3;
+const PERMISSIONS_MASK: u32 = 0o7777;
+
+const DIRECTORY_ERROR: &str = "Is a directory, use -r to copy it";
+const TARGET_DIRECTORY_ERROR: &str = "The target must be a directory to copy several sources";
+const NO_FILE_NAME_ERROR: &str = "The path has no file name";
+const INVALID_ENTRY_NAME_ERROR: &str = "The server sent a directory entry which is not a file name";
+
+//The command line of sssh-cp
+pub struct CopyCli {
+    pub sources: Vec<Location>,
+    pub target: Location,
+    pub options: CopyOptions,
+    pub verbosity: u8,
+    pub help: bool,
+    pub config: SessionConfig,
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct CopyOptions {
+    pub recursive: bool,
+    pub preserve: bool,
+    pub quiet: bool,
+}
+
+//A path of the copy, local or on the server
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Location {
+    Local(PathBuf),
+    Remote { destination: String, path: String },
+}
+
+impl CopyCli {
+
+    //Parses the arguments, without the program name, returning an usage error message
+    pub fn parse(args: &[String]) -> std::result::Result<Self, String> {
+
+        let mut config = SessionConfig::default();
+        let mut options = CopyOptions::default();
+        let mut verbosity = 0;
+        let mut help = false;
+        let mut paths = Vec::new();
+        let mut index = 0;
+
+        while index < args.len() {
+
+            let arg = &args[index];
+            index += 1;
+
+            if !arg.starts_with('-') || arg.len() == 1 {
+                paths.push(Location::parse(arg));
+                continue;
+            }
+
+            let mut flags = arg[1..].chars();
+
+            while let Some(flag) = flags.next() {
+                match flag {
+                    'v' => verbosity = (verbosity + 1).min(MAX_VERBOSITY),
+                    'B' => config.batch_mode = true,
+                    'h' => help = true,
+                    'r' => options.recursive = true,
+                    'p' => options.preserve = true,
+                    'q' => options.quiet = true,
+                    'P' | 'i' | 'o' => {
+
+                        let attached: String = flags.by_ref().collect();
+
+                        let value = if !attached.is_empty() {
+                            attached
+                        } else if index < args.len() {
+                            index += 1;
+                            args[index - 1].clone()
+                        } else {
+                            return Err(format!("Option -{} requires a value", flag));
+                        };
+
+                        match flag {
+                            'P' => config.port = Some(cli::parse_port(&value)?),
+                            'i' => config.identity_file = Some(PathBuf::from(value)),
+                            _ => cli::parse_option(&mut config, &value)?,
+                        }
+                    },
+                    _ => return Err(format!("Unknown option -{}", flag)),
+                }
+            }
+        }
+
+        if help {
+            return Ok(Self { sources: Vec::new(), target: Location::Local(PathBuf::new()), options, verbosity, help, config });
+        }
+
+        let Some(target) = paths.pop().filter(|_| !paths.is_empty()) else {
+            return Err("Missing the source or the target".to_string());
+        };
+
+        let sources = paths;
+
+        //Only one side is remote, on a single server
+        let valid = match &target {
+            Location::Local(_) => sources.iter().all(|source| matches!(source, Location::Remote { destination, .. } if Some(destination) == sources[0].destination())),
+            Location::Remote { .. } => sources.iter().all(|source| matches!(source, Location::Local(_))),
+        };
+
+        if !valid {
+            return Err("Either every source or the target must be remote, as user#host:path, on the same server".to_string());
+        }
+
+        Ok(Self { sources, target, options, verbosity, help, config })
+    }
+
+    //The user#host of the copy
+    pub fn destination(&self) -> &str {
+        self.target.destination().or(self.sources[0].destination()).map(String::as_str).unwrap_or_default()
+    }
+}
+
+impl Location {
+
+    /*
+     * A remote path has the user#host before the first colon outside
+     * brackets, as user#[::1]:path, anything else is a local path, which
+     * may start with ./ to have a colon
+     */
+    fn parse(arg: &str) -> Self {
+
+        if let Some((user, rest)) = arg.split_once(REMOTE_SEPARATOR) {
+
+            //The path is after the bracketed host, or the first colon
+            let host_end = match rest.strip_prefix('[') {
+                Some(bracketed) => bracketed.find(']').map(|end| end + 2),
+                None => Some(0),
+            };
+
+            let colon = host_end.and_then(|start| rest[start..].find(PATH_SEPARATOR).map(|colon| start + colon));
+
+            if let Some(colon) = colon.filter(|_| !user.is_empty() && !user.contains('/')) {
+                return Location::Remote {
+                    destination: format!("{}{}{}", user, REMOTE_SEPARATOR, &rest[..colon]),
+                    path: rest[colon + 1..].to_string(),
+                };
+            }
+        }
+
+        Location::Local(PathBuf::from(arg))
+    }
+
+    fn destination(&self) -> Option<&String> {
+        match self {
+            Location::Remote { destination, .. } => Some(destination),
+            Location::Local(_) => None,
+        }
+    }
+}
+
+/*
+ * Copies every source to the target, showing each failure,
+ * returning if all of them were copied
+ */
+pub fn run(client: &mut TransferClient, cli: &CopyCli) -> Result<bool> {
+
+    let mut copied = true;
+
+    let several = cli.sources.len() > 1;
+
+    for source in &cli.sources {
+
+        let result = match (source, &cli.target) {
+            (Location::Local(local), Location::Remote { path, .. }) => upload_source(client, local, path, several, cli.options),
+            (Location::Remote { path, .. }, Location::Local(local)) => download_source(client, remote_or_home(path), local, several, cli.options),
+            _ => unreachable!("CopyCli::parse only accepts a remote side"),
+        };
+
+        if let Err(e) = result {
+            eprintln!("sssh-cp: {}: {}", source_name(source), cli::error_message(&e));
+            copied = false;
+        }
+    }
+
+    Ok(copied)
+}
+
+//Copies a local source into the remote target directory, or as the target
+fn upload_source(client: &mut TransferClient, local: &Path, target: &str, several: bool, options: CopyOptions) -> Result<()> {
+
+    let target = remote_or_home(target);
+
+    let is_directory = client.stat(target).map(|attributes| attributes.is_dir).unwrap_or(false);
+
+    if several && !is_directory {
+        return Err(Error::Static(TARGET_DIRECTORY_ERROR));
+    }
+
+    let remote = match is_directory {
+        true => join_remote(target, &file_name(local)?),
+        false => target.to_string(),
+    };
+
+    upload(client, local, &remote, options)
+}
+
+//Copies a remote source into the local target directory, or as the target
+fn download_source(client: &mut TransferClient, remote: &str, target: &Path, several: bool, options: CopyOptions) -> Result<()> {
+
+    if several && !target.is_dir() {
+        return Err(Error::Static(TARGET_DIRECTORY_ERROR));
+    }
+
+    let local = match target.is_dir() {
+        true => target.join(file_name(Path::new(remote))?),
+        false => target.to_path_buf(),
+    };
+
+    download(client, remote, &local, options)
+}
+
+fn upload(client: &mut TransferClient, local: &Path, remote: &str, options: CopyOptions) -> Result<()> {
+
+    let metadata = fs::metadata(local)?;
+
+    if metadata.is_dir() {
+
+        if !options.recursive {
+            return Err(Error::Static(DIRECTORY_ERROR));
+        }
+
+        //An existing directory is merged, as cp -r
+        if !client.stat(remote).is_ok_and(|attributes| attributes.is_dir) {
+            client.make_dir(remote)?;
+        }
+
+        for entry in fs::read_dir(local)? {
+
+            let entry = entry?;
+            let path = entry.path();
+
+            if entry.file_type()?.is_symlink() && path.is_dir() {
+                skip_directory_link(&path.to_string_lossy());
+                continue;
+            }
+
+            let name = entry.file_name().to_string_lossy().to_string();
+            upload(client, &path, &join_remote(remote, &name), options)?;
+        }
+    } else {
+        let mut progress = Progress::new(&local.to_string_lossy(), options.quiet);
+        let result = client.upload(local, remote, false, &mut progress);
+        progress.finish();
+        result?;
+    }
+
+    //The times after the content, since writing it changes them
+    if options.preserve {
+        client.set_permissions(remote, metadata.mode() & PERMISSIONS_MASK)?;
+        client.set_times(remote, metadata.atime(), metadata.mtime())?;
+    }
+
+    Ok(())
+}
+
+fn download(client: &mut TransferClient, remote: &str, local: &Path, options: CopyOptions) -> Result<()> {
+
+    let attributes = client.stat(remote)?;
+
+    if attributes.is_dir {
+
+        if !options.recursive {
+            return Err(Error::Static(DIRECTORY_ERROR));
+        }
+
+        if !local.is_dir() {
+            fs::create_dir(local)?;
+        }
+
+        for entry in client.read_dir(remote)? {
+
+            //The name is joined to the local directory, so it must not leave it
+            if !protocol::is_valid_file_name(&entry.name) {
+                return Err(Error::Static(INVALID_ENTRY_NAME_ERROR));
+            }
+
+            let path = join_remote(remote, &entry.name);
+
+            //The entries have the attributes of the link target, so only a directory may be a link to one
+            if entry.attributes.is_dir && client.link_stat(&path)?.is_symlink() {
+                skip_directory_link(&path);
+                continue;
+            }
+
+            download(client, &path, &local.join(&entry.name), options)?;
+        }
+    } else {
+        let mut progress = Progress::new(remote, options.quiet);
+        let result = client.download(remote, local, false, &mut progress);
+        progress.finish();
+        result?;
+    }
+
+    if options.preserve {
+        fs::set_permissions(local, fs::Permissions::from_mode(attributes.mode & PERMISSIONS_MASK))?;
+        file_sys::set_file_times(local, attributes.accessed, attributes.modified)?;
+    }
+
+    Ok(())
+}
+
+//A link to a directory may be to one of his parents, so it is never followed inside a copied directory
+fn skip_directory_link(path: &str) {
+    eprintln!("sssh-cp: {}: skipping the symbolic link to a directory", path);
+}
+
+//An empty remote path, as user#host:, is the user home
+fn remote_or_home(path: &str) -> &str {
+    if path.is_empty() { "." } else { path }
+}
+
+fn join_remote(directory: &str, name: &str) -> String {
+    format!("{}/{}", directory.trim_end_matches('/'), name)
+}
+
+fn file_name(path: &Path) -> Result<String> {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or(Error::Static(NO_FILE_NAME_ERROR))
+}
+
+fn source_name(source: &Location) -> String {
+    match source {
+        Location::Local(path) => path.display().to_string(),
+        Location::Remote { destination, path } => format!("{}{}{}", destination, PATH_SEPARATOR, path),
+    }
+}
@@ -0,0 +1,396 @@
+/*
+ * ##############################################
+ * File responsible for the interactive transfer
+ * client, sssh-ftp:
+ *
+ * sssh-ftp [options] user#host
+ *
+ * With the options of sssh, -Bhv, -i, -o and -p,
+ * then it reads a command by line:
+ *
+ * ls [path] - Lists the remote directory
+ * cd path - Changes the remote directory
+ * pwd - Shows the remote directory
+ * lcd path - Changes the local directory
+ * lpwd - Shows the local directory
+ * get [-a] remote [local] - Downloads the file
+ * put [-a] local [remote] - Uploads the file
+ * mkdir path, rmdir path, rm path - As the shell ones
+ * rename from to - Renames the remote file
+ * help - Shows the commands
+ * exit, quit - Ends the session
+ *
+ * Where -a resumes an interrupted transfer, from
+ * the size of the destination, and an argument
+ * with spaces is between double quotes.
+ *
+ * Without a terminal, as from a script, the
+ * commands stop at the first failure.
+ * ##############################################
+ */
+
+use std::env;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
+use crate::cli;
+use crate::error::{Error, Result};
+use crate::session::config::SessionConfig;
+use crate::transfer::client::TransferClient;
+use crate::transfer::progress::Progress;
+use crate::transfer::protocol::{self, FileAttributes};
+
+pub const USAGE: &str = "Usage: sssh-ftp [-Bhv] [-i identity_file] [-o option=value] [-p port] user#host";
+
+const HELP: &str = "Commands:
+ls [path]                 List the remote directory
+cd path                   Change the remote directory
+pwd                       Show the remote directory
+lcd path                  Change the local directory
+lpwd                      Show the local directory
+get [-a] remote [local]   Download a file, -a resumes it
+put [-a] local [remote]   Upload a file, -a resumes it
+mkdir path                Create a remote directory
+rmdir path                Remove a remote directory
+rm path                   Remove a remote file
+rename from to            Rename a remote file
+help                      Show this help
+exit, quit                End the session";
+
+const PROMPT: &str = "sssh-ftp> ";
+const RESUME_FLAG: &str = "-a";
+const MAX_VERBOSITY: u8 = 3;
+
+const NOT_A_DIRECTORY_ERROR: &str = "Not a directory";
+const NO_FILE_NAME_ERROR: &str = "The path has no file name";
+const QUOTE_ERROR: &str = "Missing the closing quote";
+
+//The command line of sssh-ftp
+pub struct FtpCli {
+    pub destination: String,
+    pub verbosity: u8,
+    pub help: bool,
+    pub config: SessionConfig,
+}
+
+//Why a line could not run, the usage of the command or the failure
+enum CommandError {
+    Usage(&'static str),
+    Failed(Error),
+}
+
+impl From<Error> for CommandError {
+    fn from(e: Error) -> Self {
+        CommandError::Failed(e)
+    }
+}
+
+impl From<io::Error> for CommandError {
+    fn from(e: io::Error) -> Self {
+        CommandError::Failed(Error::Io(e))
+    }
+}
+
+impl FtpCli {
+
+    //Parses the arguments, without the program name, returning an usage error message
+    pub fn parse(args: &[String]) -> std::result::Result<Self, String> {
+
+        let mut config = SessionConfig::default();
+        let mut verbosity = 0;
+        let mut help = false;
+        let mut destination = None;
+        let mut index = 0;
+
+        while index < args.len() {
+
+            let arg = &args[index];
+            index += 1;
+
+            if !arg.starts_with('-') || arg.len() == 1 {
+
+                if destination.is_some() {
+                    return Err(format!("Unexpected argument {}", arg));
+                }
+
+                destination = Some(arg.clone());
+                continue;
+            }
+
+            let mut flags = arg[1..].chars();
+
+            while let Some(flag) = flags.next() {
+                match flag {
+                    'v' => verbosity = (verbosity + 1).min(MAX_VERBOSITY),
+                    'B' => config.batch_mode = true,
+                    'h' => help = true,
+                    'p' | 'i' | 'o' => {
+
+                        let attached: String = flags.by_ref().collect();
+
+                        let value = if !attached.is_empty() {
+                            attached
+                        } else if index < args.len() {
+                            index += 1;
+                            args[index - 1].clone()
+                        } else {
+                            return Err(format!("Option -{} requires a value", flag));
+                        };
+
+                        match flag {
+                            'p' => config.port = Some(cli::parse_port(&value)?),
+                            'i' => config.identity_file = Some(PathBuf::from(value)),
+                            _ => cli::parse_option(&mut config, &value)?,
+                        }
+                    },
+                    _ => return Err(format!("Unknown option -{}", flag)),
+                }
+            }
+        }
+
+        match (help, destination) {
+            (true, _) => Ok(Self { destination: String::new(), verbosity, help, config }),
+            (false, Some(destination)) => Ok(Self { destination, verbosity, help, config }),
+            (false, None) => Err("Missing the destination user#host".to_string()),
+        }
+    }
+}
+
+/*
+ * Runs the commands of stdin until exit or the end of stdin, where
+ * without a terminal the first failure stops them, and is returned
+ */
+pub fn run(client: &mut TransferClient) -> Result<()> {
+
+    let interactive = io::stdin().is_terminal();
+
+    let mut remote_directory = client.real_path(".")?;
+    let mut lines = io::stdin().lock().lines();
+
+    loop {
+
+        if interactive {
+            print!("{}", PROMPT);
+            io::stdout().flush()?;
+        }
+
+        let Some(line) = lines.next() else {
+            return Ok(());
+        };
+
+        let line = line?;
+
+        let args = match split_arguments(&line) {
+            Ok(args) => args,
+            Err(e) => {
+                eprintln!("sssh-ftp: {}", e);
+                continue;
+            },
+        };
+
+        let Some((command, args)) = args.split_first() else {
+            continue;
+        };
+
+        if matches!(command.as_str(), "exit" | "quit" | "bye") {
+            return Ok(());
+        }
+
+        match run_command(client, &mut remote_directory, command, args) {
+            Ok(()) => {},
+            Err(CommandError::Usage(usage)) => eprintln!("Usage: {}", usage),
+            Err(CommandError::Failed(e)) if !interactive => return Err(e),
+            Err(CommandError::Failed(e)) => eprintln!("sssh-ftp: {}", cli::error_message(&e)),
+        }
+    }
+}
+
+fn run_command(client: &mut TransferClient, remote_directory: &mut String, command: &str, args: &[String]) -> std::result::Result<(), CommandError> {
+
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    match (command, args.as_slice()) {
+        ("ls", []) => list(client, remote_directory),
+        ("ls", [path]) => list(client, &remote_path(remote_directory, path)),
+        ("ls", _) => Err(CommandError::Usage("ls [path]")),
+        ("cd", [path]) => {
+
+            let path = client.real_path(&remote_path(remote_directory, path))?;
+
+            if !client.stat(&path)?.is_dir {
+                return Err(CommandError::Failed(Error::Transfer(NOT_A_DIRECTORY_ERROR.to_string())));
+            }
+
+            *remote_directory = path;
+            Ok(())
+        },
+        ("cd", _) => Err(CommandError::Usage("cd path")),
+        ("pwd", []) => {
+            println!("Remote directory: {}", remote_directory);
+            Ok(())
+        },
+        ("lcd", [path]) => Ok(env::set_current_dir(path)?),
+        ("lcd", _) => Err(CommandError::Usage("lcd path")),
+        ("lpwd", []) => {
+            println!("Local directory: {}", env::current_dir()?.display());
+            Ok(())
+        },
+        ("get", _) => {
+
+            let (resume, paths) = resume_flag(&args);
+
+            let (remote, local) = match paths {
+                [remote] => (*remote, file_name(remote)?),
+                [remote, local] => (*remote, PathBuf::from(local)),
+                _ => return Err(CommandError::Usage("get [-a] remote [local]")),
+            };
+
+            let remote = remote_path(remote_directory, remote);
+
+            println!("Fetching {} to {}", remote, local.display());
+
+            let mut progress = Progress::new(&remote, false);
+            let size = client.download(&remote, &local, resume, &mut progress);
+            progress.finish();
+
+            println!("{} bytes received", size?);
+            Ok(())
+        },
+        ("put", _) => {
+
+            let (resume, paths) = resume_flag(&args);
+
+            let (local, remote) = match paths {
+                [local] => (Path::new(local), file_name(local)?.to_string_lossy().to_string()),
+                [local, remote] => (Path::new(local), remote.to_string()),
+                _ => return Err(CommandError::Usage("put [-a] local [remote]")),
+            };
+
+            let remote = remote_path(remote_directory, &remote);
+
+            println!("Uploading {} to {}", local.display(), remote);
+
+            let mut progress = Progress::new(&local.to_string_lossy(), false);
+            let size = client.upload(local, &remote, resume, &mut progress);
+            progress.finish();
+
+            println!("{} bytes sent", size?);
+            Ok(())
+        },
+        ("mkdir", [path]) => Ok(client.make_dir(&remote_path(remote_directory, path))?),
+        ("mkdir", _) => Err(CommandError::Usage("mkdir path")),
+        ("rmdir", [path]) => Ok(client.remove_dir(&remote_path(remote_directory, path))?),
+        ("rmdir", _) => Err(CommandError::Usage("rmdir path")),
+        ("rm", [path]) => Ok(client.remove(&remote_path(remote_directory, path))?),
+        ("rm", _) => Err(CommandError::Usage("rm path")),
+        ("rename", [from, to]) => Ok(client.rename(&remote_path(remote_directory, from), &remote_path(remote_directory, to))?),
+        ("rename", _) => Err(CommandError::Usage("rename from to")),
+        ("help" | "?", _) => {
+            println!("{}", HELP);
+            Ok(())
+        },
+        _ => {
+            eprintln!("Unknown command {}, see help", command);
+            Ok(())
+        },
+    }
+}
+
+//Lists a directory, as ls -l, or a single file
+fn list(client: &mut TransferClient, path: &str) -> std::result::Result<(), CommandError> {
+
+    let attributes = client.stat(path)?;
+
+    if !attributes.is_dir {
+        println!("{}", list_line(&attributes, path));
+        return Ok(());
+    }
+
+    for entry in client.read_dir(path)? {
+        println!("{}", list_line(&entry.attributes, &entry.name));
+    }
+
+    Ok(())
+}
+
+fn list_line(attributes: &FileAttributes, name: &str) -> String {
+    format!("{} {:>12} {}", mode_string(attributes), attributes.size, name)
+}
+
+//The type and permissions, as drwxr-xr-x
+fn mode_string(attributes: &FileAttributes) -> String {
+
+    const PERMISSIONS: [char; 3] = ['r', 'w', 'x'];
+
+    let mut mode = String::with_capacity(10);
+    mode.push(if attributes.is_dir { 'd' } else { '-' });
+
+    for bit in (0..9).rev() {
+        mode.push(if attributes.mode & (1 << bit) != 0 { PERMISSIONS[2 - bit % 3] } else { '-' });
+    }
+
+    mode
+}
+
+//A remote path, where a relative one is from the remote directory
+fn remote_path(remote_directory: &str, path: &str) -> String {
+    if path.starts_with('/') {
+        path.to_string()
+    } else {
+        format!("{}/{}", remote_directory.trim_end_matches('/'), path)
+    }
+}
+
+//The default destination of a transfer, the source file name on the current directory
+//The name a file is saved as, when not given, which must stay in the current directory
+fn file_name(path: &str) -> std::result::Result<PathBuf, CommandError> {
+    Path::new(path).file_name()
+        .filter(|name| protocol::is_valid_file_name(&name.to_string_lossy()))
+        .map(PathBuf::from)
+        .ok_or(CommandError::Failed(Error::Static(NO_FILE_NAME_ERROR)))
+}
+
+fn resume_flag<'a, 'b>(args: &'a [&'b str]) -> (bool, &'a [&'b str]) {
+    match args.split_first() {
+        Some((&RESUME_FLAG, paths)) => (true, paths),
+        _ => (false, args),
+    }
+}
+
+//Splits the line by whitespace, where an argument between double quotes may have spaces
+fn split_arguments(line: &str) -> std::result::Result<Vec<String>, &'static str> {
+
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_argument = false;
+    let mut in_quotes = false;
+
+    for c in line.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                in_argument = true;
+            },
+            c if c.is_whitespace() && !in_quotes => {
+                if in_argument {
+                    args.push(std::mem::take(&mut current));
+                    in_argument = false;
+                }
+            },
+            c => {
+                current.push(c);
+                in_argument = true;
+            },
+        }
+    }
+
+    if in_quotes {
+        return Err(QUOTE_ERROR);
+    }
+
+    if in_argument {
+        args.push(current);
+    }
+
+    Ok(args)
+}
@@ -0,0 +1,21 @@
+/*
+ *#########################################################
+ * File responsible for the file transfers, over
+ * the transfer subsystem of an authenticated session:
+ *
+ * protocol - The requests and responses, as frames
+ * server - The sssh-transfer program, which the server
+ *   starts as the user
+ * client - The requests and whole file transfers
+ * progress - The progress shown during a transfer
+ * ftp - The interactive sssh-ftp client
+ * copy - The one-shot sssh-cp client
+ *#########################################################
+ */
+
+pub mod protocol;
+pub mod server;
+pub mod client;
+pub mod progress;
+pub mod ftp;
+pub mod copy;
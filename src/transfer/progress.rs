@@ -0,0 +1,99 @@
+/*
+ * ##############################################
+ * File responsible for the transfer progress,
+ * shown on stderr only if it is a terminal, as:
+ *
+ * name    45%   2.1MB   1.3MB/s
+ *
+ * Redrawn on the same line, at most every
+ * REDRAW_INTERVAL, and ended with a newline.
+ * ##############################################
+ */
+
+use std::io::{self, IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+const REDRAW_INTERVAL: Duration = Duration::from_millis(200);
+const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+const UNIT_SIZE: f64 = 1024.0;
+
+pub struct Progress {
+    name: String,
+    enabled: bool,
+    start: Instant,
+    last_draw: Option<Instant>,
+    done: u64,
+    total: u64,
+}
+
+impl Progress {
+
+    //A progress of the file, hidden if quiet or stderr is not a terminal
+    pub fn new(name: &str, quiet: bool) -> Self {
+        Self {
+            name: name.to_string(),
+            enabled: !quiet && io::stderr().is_terminal(),
+            start: Instant::now(),
+            last_draw: None,
+            done: 0,
+            total: 0,
+        }
+    }
+
+    //Updates the bytes done of the total, redrawing if it is time
+    pub fn update(&mut self, done: u64, total: u64) {
+
+        self.done = done;
+        self.total = total;
+
+        if self.last_draw.is_none_or(|last| last.elapsed() >= REDRAW_INTERVAL) {
+            self.draw();
+        }
+    }
+
+    //Draws the last state and ends the line
+    pub fn finish(&mut self) {
+
+        if self.enabled {
+            self.draw();
+            eprintln!();
+        }
+    }
+
+    fn draw(&mut self) {
+
+        if !self.enabled {
+            return;
+        }
+
+        self.last_draw = Some(Instant::now());
+
+        let percent = match self.total {
+            0 => 100,
+            total => self.done.saturating_mul(100) / total,
+        };
+
+        let seconds = self.start.elapsed().as_secs_f64();
+        let rate = if seconds > 0.0 { self.done as f64 / seconds } else { 0.0 };
+
+        let mut stderr = io::stderr();
+        let _ = write!(stderr, "\r{:<40} {:>3}% {:>9} {:>9}/s", self.name, percent, human_size(self.done as f64), human_size(rate));
+        let _ = stderr.flush();
+    }
+}
+
+//The size with his unit, as 1.3MB
+fn human_size(mut size: f64) -> String {
+
+    let mut unit = 0;
+
+    while size >= UNIT_SIZE && unit < UNITS.len() - 1 {
+        size /= UNIT_SIZE;
+        unit += 1;
+    }
+
+    match unit {
+        0 => format!("{}{}", size as u64, UNITS[unit]),
+        _ => format!("{:.1}{}", size, UNITS[unit]),
+    }
+}
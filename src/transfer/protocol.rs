@@ -0,0 +1,178 @@
+/*
+ * ##############################################
+ * File responsible for the transfer protocol,
+ * the requests of the client and the responses
+ * of the transfer server, where each one is a
+ * frame on the subsystem channel:
+ *
+ * length (u32, big endian) || bincode content
+ *
+ * The server answers each request in order:
+ *
+ * Open | OpenDir -> Handle | Error
+ * Read -> Data | Error, where an empty Data is the end of the file
+ * ReadDirEntries -> Entries | Error, where empty Entries are the end of the directory
+ * Write | Close | MakeDir | Remove | RemoveDir | Rename | SetPermissions | SetTimes -> Ok | Error
+ * Stat | LinkStat -> Attributes | Error, where LinkStat does not follow a symbolic link
+ * ReadDir -> Entries | Error, all in one frame, as older clients ask
+ * RealPath -> Path | Error
+ *
+ * A directory is read by batches of a handle of OpenDir,
+ * so any directory fits the frames.
+ *
+ * The paths are of the server, a relative one
+ * is from the user home. The client rejects an
+ * entry name which is not a single file name.
+ * ##############################################
+ */
+
+use std::io::{Read, Write};
+
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+
+use crate::error::{Error, Result};
+
+pub const TRANSFER_SUBSYSTEM: &str = "transfer";
+
+//The most bytes a Read returns, and a Write should send
+pub const MAX_DATA_SIZE: u32 = 32 * 1024;
+
+//The most entries a ReadDirEntries returns, as a name has up to 255 bytes they fit a frame
+pub const MAX_DIR_ENTRIES: usize = 256;
+
+//Far above a request with data, so a frame never needs more memory
+const MAX_FRAME_SIZE: u32 = 256 * 1024;
+
+const FRAME_SIZE_ERROR: &str = "Received a transfer frame above the maximum size";
+const FRAME_TOO_LARGE_ERROR: &str = "The transfer frame is above the maximum size";
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum OpenMode {
+    Read,
+    //Creates the file if it does not exist, with the permissions
+    Write { truncate: bool, permissions: u32 },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum Request {
+    Open { path: String, mode: OpenMode },
+    Read { handle: u32, offset: u64, length: u32 },
+    Write { handle: u32, offset: u64, data: Vec<u8> },
+    Close { handle: u32 },
+    Stat { path: String },
+    ReadDir { path: String },
+    MakeDir { path: String },
+    Remove { path: String },
+    RemoveDir { path: String },
+    Rename { from: String, to: String },
+    RealPath { path: String },
+    SetPermissions { path: String, permissions: u32 },
+    //Seconds since the epoch
+    SetTimes { path: String, accessed: i64, modified: i64 },
+    LinkStat { path: String },
+    OpenDir { path: String },
+    ReadDirEntries { handle: u32 },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum Response {
+    Ok,
+    Handle(u32),
+    Data(Vec<u8>),
+    Attributes(FileAttributes),
+    Entries(Vec<DirEntry>),
+    Path(String),
+    Error(String),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FileAttributes {
+    pub size: u64,
+    //The type and permissions, as st_mode
+    pub mode: u32,
+    //Seconds since the epoch
+    pub accessed: i64,
+    pub modified: i64,
+    pub is_dir: bool,
+}
+
+impl FileAttributes {
+
+    //Only of LinkStat, the other attributes are of the file the link points to
+    pub fn is_symlink(&self) -> bool {
+        self.mode & libc::S_IFMT == libc::S_IFLNK
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DirEntry {
+    pub name: String,
+    pub attributes: FileAttributes,
+}
+
+/*
+ * A name of a single file, without a directory, as the ones the
+ * client joins to a local directory, so a server sending "..",
+ * or a name with a '/', cannot write outside of it
+ */
+pub fn is_valid_file_name(name: &str) -> bool {
+    !name.is_empty() && name != "." && name != ".." && !name.contains('/')
+}
+
+pub fn write_frame<W: Write, T: Serialize>(writer: &mut W, content: &T) -> Result<()> {
+
+    let frame = encode_frame(content)?;
+
+    writer.write_all(&frame)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+//The frame of the content, failing above the maximum size, as the other side would reject it
+pub fn encode_frame<T: Serialize>(content: &T) -> Result<Vec<u8>> {
+
+    let bytes = bincode_options().serialize(content)?;
+
+    if bytes.len() > MAX_FRAME_SIZE as usize {
+        return Err(Error::Static(FRAME_TOO_LARGE_ERROR));
+    }
+
+    let mut frame = Vec::with_capacity(4 + bytes.len());
+    frame.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+    frame.extend_from_slice(&bytes);
+
+    Ok(frame)
+}
+
+//Reads the next frame, None if the stream closed before it
+pub fn read_frame<R: Read, T: DeserializeOwned>(reader: &mut R) -> Result<Option<T>> {
+
+    let mut length = [0u8; 4];
+
+    match reader.read_exact(&mut length) {
+        Ok(()) => {},
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(Error::Io(e)),
+    }
+
+    let length = u32::from_be_bytes(length);
+
+    if length > MAX_FRAME_SIZE {
+        return Err(Error::Static(FRAME_SIZE_ERROR));
+    }
+
+    let mut content = vec![0u8; length as usize];
+    reader.read_exact(&mut content)?;
+
+    Ok(Some(bincode_options().deserialize(&content)?))
+}
+
+//The same encoding of the messages, limited to a frame
+fn bincode_options() -> impl Options {
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .with_limit(MAX_FRAME_SIZE as u64)
+}
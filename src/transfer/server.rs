@@ -0,0 +1,206 @@
+/*
+ * ##############################################
+ * File responsible for the transfer server, the
+ * subsystem program which the sssh server starts
+ * as the user, on his home, so every file is
+ * accessed with his permissions.
+ *
+ * It reads the requests on stdin and writes the
+ * responses on stdout, until stdin closes, as
+ * the sssh server relays them on the channel.
+ *
+ * The open files and directories are kept by
+ * their handle, the handles left open are closed
+ * at the end. A response which does not fit a
+ * frame is sent as an error.
+ * ##############################################
+ */
+
+use std::collections::HashMap;
+use std::fs::{self, File, Metadata, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::os::unix::fs::{FileExt, MetadataExt, OpenOptionsExt, PermissionsExt};
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::file_sys;
+use crate::transfer::protocol::{self, DirEntry, FileAttributes, OpenMode, Request, Response, MAX_DATA_SIZE, MAX_DIR_ENTRIES};
+
+const UNKNOWN_HANDLE_ERROR: &str = "Unknown file handle";
+const INVALID_NAME_ERROR: &str = "The file name is not valid UTF-8";
+
+//The open files and directories of the client, where a directory has the entries not read yet
+struct Handles {
+    files: HashMap<u32, File>,
+    dirs: HashMap<u32, std::vec::IntoIter<DirEntry>>,
+    next: u32,
+}
+
+//Answers every request of stdin on stdout, until the client closes it
+pub fn serve() -> Result<()> {
+
+    let mut reader = BufReader::new(io::stdin().lock());
+    let mut writer = BufWriter::new(io::stdout().lock());
+
+    let mut handles = Handles { files: HashMap::new(), dirs: HashMap::new(), next: 0 };
+
+    while let Some(request) = protocol::read_frame::<_, Request>(&mut reader)? {
+
+        let response = handles.answer(request).unwrap_or_else(|e| Response::Error(e.to_string()));
+
+        //A response which cannot be sent fails only its request
+        let frame = protocol::encode_frame(&response).or_else(|e| protocol::encode_frame(&Response::Error(e.to_string())))?;
+
+        writer.write_all(&frame)?;
+        writer.flush()?;
+    }
+
+    Ok(())
+}
+
+impl Handles {
+
+    fn answer(&mut self, request: Request) -> io::Result<Response> {
+
+        let response = match request {
+            Request::Open { path, mode } => {
+
+                let file = match mode {
+                    OpenMode::Read => File::open(path)?,
+                    OpenMode::Write { truncate, permissions } => OpenOptions::new()
+                        .write(true)
+                        .create(true)
+                        .truncate(truncate)
+                        .mode(permissions)
+                        .open(path)?,
+                };
+
+                let handle = self.new_handle();
+                self.files.insert(handle, file);
+
+                Response::Handle(handle)
+            },
+            Request::OpenDir { path } => {
+
+                let entries = read_dir(&path)?;
+
+                let handle = self.new_handle();
+                self.dirs.insert(handle, entries.into_iter());
+
+                Response::Handle(handle)
+            },
+            Request::ReadDirEntries { handle } => {
+                let entries = self.dirs.get_mut(&handle).ok_or_else(unknown_handle)?;
+                Response::Entries(entries.take(MAX_DIR_ENTRIES).collect())
+            },
+            Request::Read { handle, offset, length } => {
+
+                let mut data = vec![0u8; length.min(MAX_DATA_SIZE) as usize];
+                let size = self.get(handle)?.read_at(&mut data, offset)?;
+                data.truncate(size);
+
+                Response::Data(data)
+            },
+            Request::Write { handle, offset, data } => {
+                self.get(handle)?.write_all_at(&data, offset)?;
+                Response::Ok
+            },
+            Request::Close { handle } => {
+                if self.files.remove(&handle).is_none() {
+                    self.dirs.remove(&handle).ok_or_else(unknown_handle)?;
+                }
+                Response::Ok
+            },
+            Request::Stat { path } => Response::Attributes(attributes(&fs::metadata(path)?)),
+            Request::LinkStat { path } => Response::Attributes(attributes(&fs::symlink_metadata(path)?)),
+            Request::ReadDir { path } => Response::Entries(read_dir(&path)?),
+            Request::MakeDir { path } => {
+                fs::create_dir(path)?;
+                Response::Ok
+            },
+            Request::Remove { path } => {
+                fs::remove_file(path)?;
+                Response::Ok
+            },
+            Request::RemoveDir { path } => {
+                fs::remove_dir(path)?;
+                Response::Ok
+            },
+            Request::Rename { from, to } => {
+                fs::rename(from, to)?;
+                Response::Ok
+            },
+            Request::RealPath { path } => {
+                let path = fs::canonicalize(path)?;
+                Response::Path(path.to_str().ok_or_else(invalid_name)?.to_string())
+            },
+            Request::SetPermissions { path, permissions } => {
+                fs::set_permissions(path, fs::Permissions::from_mode(permissions))?;
+                Response::Ok
+            },
+            Request::SetTimes { path, accessed, modified } => {
+                file_sys::set_file_times(Path::new(&path), accessed, modified).map_err(|e| match e {
+                    Error::Io(e) => e,
+                    e => io::Error::other(e.to_string()),
+                })?;
+                Response::Ok
+            },
+        };
+
+        Ok(response)
+    }
+
+    fn get(&self, handle: u32) -> io::Result<&File> {
+        self.files.get(&handle).ok_or_else(unknown_handle)
+    }
+
+    fn new_handle(&mut self) -> u32 {
+        let handle = self.next;
+        self.next = self.next.wrapping_add(1);
+        handle
+    }
+}
+
+//The entries of the directory, sorted by name, where a broken link has his own attributes
+fn read_dir(path: &str) -> io::Result<Vec<DirEntry>> {
+
+    let mut entries = Vec::new();
+
+    for entry in fs::read_dir(path)? {
+
+        let entry = entry?;
+
+        let Ok(name) = entry.file_name().into_string() else {
+            continue;
+        };
+
+        let metadata = match fs::metadata(entry.path()) {
+            Ok(metadata) => metadata,
+            Err(_) => entry.metadata()?,
+        };
+
+        entries.push(DirEntry { name, attributes: attributes(&metadata) });
+    }
+
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(entries)
+}
+
+fn attributes(metadata: &Metadata) -> FileAttributes {
+    FileAttributes {
+        size: metadata.len(),
+        mode: metadata.mode(),
+        accessed: metadata.atime(),
+        modified: metadata.mtime(),
+        is_dir: metadata.is_dir(),
+    }
+}
+
+fn unknown_handle() -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, UNKNOWN_HANDLE_ERROR)
+}
+
+fn invalid_name() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, INVALID_NAME_ERROR)
+}
@@ -1,9 +1,18 @@
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::thread;
+use std::time::Duration;
 /*
  *#############################################
  * File with functions that may not specific
  *#############################################
  */
 
+//How many debug messages are shown, set by -v
+static VERBOSITY: AtomicU8 = AtomicU8::new(0);
+
+//The wait after a failed accept, as out of descriptors, which would fail again at once
+const ACCEPT_ERROR_DELAY: Duration = Duration::from_millis(100);
 
 /*
  * Asks for an input from a user,
@@ -18,10 +27,15 @@ pub fn ask_confirmation(prompt : &str) -> bool {
     loop{
         println!("{}",prompt);
         print!("Proceed? (y/n): ");
+        let _ = std::io::stdout().flush();
         
         let mut input = String::new();
 
-        std::io::stdin().read_line(&mut input).unwrap();
+        //Without an input, as a closed stdin, it is a no
+        match std::io::stdin().read_line(&mut input) {
+            Ok(0) | Err(_) => return false,
+            Ok(_) => {},
+        }
 
         input = input.to_lowercase().trim().to_string();
 
@@ -34,4 +48,37 @@ pub fn ask_confirmation(prompt : &str) -> bool {
     } 
 }
 
+pub fn set_verbosity(level : u8) {
+    VERBOSITY.store(level, Ordering::Relaxed);
+}
 
+//Prints a debug message on stderr, if the verbosity is at least the level
+pub fn debug(level : u8, message : &str) {
+    if VERBOSITY.load(Ordering::Relaxed) >= level {
+        eprintln!("debug{}: {}", level, message);
+    }
+}
+
+/*
+ * Handles each connection of a listener, as of his incoming(), until
+ * is_closed tells it was closed, which wakes up the accept, where a
+ * failed accept, as out of descriptors, does not end the loop, but
+ * waits a bit, so it does not spin until a descriptor is free
+ */
+pub fn accept_connections<S>(incoming: impl Iterator<Item = io::Result<S>>, is_closed: impl Fn() -> bool, on_error: impl Fn(&io::Error), mut handle: impl FnMut(S)) {
+
+    for stream in incoming {
+
+        if is_closed() {
+            break;
+        }
+
+        match stream {
+            Ok(stream) => handle(stream),
+            Err(e) => {
+                on_error(&e);
+                thread::sleep(ACCEPT_ERROR_DELAY);
+            },
+        }
+    }
+}